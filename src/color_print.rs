@@ -24,7 +24,7 @@ macro_rules! cprint {
         let buf_writer = termcolor::BufferWriter::stdout(termcolor::ColorChoice::Auto);
         let mut buf = buf_writer.buffer();
 
-        crate::__cprint_raw!($color, buf_writer, &mut buf, write!(buf, $label));
+        $crate::__cprint_raw!($color, buf_writer, &mut buf, write!(buf, $label));
         std::io::stdout().flush().ok();
     }};
 
@@ -35,7 +35,7 @@ macro_rules! cprint {
         let buf_writer = termcolor::BufferWriter::stdout(termcolor::ColorChoice::Auto);
         let mut buf = buf_writer.buffer();
 
-        crate::__cprint_raw!($color, buf_writer, &mut buf, write!(buf, $label, $($args), +));
+        $crate::__cprint_raw!($color, buf_writer, &mut buf, write!(buf, $label, $($args), +));
         std::io::stdout().flush().ok();
     }};
 }
@@ -65,7 +65,7 @@ macro_rules! cprintln {
         let buf_writer = termcolor::BufferWriter::stdout(termcolor::ColorChoice::Auto);
         let mut buf = buf_writer.buffer();
 
-        crate::__cprint_raw!($color, buf_writer, &mut buf, writeln!(buf, $label));
+        $crate::__cprint_raw!($color, buf_writer, &mut buf, writeln!(buf, $label));
     }};
 
     ($color:expr, $label:literal, $($args:expr), +) => {{
@@ -73,7 +73,7 @@ macro_rules! cprintln {
         let buf_writer = termcolor::BufferWriter::stdout(termcolor::ColorChoice::Auto);
         let mut buf = buf_writer.buffer();
 
-        crate::__cprint_raw!($color, buf_writer, &mut buf, writeln!(buf, $label, $($args), +));
+        $crate::__cprint_raw!($color, buf_writer, &mut buf, writeln!(buf, $label, $($args), +));
     }};
 }
 
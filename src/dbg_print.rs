@@ -26,7 +26,7 @@ macro_rules! __wslcmd_dbg {
             #[cfg(debug_assertions)]
             {
                 // print header
-                crate::__wslcmd_dbg_header!($label);
+                $crate::__wslcmd_dbg_header!($label);
             }
 
             // for release mode
@@ -47,9 +47,9 @@ macro_rules! __wslcmd_dbg {
             #[cfg(debug_assertions)]
             {
                 // print header
-                crate::__wslcmd_dbg_header!($label);
+                $crate::__wslcmd_dbg_header!($label);
                 // print body
-                crate::__wslcmd_dbg_body!($($args),+)
+                $crate::__wslcmd_dbg_body!($($args),+)
             }
 
             // for release mode
@@ -72,7 +72,7 @@ macro_rules! __wslcmd_dbg {
             #[cfg(debug_assertions)]
             {
                 // print body
-                crate::__wslcmd_dbg_body!($($args),+)
+                $crate::__wslcmd_dbg_body!($($args),+)
             }
 
             // for release mode
@@ -1,10 +1,31 @@
-use super::libwslcmd::{WCPath, WslCmd};
+use super::libwslcmd::{strip_prefix, LinkMeta, WCPath, WslCmd, WslCmdManifest, CMDNAME_DELIM};
+use std::path::Path;
 
 /// Name of env arg, which prevent argument path conversion if set
 const ENVFLAG_NO_ARGCONV: &str = "WSLCMD_NO_ARGCONV";
 
+/// Find metadata of the executed link, in the manifest inside `linkdir` if given,
+/// or inside the current exe dir if not
+///
+/// Only the manifest of the binary is read, if the current exe resolves to the binary
+/// (symlinks, or run through the binary). Otherwise all manifests in the dir are searched,
+/// as hardlinks and copies cannot be followed to the binary.
+pub fn find_link_meta(args: &[String], linkdir: Option<&Path>) -> Option<LinkMeta> {
+    let linkname = args.first()?.wcpath_basename()?;
+    let exepath = std::env::current_exe().ok()?;
+    let binpath = exepath.wcpath_canonicalize()?;
+    let binname = binpath
+        .wcpath_filename()
+        // the exe itself is the link: hardlink or copy (or the binary itself)
+        .filter(|_| binpath.wcpath_basename() != Some(linkname));
+
+    WslCmdManifest::find_link_meta(&linkdir.or(exepath.wcpath_parent())?, linkname, binname)
+}
+
 /// Convert Windows cmdline to WSL cmdline, then execute converted WSL command
-pub fn execution_mode(args: &[String]) -> Result<(), i32> {
+///
+/// Namespace prefix in `meta` of the link is stripped from the command name
+pub fn execution_mode(args: &[String], meta: Option<&LinkMeta>) -> Result<(), i32> {
     __wslcmd_dbg!("Execution mode - cmdline args", args); // debug msg
 
     // environment files to load with WSL shell before command execute
@@ -15,20 +36,23 @@ pub fn execution_mode(args: &[String]) -> Result<(), i32> {
             .and_then(|pb| {
                 // convert '\' (win-path dir delim) to '/',
                 // then and wrap with wslpath as cur_exe is win-path
-                pb.with_file_name("profile").to_str().and_then(|s| {
-                    Some(format!(
+                pb.with_file_name("profile").to_str().map(|s| {
+                    format!(
                         "$(wslpath '{}')",
                         // escape ' inside quote-str
                         s.replace("'", r"'\''")
-                    ))
+                    )
                 })
             })
             .unwrap_or_default(),
     ];
 
+    // namespace prefix of the link, recorded in the manifest
+    let prefix = meta.and_then(|meta| meta.get_prefix().as_deref());
+
     // execute, and return process exitcode
     args.split_first() // split into cmd + args
-        .and_then(|(cmd, args)| parse_cmd(cmd).map(|t| (t, args)))
+        .and_then(|(cmd, args)| parse_cmd(cmd, prefix).map(|t| (t, args)))
         .ok_or(-1) // Option -> Result
         .and_then(|((cmd, user, dist), args)| {
             // build wslcmd
@@ -53,12 +77,12 @@ pub fn execution_mode(args: &[String]) -> Result<(), i32> {
         })
 }
 
-/// Delimiter of command name, which divides into bg proc mode, wsl command name, wsl user name
-const CMDNAME_DELIM: char = '!';
-
 // parse command name, to get (detached mode, command, user)
 // returns None if error (failed to get basename, command name is empty)
-fn parse_cmd(binname: &String) -> Option<(String, Option<String>, Option<String>)> {
+fn parse_cmd(
+    binname: &String,
+    prefix: Option<&str>,
+) -> Option<(String, Option<String>, Option<String>)> {
     let mut it = {
         binname
             .wcpath_basename()?
@@ -66,8 +90,10 @@ fn parse_cmd(binname: &String) -> Option<(String, Option<String>, Option<String>
             .peekable()
     };
     Some((
-        it.next().map(String::from).filter(|s| !s.is_empty())?, // command, must not empty
-        it.next().map(String::from).filter(|s| !s.is_empty()),  // user
-        it.next().map(String::from).filter(|s| !s.is_empty()),  // distribution
+        it.next()
+            .map(|s| strip_prefix(s, prefix)) // strip namespace prefix of the link
+            .filter(|s| !s.is_empty())?, // command, must not empty
+        it.next().map(String::from).filter(|s| !s.is_empty()), // user
+        it.next().map(String::from).filter(|s| !s.is_empty()), // distribution
     ))
 }
@@ -0,0 +1,78 @@
+use super::{CMDNAME_DELIM, DETACHED_PROC_PREFIX};
+
+///
+/// Strip the namespace prefix of a link from its command name, keeping detached process prefix
+///
+/// Only the prefix which the link was created with (recorded in the manifest) is stripped,
+/// as other links may start with the same letters (Ex: 'which' with prefix 'w').
+///
+/// # Arguments
+///
+/// * `cmdname` - A command name to strip
+/// * `prefix`  - Namespace prefix of the link
+///
+/// # Return
+///
+/// Command name without namespace prefix
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(strip_prefix("w-find", Some("w-")), "find");
+/// assert_eq!(strip_prefix(".w-find", Some("w-")), ".find");
+/// assert_eq!(strip_prefix("which", None), "which");
+/// ```
+///
+#[allow(dead_code)]
+pub fn strip_prefix(cmdname: &str, prefix: Option<&str>) -> String {
+    let (detached, name) = match cmdname.strip_prefix(DETACHED_PROC_PREFIX) {
+        Some(s) => (true, s),
+        None => (false, cmdname),
+    };
+
+    // prefix must leave non-empty command name
+    match prefix.and_then(|prefix| name.strip_prefix(prefix)) {
+        Some(s) if !s.is_empty() && detached => format!("{}{}", DETACHED_PROC_PREFIX, s),
+        Some(s) if !s.is_empty() => s.to_string(),
+        _ => cmdname.to_string(),
+    }
+}
+
+///
+/// Check if given str can be used as a prefix
+///
+/// # Arguments
+///
+/// * `prefix` - A prefix to check
+///
+#[allow(dead_code)]
+pub fn is_valid_prefix(prefix: &str) -> bool {
+    !prefix.is_empty()
+        && !prefix.starts_with(DETACHED_PROC_PREFIX)
+        && !prefix.contains(CMDNAME_DELIM)
+        && !prefix.contains(|c: char| c.is_whitespace() || c == '/' || c == '\\')
+}
+
+#[cfg(test)]
+/// For module test
+mod test {
+    use super::{is_valid_prefix, strip_prefix};
+
+    #[test]
+    fn test_strip_prefix() {
+        assert_eq!(strip_prefix("w-find", Some("w-")), "find");
+        assert_eq!(strip_prefix(".w-find", Some("w-")), ".find");
+        assert_eq!(strip_prefix("w-", Some("w-")), "w-"); // empty cmd is not stripped
+        assert_eq!(strip_prefix("find", Some("w-")), "find");
+        assert_eq!(strip_prefix("which", None), "which"); // only own prefix
+    }
+
+    #[test]
+    fn test_valid_prefix() {
+        assert!(is_valid_prefix("w-"));
+        assert!(!is_valid_prefix(""));
+        assert!(!is_valid_prefix(".w"));
+        assert!(!is_valid_prefix("w!"));
+        assert!(!is_valid_prefix("a/b"));
+    }
+}
@@ -0,0 +1,447 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::fs::File;
+use std::io;
+use std::io::{Error, ErrorKind};
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::{FileKind, LinkFs};
+
+/// Max number of symlinks to follow while resolving a path
+const MAX_SYMLINK_HOPS: usize = 40;
+
+#[derive(Debug, Clone)]
+/// Contents of a node in [`MemFs`]
+enum MemData {
+    File(Vec<u8>),
+    Dir,
+    Symlink(PathBuf),
+}
+
+#[derive(Debug, Clone)]
+/// A file, directory or symlink in [`MemFs`]
+struct MemNode {
+    data: MemData,
+    mtime: SystemTime,
+}
+
+#[derive(Debug, Default)]
+/// State of [`MemFs`]
+struct MemFsState {
+    /// All nodes by absolute path
+    nodes: BTreeMap<PathBuf, MemNode>,
+
+    /// Logical clock, increased on each change
+    clock: u64,
+
+    /// Paths on which all changes fail (to test rollback)
+    denied: HashSet<PathBuf>,
+}
+
+#[derive(Debug, Default)]
+/// In-memory filesystem with files, directories and symlinks, for tests.
+/// Paths must be absolute, and symlinks to directories are not followed inside paths.
+pub struct MemFs {
+    state: RefCell<MemFsState>,
+}
+
+impl MemFs {
+    ///
+    /// Create new empty [`MemFs`], with the root directory
+    ///
+    pub fn new() -> Self {
+        let fs = Self::default();
+        fs.create_dir_all(&root_of(&mem_root())).ok();
+        fs
+    }
+
+    ///
+    /// Make all later changes on the path fail with [`ErrorKind::PermissionDenied`]
+    ///
+    pub fn deny(&self, path: &Path) {
+        normalize(path)
+            .map(|p| self.state.borrow_mut().denied.insert(p))
+            .ok();
+    }
+
+    ///
+    /// Allow changes on the path again, denied by [`deny`](Self::deny)
+    ///
+    pub fn allow(&self, path: &Path) {
+        normalize(path)
+            .map(|p| self.state.borrow_mut().denied.remove(&p))
+            .ok();
+    }
+
+    // resolve path following symlinks, including the last component if follow_last
+    fn resolve(&self, path: &Path, follow_last: bool) -> io::Result<PathBuf> {
+        let state = self.state.borrow();
+        let mut pending: VecDeque<PathBuf> = components_of(&normalize(path)?);
+        let mut resolved = PathBuf::new();
+        let mut hops = 0;
+
+        while let Some(comp) = pending.pop_front() {
+            match comp.components().next() {
+                Some(Component::Prefix(_)) | Some(Component::RootDir) => resolved.push(comp),
+                Some(Component::ParentDir) => {
+                    resolved.pop();
+                }
+                Some(Component::Normal(s)) => {
+                    let candidate = resolved.join(s);
+                    match state.nodes.get(&candidate).map(|node| &node.data) {
+                        Some(MemData::Symlink(target)) if follow_last || !pending.is_empty() => {
+                            hops += 1;
+                            if hops > MAX_SYMLINK_HOPS {
+                                return Err(Error::other("Too many symlinks"));
+                            }
+                            // restart from the target, relative to dir of the symlink
+                            components_of(&resolved.join(target))
+                                .into_iter()
+                                .rev()
+                                .for_each(|c| pending.push_front(c));
+                            resolved = PathBuf::new();
+                        }
+                        Some(_) => resolved = candidate,
+                        None => return Err(Error::new(ErrorKind::NotFound, "No such file")),
+                    }
+                }
+                Some(Component::CurDir) | None => (),
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    // get node at the path, without following symlinks
+    fn node(&self, path: &Path) -> io::Result<MemNode> {
+        let path = normalize(path)?;
+        self.state
+            .borrow()
+            .nodes
+            .get(&path)
+            .cloned()
+            .ok_or(Error::new(ErrorKind::NotFound, "No such file"))
+    }
+
+    // insert a new node. fails if exists, parent dir does not exist, or denied.
+    fn create(&self, path: &Path, data: MemData) -> io::Result<()> {
+        let path = normalize(path)?;
+        let mut state = self.state.borrow_mut();
+
+        state.check_allowed(&path)?;
+        match (state.nodes.contains_key(&path), state.is_dir_parent(&path)) {
+            (true, _) => Err(Error::new(ErrorKind::AlreadyExists, "File exists")),
+            (false, true) => {
+                state.insert(path, data);
+                Ok(())
+            }
+            (false, false) => Err(Error::new(ErrorKind::NotFound, "No such directory")),
+        }
+    }
+}
+
+impl MemFsState {
+    // insert a node, updating mtime of it and its parent dir
+    fn insert(&mut self, path: PathBuf, data: MemData) {
+        let mtime = self.tick();
+        if let Some(parent) = path.parent().and_then(|p| self.nodes.get_mut(p)) {
+            parent.mtime = mtime;
+        }
+        self.nodes.insert(path, MemNode { data, mtime });
+    }
+
+    // remove a node, updating mtime of its parent dir
+    fn remove(&mut self, path: &Path) -> Option<MemNode> {
+        let mtime = self.tick();
+        if let Some(parent) = path.parent().and_then(|p| self.nodes.get_mut(p)) {
+            parent.mtime = mtime;
+        }
+        self.nodes.remove(path)
+    }
+
+    // increase logical clock, and get it as time
+    fn tick(&mut self) -> SystemTime {
+        self.clock += 1;
+        UNIX_EPOCH + Duration::from_secs(self.clock)
+    }
+
+    // check if parent of the path is an existing dir
+    fn is_dir_parent(&self, path: &Path) -> bool {
+        matches!(
+            path.parent().and_then(|p| self.nodes.get(p)),
+            Some(MemNode {
+                data: MemData::Dir,
+                ..
+            })
+        )
+    }
+
+    // returns Err if changes on the path are denied
+    fn check_allowed(&self, path: &Path) -> io::Result<()> {
+        match self.denied.contains(path) {
+            true => Err(Error::new(ErrorKind::PermissionDenied, "Access denied")),
+            false => Ok(()),
+        }
+    }
+}
+
+impl LinkFs for MemFs {
+    fn symlink_file(&self, target: &Path, link: &Path) -> io::Result<()> {
+        self.create(link, MemData::Symlink(target.to_path_buf()))
+    }
+
+    fn hard_link(&self, original: &Path, link: &Path) -> io::Result<()> {
+        // modeled as a copy, as contents are never changed
+        self.copy_new(original, link)
+    }
+
+    fn copy_new(&self, from: &Path, to: &Path) -> io::Result<()> {
+        match self.node(&self.resolve(from, true)?)?.data {
+            MemData::File(data) => self.create(to, MemData::File(data)),
+            _ => Err(Error::new(ErrorKind::InvalidInput, "Not a file")),
+        }
+    }
+
+    fn write_new(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.create(path, MemData::File(data.to_vec()))
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        // write to the target if symlink
+        let path = match self.resolve(path, true) {
+            Ok(pb) => pb,
+            Err(_) => normalize(path)?,
+        };
+
+        match self.node(&path).map(|node| node.data) {
+            Ok(MemData::File(_)) => {
+                let mut state = self.state.borrow_mut();
+                state.check_allowed(&path)?;
+                let mtime = state.tick();
+                state.nodes.insert(
+                    path,
+                    MemNode {
+                        data: MemData::File(data.to_vec()),
+                        mtime,
+                    },
+                );
+                Ok(())
+            }
+            Ok(_) => Err(Error::new(ErrorKind::InvalidInput, "Not a file")),
+            Err(_) => self.create(&path, MemData::File(data.to_vec())),
+        }
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        match self.node(&self.resolve(path, true)?)?.data {
+            MemData::File(data) => String::from_utf8(data)
+                .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string())),
+            _ => Err(Error::new(ErrorKind::InvalidInput, "Not a file")),
+        }
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        match self.node(path)?.data {
+            MemData::Symlink(target) => Ok(target),
+            _ => Err(Error::new(ErrorKind::InvalidInput, "Not a symlink")),
+        }
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        self.resolve(path, true)
+    }
+
+    fn file_type(&self, path: &Path) -> io::Result<FileKind> {
+        self.node(path).map(|node| match node.data {
+            MemData::File(_) => FileKind::File,
+            MemData::Dir => FileKind::Dir,
+            MemData::Symlink(_) => FileKind::Symlink,
+        })
+    }
+
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<(PathBuf, FileKind)>> {
+        let dir = self.resolve(dir, true)?;
+        match self.node(&dir)?.data {
+            MemData::Dir => (),
+            _ => return Err(Error::new(ErrorKind::InvalidInput, "Not a directory")),
+        }
+
+        let paths: Vec<PathBuf> = {
+            self.state
+                .borrow()
+                .nodes
+                .keys()
+                .filter(|p| p.parent() == Some(dir.as_path()))
+                .cloned()
+                .collect()
+        };
+        paths
+            .into_iter()
+            .map(|pb| self.file_type(&pb).map(|kind| (pb, kind)))
+            .collect()
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let (from, to) = (normalize(from)?, normalize(to)?);
+        let node = self.node(&from)?;
+        let mut state = self.state.borrow_mut();
+
+        state.check_allowed(&from)?;
+        state.check_allowed(&to)?;
+        let is_dir_to = matches!(
+            state.nodes.get(&to),
+            Some(MemNode {
+                data: MemData::Dir,
+                ..
+            })
+        );
+        match (&node.data, is_dir_to, state.is_dir_parent(&to)) {
+            (MemData::Dir, _, _) | (_, true, _) => Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Directory cannot be renamed",
+            )),
+            (_, false, true) => {
+                state.remove(&from);
+                state.insert(to, node.data);
+                Ok(())
+            }
+            (_, false, false) => Err(Error::new(ErrorKind::NotFound, "No such directory")),
+        }
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        let path = normalize(path)?;
+        let node = self.node(&path)?;
+        let mut state = self.state.borrow_mut();
+
+        state.check_allowed(&path)?;
+        match node.data {
+            MemData::Dir => Err(Error::new(ErrorKind::InvalidInput, "Is a directory")),
+            _ => {
+                state.remove(&path);
+                Ok(())
+            }
+        }
+    }
+
+    fn create_dir_all(&self, dir: &Path) -> io::Result<()> {
+        let dir = normalize(dir)?;
+        let mut state = self.state.borrow_mut();
+
+        dir.ancestors()
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .try_for_each(|p| match state.nodes.get(p).map(|node| &node.data) {
+                Some(MemData::Dir) => Ok(()),
+                Some(_) => Err(Error::new(ErrorKind::AlreadyExists, "Not a directory")),
+                None => {
+                    state.insert(p.to_path_buf(), MemData::Dir);
+                    Ok(())
+                }
+            })
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        self.node(&self.resolve(path, true)?).map(|node| node.mtime)
+    }
+
+    fn open_lock_file(&self, _path: &Path) -> io::Result<Option<File>> {
+        // not shared with other processes: nothing to lock
+        Ok(None)
+    }
+}
+
+///
+/// Get root dir to create [`MemFs`] paths under, which is absolute on the host
+///
+pub fn mem_root() -> PathBuf {
+    match cfg!(target_os = "windows") {
+        true => PathBuf::from(r"C:\wslcmd-mem"),
+        false => PathBuf::from("/wslcmd-mem"),
+    }
+}
+
+// get root of the absolute path (Ex: 'C:\', '/')
+fn root_of(path: &Path) -> PathBuf {
+    path.components()
+        .take_while(|c| matches!(c, Component::Prefix(_) | Component::RootDir))
+        .collect()
+}
+
+// split path into owned components
+fn components_of(path: &Path) -> VecDeque<PathBuf> {
+    path.components()
+        .map(|c| PathBuf::from(c.as_os_str()))
+        .collect()
+}
+
+// normalize absolute path lexically, removing '.' components
+// returns Err if the path is not absolute
+fn normalize(path: &Path) -> io::Result<PathBuf> {
+    match path.is_absolute() {
+        true => Ok(path
+            .components()
+            .filter(|c| !matches!(c, Component::CurDir))
+            .collect()),
+        false => Err(Error::new(ErrorKind::InvalidInput, "Path must be absolute")),
+    }
+}
+
+#[cfg(test)]
+/// For module test
+mod test {
+    use super::super::{FileKind, LinkFs};
+    use super::{mem_root, MemFs};
+    use std::io::ErrorKind;
+    use std::path::Path;
+
+    #[test]
+    fn test_mem_fs() {
+        let fs = MemFs::new();
+        let root = mem_root();
+        let (dir, dir_bin) = (root.join("links"), root.join("bin"));
+        fs.create_dir_all(&dir).expect("Create dir");
+        fs.create_dir_all(&dir_bin).expect("Create dir");
+        fs.write_new(&dir_bin.join("wslcmd.exe"), b"bin")
+            .expect("Write");
+
+        // relative symlink chain: a.exe -> .a.exe -> ../bin/wslcmd.exe
+        let mtime = fs.modified(&dir).expect("Mtime");
+        fs.symlink_file(Path::new("../bin/wslcmd.exe"), &dir.join(".a.exe"))
+            .expect("Symlink");
+        fs.symlink_file(Path::new(".a.exe"), &dir.join("a.exe"))
+            .expect("Symlink");
+        assert!(fs.modified(&dir).expect("Mtime") > mtime);
+        assert_eq!(
+            fs.canonicalize(&dir.join("a.exe")).expect("Canonicalize"),
+            dir_bin.join("wslcmd.exe")
+        );
+        assert_eq!(fs.file_type(&dir.join("a.exe")).unwrap(), FileKind::Symlink);
+        assert_eq!(fs.read_to_string(&dir.join("a.exe")).unwrap(), "bin");
+        assert_eq!(fs.read_dir(&dir).expect("Read dir").len(), 2);
+
+        // existing file, dangling symlink
+        assert_eq!(
+            fs.symlink_file(Path::new("x"), &dir.join("a.exe"))
+                .unwrap_err()
+                .kind(),
+            ErrorKind::AlreadyExists
+        );
+        fs.remove_file(&dir.join(".a.exe")).expect("Remove");
+        assert!(!fs.exists(&dir.join("a.exe")) && fs.entry_exists(&dir.join("a.exe")));
+
+        // denied path
+        fs.deny(&dir.join("b.exe"));
+        assert_eq!(
+            fs.write_new(&dir.join("b.exe"), b"").unwrap_err().kind(),
+            ErrorKind::PermissionDenied
+        );
+        fs.allow(&dir.join("b.exe"));
+        fs.write_new(&dir.join("b.exe"), b"").expect("Write");
+        fs.rename(&dir.join("b.exe"), &dir.join("c.exe"))
+            .expect("Rename");
+        assert!(!fs.entry_exists(&dir.join("b.exe")) && fs.exists(&dir.join("c.exe")));
+    }
+}
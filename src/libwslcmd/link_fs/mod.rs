@@ -0,0 +1,268 @@
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::SystemTime;
+
+/// In-memory filesystem for tests
+#[cfg(test)]
+mod mem_fs;
+#[cfg(test)]
+pub use mem_fs::{mem_root, MemFs};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Type of a file, without following symlinks
+pub enum FileKind {
+    /// Regular file
+    File,
+
+    /// Directory
+    Dir,
+
+    /// Symlink
+    Symlink,
+}
+
+/// Filesystem operations used to manage links,
+/// so that link logic can be run on the real filesystem or on an in-memory one
+pub trait LinkFs {
+    /// Create a file symlink at `link`, pointing to `target`
+    fn symlink_file(&self, target: &Path, link: &Path) -> io::Result<()>;
+
+    /// Create a hardlink at `link` of the `original` file
+    fn hard_link(&self, original: &Path, link: &Path) -> io::Result<()>;
+
+    /// Copy `from` to a new file `to`. Fails if `to` already exists.
+    fn copy_new(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    /// Write `data` to a new file. Fails if the file already exists.
+    fn write_new(&self, path: &Path, data: &[u8]) -> io::Result<()>;
+
+    /// Write `data` to a file, replacing its contents if exists
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()>;
+
+    /// Read a file to string
+    fn read_to_string(&self, path: &Path) -> io::Result<String>;
+
+    /// Read target of a symlink
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf>;
+
+    /// Get absolute path, following all symlinks
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf>;
+
+    /// Get type of a file, without following symlinks
+    fn file_type(&self, path: &Path) -> io::Result<FileKind>;
+
+    /// List paths and types of entries in a directory
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<(PathBuf, FileKind)>>;
+
+    /// Rename a file, replacing `to` if exists
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    /// Remove a file (or a symlink itself)
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+
+    /// Create a directory and all of its parents
+    fn create_dir_all(&self, dir: &Path) -> io::Result<()>;
+
+    /// Get modified time of a file or directory, following symlinks
+    fn modified(&self, path: &Path) -> io::Result<SystemTime>;
+
+    /// Open a file to hold an inter-process lock on.
+    /// [`None`] if the filesystem is not shared with other processes.
+    fn open_lock_file(&self, path: &Path) -> io::Result<Option<File>>;
+
+    /// Check if a path exists, following symlinks
+    fn exists(&self, path: &Path) -> bool {
+        self.canonicalize(path).is_ok()
+    }
+
+    /// Check if a path is an existing directory, following symlinks
+    fn is_dir(&self, path: &Path) -> bool {
+        self.canonicalize(path)
+            .and_then(|pb| self.file_type(&pb))
+            .is_ok_and(|kind| kind == FileKind::Dir)
+    }
+
+    /// Check if any file (or symlink itself) exists at the path
+    fn entry_exists(&self, path: &Path) -> bool {
+        self.file_type(path).is_ok()
+    }
+
+    /// Check if the path is a regular file, without following symlinks
+    fn is_file(&self, path: &Path) -> bool {
+        self.file_type(path)
+            .is_ok_and(|kind| kind == FileKind::File)
+    }
+
+    /// Check if the path is a symlink itself, without following it
+    fn is_symlink(&self, path: &Path) -> bool {
+        self.file_type(path)
+            .is_ok_and(|kind| kind == FileKind::Symlink)
+    }
+}
+
+#[derive(Clone)]
+/// Shared reference of [`LinkFs`], which is [`StdFs`] by default
+pub struct LinkFsRef(Rc<dyn LinkFs>);
+
+impl LinkFsRef {
+    ///
+    /// Create new [`LinkFsRef`] of the given filesystem
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let fs = LinkFsRef::new(Rc::new(StdFs));
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn new(fs: Rc<dyn LinkFs>) -> Self {
+        Self(fs)
+    }
+}
+
+impl Default for LinkFsRef {
+    fn default() -> Self {
+        Self(Rc::new(StdFs))
+    }
+}
+
+impl Deref for LinkFsRef {
+    type Target = dyn LinkFs;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
+
+impl fmt::Debug for LinkFsRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("LinkFsRef")
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+/// Real filesystem, of [`std::fs`]
+pub struct StdFs;
+
+impl LinkFs for StdFs {
+    fn symlink_file(&self, target: &Path, link: &Path) -> io::Result<()> {
+        #[cfg(target_os = "windows")]
+        return std::os::windows::fs::symlink_file(target, link);
+
+        // other hosts are only for unit tests
+        #[cfg(not(target_os = "windows"))]
+        return std::os::unix::fs::symlink(target, link);
+    }
+
+    fn hard_link(&self, original: &Path, link: &Path) -> io::Result<()> {
+        std::fs::hard_link(original, link)
+    }
+
+    fn copy_new(&self, from: &Path, to: &Path) -> io::Result<()> {
+        // create new file only if not exists, as fs::copy overwrites existing one
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(to)
+            .and_then(|mut f_to| {
+                File::open(from)
+                    .and_then(|mut f_from| io::copy(&mut f_from, &mut f_to))
+                    .map(|_| ())
+                    // if failed, remove partially written file
+                    .inspect_err(|_| {
+                        drop(f_to);
+                        std::fs::remove_file(to).ok();
+                    })
+            })
+    }
+
+    fn write_new(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        use std::io::Write;
+
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .and_then(|mut f| f.write_all(data))
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        std::fs::write(path, data)
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        std::fs::read_link(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        std::fs::canonicalize(path)
+    }
+
+    fn file_type(&self, path: &Path) -> io::Result<FileKind> {
+        std::fs::symlink_metadata(path).map(|md| to_file_kind(md.file_type()))
+    }
+
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<(PathBuf, FileKind)>> {
+        // type of each entry is got from the dir listing, without reading metadata of it
+        Ok(std::fs::read_dir(dir)?
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                entry
+                    .file_type()
+                    .ok()
+                    .map(|file_type| (entry.path(), to_file_kind(file_type)))
+            })
+            .collect())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        std::fs::rename(from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        std::fs::remove_file(path)
+    }
+
+    fn create_dir_all(&self, dir: &Path) -> io::Result<()> {
+        std::fs::create_dir_all(dir)
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        std::fs::metadata(path).and_then(|md| md.modified())
+    }
+
+    fn open_lock_file(&self, path: &Path) -> io::Result<Option<File>> {
+        std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .map(Some)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+}
+
+// convert std file type to FileKind (other types are regarded as file)
+fn to_file_kind(file_type: std::fs::FileType) -> FileKind {
+    match (file_type.is_symlink(), file_type.is_dir()) {
+        (true, _) => FileKind::Symlink,
+        (false, true) => FileKind::Dir,
+        (false, false) => FileKind::File,
+    }
+}
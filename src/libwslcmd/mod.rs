@@ -5,13 +5,34 @@ pub use wcstr::WCStr;
 /// Converts Windows cmdline to WSL cmdline
 mod wslcmd;
 pub use wslcmd::WslCmd;
+/// Strip and validate namespace prefixes of WslCmd
+mod cmd_prefix;
+pub use cmd_prefix::{is_valid_prefix, strip_prefix};
 /// Manage list of WslCmd in a directory
 mod wslcmd_list;
-pub use wslcmd_list::WslCmdList;
+pub use wslcmd_list::{
+    CmdSelector, JournalEntry, LinkHealth, LinkInfo, LinkIssue, LinkOptions, LinkSet, WslCmdList,
+};
+/// Query informations from WSL
+mod wsl_query;
+pub use wsl_query::{similar_cmds, wsl_find_cmds, wsl_path_executables, wsl_whatis};
+/// Find commands on Windows PATH
+mod win_cmd_path;
+pub use win_cmd_path::{CmdShadows, WinCmdPath};
+/// Abstract filesystem operations on links
+mod link_fs;
+/// Abstract process spawning of WSL cmdlines
+mod wsl_launcher;
+/// Manage manifest of WslCmd links
+mod wslcmd_manifest;
+pub use wslcmd_manifest::{DetachedMode, LinkKind, LinkMeta, WslCmdManifest};
 
 /// Detached process prefix on cmdname
 const DETACHED_PROC_PREFIX: char = '.';
 
-// prevent compilation at environments other than Windows
-#[cfg(not(target_os = "windows"))]
+/// Delimiter of command name, which divides into command, user, distribution
+pub const CMDNAME_DELIM: char = '!';
+
+// prevent compilation at environments other than Windows (except for unit tests)
+#[cfg(not(any(target_os = "windows", test)))]
 compile_error!("WslCmd only works on Windows target!");
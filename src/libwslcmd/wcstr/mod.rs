@@ -21,17 +21,58 @@ pub trait WCStr: WCPath + Clone {
     fn wcstr_replace_all_regex(&self, match_pattern: &str, replace_with: &str) -> Option<String> {
         self.wcstr_as_ref().and_then(|s| {
             regex::Regex::new(match_pattern)
-                .map(|re| re.replace_all(&s, replace_with).into_owned())
+                .map(|re| re.replace_all(s, replace_with).into_owned())
                 .ok()
         })
     }
 
+    /// Check if whole [`WCStr`] matches the glob pattern (`*`, `?`, `[...]`),
+    /// ignoring case as Windows filenames do
+    fn wcstr_matches_glob(&self, glob_pattern: &str) -> bool {
+        self.wcstr_as_ref().is_some_and(|s| {
+            regex::Regex::new(&glob_to_regex(glob_pattern)).is_ok_and(|re| re.is_match(s))
+        })
+    }
+
     /// Invoke function on [`WCStr`], for call chaining
     fn wcstr_invoke<T: WCStr, F: FnOnce(&Self) -> T>(&self, f: F) -> T {
         f(self)
     }
 }
 
+// convert glob pattern to anchored, case-insensitive regex pattern
+//   Ex) 'g*.[ch]' -> '(?i)^g.*\.[ch]$'
+fn glob_to_regex(glob_pattern: &str) -> String {
+    let mut it = glob_pattern.chars().peekable();
+    let mut ret = String::from("(?i)^");
+
+    while let Some(c) = it.next() {
+        match c {
+            '*' => ret.push_str(".*"),
+            '?' => ret.push('.'),
+            // char class: '[!...]' -> '[^...]'
+            '[' => {
+                ret.push('[');
+                if it.next_if(|c| *c == '!' || *c == '^').is_some() {
+                    ret.push('^');
+                }
+                for c in it.by_ref() {
+                    match c {
+                        ']' => break,
+                        '\\' | '[' | '&' | '~' => ret.push_str(&regex::escape(&c.to_string())),
+                        _ => ret.push(c),
+                    }
+                }
+                ret.push(']');
+            }
+            _ => ret.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+
+    ret.push('$');
+    ret
+}
+
 /// [`String`] implementations for [`WCStr`]
 impl WCStr for String {
     fn wcstr_as_ref(&self) -> Option<&str> {
@@ -88,3 +129,23 @@ impl WCStr for Option<&str> {
         self.map(String::from)
     }
 }
+
+#[cfg(test)]
+/// For module test
+mod test {
+    use super::WCStr;
+
+    #[test]
+    fn test_matches_glob() {
+        assert!("git".wcstr_matches_glob("g*"));
+        assert!("git".wcstr_matches_glob("g?t"));
+        assert!("git".wcstr_matches_glob("[fg]it"));
+        assert!(!"git".wcstr_matches_glob("[!g]it"));
+        assert!(!"git-lfs".wcstr_matches_glob("g?t"));
+        assert!("a.b".wcstr_matches_glob("a.b"));
+        assert!(!"axb".wcstr_matches_glob("a.b"));
+        assert!("lsb_release!!debian".wcstr_matches_glob("*!debian"));
+        assert!("git".wcstr_matches_glob("Git*"));
+        assert!("Git-LFS".wcstr_matches_glob("git-[l]fs"));
+    }
+}
@@ -48,7 +48,7 @@ pub trait WCPath: Clone {
 
     /// Check if [`WCPath`] is absolute path
     fn wcpath_is_absolute(&self) -> bool {
-        self.wcpath_as_path().map_or(false, |p| p.is_absolute())
+        self.wcpath_as_path().is_some_and(|p| p.is_absolute())
     }
 
     /// Get parent of [`WCPath`]
@@ -181,6 +181,6 @@ impl WCPath for Option<&PathBuf> {
         self.map(|pb| pb.as_path())
     }
     fn wcpath_to_pathbuf(self) -> Option<PathBuf> {
-        self.map(|pb| pb.clone())
+        self.cloned()
     }
 }
@@ -0,0 +1,323 @@
+use super::WCPath;
+use derive_getters::Getters;
+use std::path::{Path, PathBuf};
+
+/// Default value of PATHEXT, if not set
+const DEFAULT_PATHEXT: &str = ".COM;.EXE;.BAT;.CMD";
+
+/// Delimiter of PATH and PATHEXT on Windows
+const PATH_DELIM: char = ';';
+
+/// Built-in commands of cmd.exe, which always take precedence over executables on PATH
+const CMD_BUILTINS: &[&str] = &[
+    "assoc", "break", "call", "cd", "chdir", "cls", "color", "copy", "date", "del", "dir", "echo",
+    "endlocal", "erase", "exit", "for", "ftype", "goto", "if", "md", "mkdir", "mklink", "move",
+    "path", "pause", "popd", "prompt", "pushd", "rd", "rem", "ren", "rename", "rmdir", "set",
+    "setlocal", "shift", "start", "time", "title", "type", "ver", "verify", "vol",
+];
+
+#[derive(Debug, Clone)]
+/// Windows PATH and PATHEXT, to find Windows commands
+pub struct WinCmdPath {
+    /// Dirs on PATH, in order
+    dirs: Vec<PathBuf>,
+
+    /// Executable extensions on PATHEXT (Ex: '.EXE')
+    exts: Vec<String>,
+}
+
+#[derive(Getters, Debug, Clone, Default, PartialEq, Eq)]
+/// Other Windows commands with the same name as a link, found by [`WinCmdPath::find_shadows`]
+pub struct CmdShadows {
+    /// Executables which come earlier than the link on PATH, so that they are run instead.
+    /// If the link dir is not on PATH, all found executables are here.
+    #[getter(rename = "get_shadowed_by")]
+    shadowed_by: Vec<PathBuf>,
+
+    /// Executables which come later than the link on PATH, so that the link is run instead
+    #[getter(rename = "get_shadowing")]
+    shadowing: Vec<PathBuf>,
+
+    /// If the command name is a built-in command of cmd.exe, which always takes precedence
+    #[getter(rename = "get_builtin")]
+    builtin: bool,
+
+    /// If the link dir is on PATH
+    #[getter(rename = "get_linkdir_on_path")]
+    linkdir_on_path: bool,
+}
+
+impl CmdShadows {
+    ///
+    /// Check if the link is not run by its name, as other command takes precedence
+    ///
+    #[allow(dead_code)]
+    pub fn is_shadowed(&self) -> bool {
+        self.builtin || !self.shadowed_by.is_empty()
+    }
+
+    ///
+    /// Check if nothing else has the same name as the link
+    ///
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        !self.builtin && self.shadowed_by.is_empty() && self.shadowing.is_empty()
+    }
+}
+
+impl WinCmdPath {
+    ///
+    /// Create new [`WinCmdPath`] from PATH and PATHEXT env vars
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let win_cmd_path = WinCmdPath::from_env();
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn from_env() -> Self {
+        Self::new(
+            &std::env::var("PATH").unwrap_or_default(),
+            &std::env::var("PATHEXT").unwrap_or_else(|_| DEFAULT_PATHEXT.to_string()),
+        )
+    }
+
+    ///
+    /// Create new [`WinCmdPath`] from values of PATH and PATHEXT
+    ///
+    /// # Arguments
+    ///
+    /// * `path`    - ';'-separated dirs
+    /// * `pathext` - ';'-separated extensions
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let win_cmd_path = WinCmdPath::new(r"C:\Windows\System32;C:\bin", ".COM;.EXE");
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn new(path: &str, pathext: &str) -> Self {
+        Self {
+            dirs: path
+                .split(PATH_DELIM)
+                .map(|s| s.trim().trim_matches('"'))
+                .filter(|s| !s.is_empty())
+                .map(PathBuf::from)
+                .collect(),
+            exts: pathext
+                .split(PATH_DELIM)
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(String::from)
+                .collect(),
+        }
+    }
+
+    ///
+    /// Find all executables with the given command name on PATH, in the order of PATH
+    ///
+    /// # Arguments
+    ///
+    /// * `cmdname`     - Command name without extension
+    /// * `exclude_dir` - A dir to skip while finding (Ex: WslCmd link dir)
+    ///
+    /// # Return
+    ///
+    /// Paths of found executables
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let found: Vec<PathBuf> = win_cmd_path.find("find", None);
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn find(&self, cmdname: &str, exclude_dir: Option<&Path>) -> Vec<PathBuf> {
+        let exclude_dir = exclude_dir.map(canonical_or_self);
+
+        self.dirs
+            .iter()
+            // skip excluded dir
+            .filter(|dir| exclude_dir.as_ref() != Some(&canonical_or_self(dir)))
+            .flat_map(|dir| self.find_in(dir, cmdname))
+            .collect()
+    }
+
+    ///
+    /// Find executables with the same name as a link, and check which one is run by the name,
+    /// following the order of PATH and PATHEXT as cmd.exe does
+    ///
+    /// # Arguments
+    ///
+    /// * `linkname` - Link name without extension
+    /// * `linkdir`  - A dir where the link is placed
+    ///
+    /// # Return
+    ///
+    /// Executables coming earlier or later than the link on PATH
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let shadows = win_cmd_path.find_shadows("python", &linkdir);
+    /// if shadows.is_shadowed() {
+    ///     println!("{:?}", shadows.get_shadowed_by());
+    /// }
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn find_shadows(&self, linkname: &str, linkdir: &Path) -> CmdShadows {
+        let linkdir = canonical_or_self(linkdir);
+        let dirs: Vec<PathBuf> = self.dirs.iter().map(canonical_or_self).collect();
+        let linkdir_pos = dirs.iter().position(|dir| dir == &linkdir);
+
+        // split found executables by the position of linkdir (other entries of linkdir are skipped)
+        let (shadowed_by, shadowing) = dirs
+            .iter()
+            .enumerate()
+            .filter(|(_, dir)| *dir != &linkdir)
+            .flat_map(|(i, dir)| {
+                self.find_in(dir, linkname)
+                    .into_iter()
+                    .map(move |pb| (i, pb))
+            })
+            .partition::<Vec<(usize, PathBuf)>, _>(|(i, _)| linkdir_pos.is_none_or(|pos| *i < pos));
+
+        CmdShadows {
+            shadowed_by: shadowed_by.into_iter().map(|(_, pb)| pb).collect(),
+            shadowing: shadowing.into_iter().map(|(_, pb)| pb).collect(),
+            builtin: Self::is_builtin(linkname),
+            linkdir_on_path: linkdir_pos.is_some(),
+        }
+    }
+
+    // find all 'dir/cmdname.ext' which exist, in the order of PATHEXT
+    fn find_in(&self, dir: &Path, cmdname: &str) -> Vec<PathBuf> {
+        self.exts
+            .iter()
+            .map(|ext| dir.join(format!("{}{}", cmdname, ext)))
+            // app execution aliases (Ex: python.exe of Microsoft Store) cannot be followed
+            .filter(|pb| pb.is_file() || std::fs::symlink_metadata(pb).is_ok_and(|md| md.is_file()))
+            .collect()
+    }
+
+    ///
+    /// Check if the directory is on PATH
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - A directory to find (Ex: WslCmd link dir)
+    ///
+    #[allow(dead_code)]
+    pub fn contains_dir(&self, dir: &Path) -> bool {
+        let dir = canonical_or_self(dir);
+
+        self.dirs.iter().any(|pb| canonical_or_self(pb) == dir)
+    }
+
+    ///
+    /// Check if given command name is a built-in command of cmd.exe
+    ///
+    /// # Arguments
+    ///
+    /// * `cmdname` - Command name without extension
+    ///
+    #[allow(dead_code)]
+    pub fn is_builtin(cmdname: &str) -> bool {
+        CMD_BUILTINS.contains(&cmdname.to_lowercase().as_str())
+    }
+}
+
+// canonicalize path if exists, to compare dirs on PATH
+fn canonical_or_self<T: AsRef<Path>>(p: T) -> PathBuf {
+    p.as_ref()
+        .wcpath_canonicalize()
+        .unwrap_or_else(|| p.as_ref().to_path_buf())
+}
+
+#[cfg(test)]
+/// For module test
+mod test {
+    use super::WinCmdPath;
+    use std::{env, fs};
+
+    const TEST_TMP_DIR: &str = "wslcmd_tmpdir_test-win-cmd-path";
+
+    #[test]
+    fn test_find() {
+        // init tmpdir with a dummy executable
+        let tmpdir = env::temp_dir().join(TEST_TMP_DIR);
+        fs::remove_dir_all(&tmpdir).ok();
+        fs::create_dir_all(&tmpdir).expect("Tmp dir initialize");
+        fs::write(tmpdir.join("tool.exe"), b"wslcmd dummy").expect("Dummy file creation");
+
+        let win_cmd_path = WinCmdPath::new(
+            &format!(r#""{}";C:\nonexistent-dir"#, tmpdir.display()),
+            ".com;.exe",
+        );
+        assert_eq!(
+            win_cmd_path.find("tool", None),
+            vec![tmpdir.join("tool.exe")]
+        );
+        assert!(win_cmd_path.find("tool", Some(&tmpdir)).is_empty()); // excluded
+        assert!(win_cmd_path.find("other", None).is_empty());
+
+        // clean tmpdir
+        fs::remove_dir_all(&tmpdir).ok();
+    }
+
+    #[test]
+    fn test_find_shadows() {
+        // init tmpdirs: [before, linkdir, after] on PATH
+        let tmpdir = env::temp_dir().join(format!("{}-shadows", TEST_TMP_DIR));
+        fs::remove_dir_all(&tmpdir).ok();
+        let dirs: Vec<_> = ["before", "links", "after"]
+            .iter()
+            .map(|s| tmpdir.join(s))
+            .collect();
+        dirs.iter()
+            .for_each(|dir| fs::create_dir_all(dir).expect("Tmp dir initialize"));
+        fs::write(dirs[0].join("python.exe"), b"wslcmd dummy").expect("Dummy file creation");
+        fs::write(dirs[1].join("python.exe"), b"wslcmd dummy").expect("Dummy file creation");
+        fs::write(dirs[2].join("python.cmd"), b"wslcmd dummy").expect("Dummy file creation");
+        fs::write(dirs[2].join("git.exe"), b"wslcmd dummy").expect("Dummy file creation");
+        let path = dirs
+            .iter()
+            .map(|dir| dir.display().to_string())
+            .collect::<Vec<_>>()
+            .join(";");
+        let win_cmd_path = WinCmdPath::new(&path, ".exe;.cmd");
+
+        // shadowed by earlier one, while shadowing later one
+        let shadows = win_cmd_path.find_shadows("python", &dirs[1]);
+        assert!(shadows.is_shadowed() && *shadows.get_linkdir_on_path());
+        assert_eq!(shadows.get_shadowed_by().len(), 1);
+        assert!(shadows.get_shadowed_by()[0].ends_with("python.exe"));
+        assert_eq!(shadows.get_shadowing().len(), 1);
+        assert!(shadows.get_shadowing()[0].ends_with("python.cmd"));
+
+        // only shadowing, nothing, and built-in
+        let shadows = win_cmd_path.find_shadows("git", &dirs[1]);
+        assert!(!shadows.is_shadowed() && shadows.get_shadowing().len() == 1);
+        assert!(win_cmd_path.find_shadows("vim", &dirs[1]).is_empty());
+        assert!(win_cmd_path.find_shadows("dir", &dirs[1]).is_shadowed());
+
+        // linkdir not on PATH: all are shadowing the link
+        let win_cmd_path = WinCmdPath::new(&dirs[2].display().to_string(), ".exe;.cmd");
+        let shadows = win_cmd_path.find_shadows("git", &dirs[1]);
+        assert!(!shadows.get_linkdir_on_path() && shadows.get_shadowed_by().len() == 1);
+        assert!(win_cmd_path.contains_dir(&dirs[2]) && !win_cmd_path.contains_dir(&dirs[1]));
+
+        // clean tmpdir
+        fs::remove_dir_all(&tmpdir).ok();
+    }
+
+    #[test]
+    fn test_builtin() {
+        assert!(WinCmdPath::is_builtin("echo"));
+        assert!(WinCmdPath::is_builtin("DIR"));
+        assert!(!WinCmdPath::is_builtin("git"));
+    }
+}
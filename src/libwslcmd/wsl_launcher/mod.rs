@@ -0,0 +1,104 @@
+use std::fmt;
+use std::io;
+use std::ops::Deref;
+use std::process::{Child, Command, Stdio};
+use std::rc::Rc;
+
+/// Launcher running WSL cmdlines with local `sh`, for tests
+#[cfg(test)]
+mod sh_launcher;
+#[cfg(test)]
+pub use sh_launcher::ShLauncher;
+
+/// Process spawning of WSL cmdlines,
+/// so that execution of WSL commands can be run with real WSL or with a stand-in
+pub trait WslLauncher {
+    /// Spawn a process of `wsl` with `wsl_args`.
+    /// If `is_detached` is set, the process is created without a console window.
+    /// If `is_piped` is set, all stdio of the process are piped, or inherited if not.
+    fn spawn(&self, wsl_args: &[String], is_detached: bool, is_piped: bool) -> io::Result<Child>;
+}
+
+#[derive(Clone)]
+/// Shared reference of [`WslLauncher`], which is [`WslExe`] by default
+pub struct WslLauncherRef(Rc<dyn WslLauncher>);
+
+impl WslLauncherRef {
+    ///
+    /// Create new [`WslLauncherRef`] of the given launcher
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let launcher = WslLauncherRef::new(Rc::new(WslExe));
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn new(launcher: Rc<dyn WslLauncher>) -> Self {
+        Self(launcher)
+    }
+}
+
+impl Default for WslLauncherRef {
+    fn default() -> Self {
+        Self(Rc::new(WslExe))
+    }
+}
+
+impl Deref for WslLauncherRef {
+    type Target = dyn WslLauncher;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
+
+impl fmt::Debug for WslLauncherRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("WslLauncherRef")
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+/// Real WSL, of `wsl.exe`
+pub struct WslExe;
+
+impl WslLauncher for WslExe {
+    fn spawn(&self, wsl_args: &[String], is_detached: bool, is_piped: bool) -> io::Result<Child> {
+        #[cfg(target_os = "windows")]
+        use std::os::windows::process::CommandExt;
+
+        // creation flags are ignored on other hosts (only for unit tests)
+        #[cfg(not(target_os = "windows"))]
+        trait CommandExt {
+            fn creation_flags(&mut self, _flags: u32) -> &mut Self;
+        }
+        #[cfg(not(target_os = "windows"))]
+        impl CommandExt for Command {
+            fn creation_flags(&mut self, _flags: u32) -> &mut Self {
+                self
+            }
+        }
+
+        set_stdio(Command::new("wsl").args(wsl_args), is_piped)
+            // set flag: create as normal mode or detached mode
+            .creation_flags({
+                match is_detached {
+                    // https://docs.microsoft.com/en-us/windows/win32/procthread/process-creation-flags
+                    true => 0x08000000,  // detached mode flag - CREATE_NO_WINDOW
+                    false => 0x00000000, // normal mode flag - RESET
+                }
+            })
+            .spawn()
+    }
+}
+
+// set all stdio of command to piped, or inherited
+fn set_stdio(command: &mut Command, is_piped: bool) -> &mut Command {
+    let stdio = || match is_piped {
+        true => Stdio::piped(),
+        false => Stdio::inherit(),
+    };
+
+    command.stdin(stdio()).stdout(stdio()).stderr(stdio())
+}
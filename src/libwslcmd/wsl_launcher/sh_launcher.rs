@@ -0,0 +1,66 @@
+use std::cell::RefCell;
+use std::io;
+use std::io::{Error, ErrorKind};
+use std::process::{Child, Command};
+
+use super::{set_stdio, WslLauncher};
+
+/// Stand-in of `wslpath` on the local shell:
+/// drive paths are converted to '/mnt/<drive>/...', and others are left as they are
+const SH_WSLPATH_FN: &str = concat!(
+    r#"wslpath() { case "$1" in "#,
+    r#"[A-Za-z]:*) printf '/mnt/%s%s\n' "$(printf '%s' "${1%%:*}" | tr 'A-Z' 'a-z')" "${1#?:}" ;; "#,
+    r#"*) printf '%s\n' "$1" ;; "#,
+    r#"esac; }; "#,
+);
+
+#[derive(Debug, Default)]
+/// Launcher running WSL cmdlines with local `sh`, for tests.
+/// The shell cmdline after '--' is run as WSL does (joined with spaces, then run by the shell),
+/// and all `wsl` args are recorded.
+pub struct ShLauncher {
+    /// `wsl` args of all launched processes
+    launched: RefCell<Vec<Vec<String>>>,
+}
+
+impl ShLauncher {
+    ///
+    /// Create new [`ShLauncher`], with nothing launched
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Get `wsl` args of all launched processes, in launched order
+    ///
+    pub fn get_launched(&self) -> Vec<Vec<String>> {
+        self.launched.borrow().clone()
+    }
+}
+
+impl WslLauncher for ShLauncher {
+    fn spawn(&self, wsl_args: &[String], _is_detached: bool, is_piped: bool) -> io::Result<Child> {
+        self.launched.borrow_mut().push(wsl_args.to_vec());
+
+        // only user and distribution options are expected before '--'
+        let mut it = wsl_args.iter();
+        loop {
+            match it.next().map(|s| s.as_str()) {
+                Some("--") => break,
+                Some("-u") | Some("-d") => it.next().map(|_| ()),
+                _ => None,
+            }
+            .ok_or(Error::new(ErrorKind::InvalidInput, "Invalid wsl args"))?;
+        }
+        let cmdline = it.map(|s| s.as_str()).collect::<Vec<&str>>().join(" ");
+
+        set_stdio(
+            Command::new("sh")
+                .arg("-c")
+                .arg(SH_WSLPATH_FN.to_string() + &cmdline),
+            is_piped,
+        )
+        .spawn()
+    }
+}
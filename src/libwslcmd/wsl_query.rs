@@ -0,0 +1,343 @@
+use std::collections::HashMap;
+
+use super::{WCStr, WslCmd};
+
+/// Shell script which prints names of executables in dirs of args (or of $PATH if no args),
+/// skipping Windows drives mounted on WSL
+const SCRIPT_PATH_EXECUTABLES: &str = concat!(
+    r#"if [ $# -eq 0 ]; then IFS=:; set -- $PATH; unset IFS; fi; "#,
+    r#"for d in "$@"; do "#,
+    r#"case "$d" in /mnt/?/*) continue;; esac; "#,
+    r#"for f in "$d"/*; do "#,
+    r#"if [ -f "$f" ] && [ -x "$f" ]; then printf '%s\n' "${f##*/}"; fi; "#,
+    r#"done; "#,
+    r#"done; "#,
+    r#"exit 0"#,
+);
+
+/// Shell script which prints names of args found as commands by `command -v`
+const SCRIPT_FIND_CMDS: &str = concat!(
+    r#"for c in "$@"; do "#,
+    r#"if command -v "$c" >/dev/null 2>&1; then printf '%s\n' "$c"; fi; "#,
+    r#"done; "#,
+    r#"exit 0"#,
+);
+
+/// Shell script which prints one-line descriptions of args by `whatis` (errors are ignored)
+const SCRIPT_WHATIS: &str = r#"whatis -- "$@" 2>/dev/null; exit 0"#;
+
+/// Max number of command names described by a WSL query, to keep the cmdline short
+const MAX_WHATIS_CMDS: usize = 200;
+
+/// Max number of similar command names to suggest
+const MAX_SIMILAR_CMDS: usize = 5;
+
+///
+/// Get names of executables on WSL PATH, or in the given WSL dirs, with a single WSL query
+///
+/// # Arguments
+///
+/// * `user` - WSL user to run the query
+/// * `dist` - WSL distribution to run the query
+/// * `dirs` - WSL dirs to find executables. If empty, dirs on WSL PATH are used.
+///
+/// # Return
+///
+/// [`Some`]\(names\) in the order of dirs without duplication, or [`None`] if the query failed
+///
+/// # Examples
+///
+/// ```
+/// let cmds: Option<Vec<String>> = wsl_path_executables(None, Some("debian"), &[] as &[&str]);
+/// ```
+///
+#[allow(dead_code)]
+pub fn wsl_path_executables<T: WCStr>(
+    user: Option<&str>,
+    dist: Option<&str>,
+    dirs: &[T],
+) -> Option<Vec<String>> {
+    let mut names = wsl_query_lines(
+        SCRIPT_PATH_EXECUTABLES,
+        &dirs
+            .iter()
+            .filter_map(WCStr::wcstr_as_ref)
+            .collect::<Vec<&str>>(),
+        user,
+        dist,
+    )?;
+
+    // remove duplicated names, keeping the first one on PATH
+    let mut names_found = std::collections::HashSet::new();
+    names.retain(|s| !s.is_empty() && names_found.insert(s.clone()));
+
+    Some(names)
+}
+
+///
+/// Find commands in WSL with `command -v`, with a single WSL query
+///
+/// # Arguments
+///
+/// * `user`    - WSL user to run the query
+/// * `dist`    - WSL distribution to run the query
+/// * `cmdnames` - WSL command names to find
+///
+/// # Return
+///
+/// [`Some`]\(names\) of found commands, or [`None`] if the query failed
+///
+/// # Examples
+///
+/// ```
+/// let found: Option<Vec<String>> = wsl_find_cmds(None, Some("debian"), &["git", "gti"]);
+/// ```
+///
+#[allow(dead_code)]
+pub fn wsl_find_cmds<T: WCStr>(
+    user: Option<&str>,
+    dist: Option<&str>,
+    cmdnames: &[T],
+) -> Option<Vec<String>> {
+    wsl_query_lines(
+        SCRIPT_FIND_CMDS,
+        &cmdnames
+            .iter()
+            .filter_map(WCStr::wcstr_as_ref)
+            .collect::<Vec<&str>>(),
+        user,
+        dist,
+    )
+    .map(|names| names.into_iter().filter(|s| !s.is_empty()).collect())
+}
+
+///
+/// Get one-line descriptions of WSL commands from `whatis`,
+/// with a WSL query per [`MAX_WHATIS_CMDS`] commands
+///
+/// # Arguments
+///
+/// * `user`     - WSL user to run the query
+/// * `dist`     - WSL distribution to run the query
+/// * `cmdnames` - WSL command names to describe
+///
+/// # Return
+///
+/// [`Some`]\(descriptions\) by command name (commands without a manual page are omitted),
+/// or [`None`] if the query failed
+///
+/// # Examples
+///
+/// ```
+/// let descs: Option<HashMap<String, String>> = wsl_whatis(None, None, &["git", "jq"]);
+/// ```
+///
+#[allow(dead_code)]
+pub fn wsl_whatis<T: WCStr>(
+    user: Option<&str>,
+    dist: Option<&str>,
+    cmdnames: &[T],
+) -> Option<HashMap<String, String>> {
+    let cmdnames: Vec<&str> = cmdnames.iter().filter_map(WCStr::wcstr_as_ref).collect();
+
+    cmdnames
+        .chunks(MAX_WHATIS_CMDS)
+        .try_fold(HashMap::new(), |mut descs, chunk| {
+            let lines = wsl_query_lines(SCRIPT_WHATIS, chunk, user, dist)?;
+            descs.extend(parse_whatis(&lines, chunk));
+            Some(descs)
+        })
+}
+
+// parse output lines of whatis, keeping the first description of each command
+//   Ex) "git (1)              - the stupid content tracker" -> ("git", "the stupid content tracker")
+fn parse_whatis(lines: &[String], cmdnames: &[&str]) -> HashMap<String, String> {
+    let mut descs = HashMap::new();
+
+    lines
+        .iter()
+        .filter_map(|line| {
+            let (name, desc) = line.split_once(" - ")?;
+            let name = name.split(" (").next()?.trim();
+            Some((name, desc.trim()))
+        })
+        // whatis may also print pages of other names (Ex: 'git-lfs' for 'git')
+        .filter(|(name, desc)| !desc.is_empty() && cmdnames.contains(name))
+        .for_each(|(name, desc)| {
+            descs
+                .entry(name.to_string())
+                .or_insert_with(|| desc.to_string());
+        });
+
+    descs
+}
+
+///
+/// Get command names similar to the given name, to suggest for a typo
+///
+/// Names within a small edit distance (insertion, deletion, substitution
+/// and transposition of characters) are returned, the closest first.
+///
+/// # Arguments
+///
+/// * `cmdname`    - A command name which is not found
+/// * `candidates` - Existing command names (Ex: executables on WSL PATH)
+///
+/// # Return
+///
+/// Similar names, at most [`MAX_SIMILAR_CMDS`]
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(similar_cmds("gti", &["git".to_string(), "vim".to_string()]), vec!["git"]);
+/// ```
+///
+#[allow(dead_code)]
+pub fn similar_cmds(cmdname: &str, candidates: &[String]) -> Vec<String> {
+    let max_distance = std::cmp::max(1, cmdname.chars().count() / 3);
+
+    let mut similar: Vec<(usize, &String)> = candidates
+        .iter()
+        .filter(|s| s.as_str() != cmdname)
+        .map(|s| (edit_distance(cmdname, s), s))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    similar.sort();
+    similar.dedup_by(|a, b| a.1 == b.1);
+
+    similar
+        .into_iter()
+        .take(MAX_SIMILAR_CMDS)
+        .map(|(_, s)| s.clone())
+        .collect()
+}
+
+// get edit distance between two strs, counting transposition of adjacent chars as one edit
+//   Ex) ("gti", "git") -> 1, ("gti", "gdb") -> 2
+fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    (0..=a.len()).for_each(|i| d[i][0] = i);
+    (0..=b.len()).for_each(|j| d[0][j] = j);
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = (a[i - 1] != b[j - 1]) as usize;
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    d[a.len()][b.len()]
+}
+
+// run shell script inside WSL with given args, then get lines of its stdout
+// returns None if failed to execute, or script exited with non-zero
+fn wsl_query_lines(
+    script: &str,
+    args: &[&str],
+    user: Option<&str>,
+    dist: Option<&str>,
+) -> Option<Vec<String>> {
+    WslCmd::new("sh")?
+        // sh -c '<script>' sh '<arg1>' '<arg2>' ...
+        .args(
+            &["-c", &sh_quote(script), "sh"]
+                .iter()
+                .map(|s| s.to_string())
+                .chain(args.iter().map(|s| sh_quote(s)))
+                .collect::<Vec<String>>(),
+            false,
+        )
+        .user(user)
+        .dist(dist)
+        // capture output without printing
+        .execute_with_stdin(Some(""))
+        .ok()?
+        .stdout
+        .map_or(Some(vec![]), |stdout| {
+            Some(
+                String::from_utf8_lossy(&stdout)
+                    .lines()
+                    .map(|s| s.trim_end_matches('\r').to_string())
+                    .collect(),
+            )
+        })
+}
+
+// quote str for WSL shell
+//   Ex) it's -> 'it'\''s'
+fn sh_quote(s: &str) -> String {
+    format!("'{}'", s.replace("'", r"'\''"))
+}
+
+#[cfg(test)]
+/// For module test
+mod test {
+    use super::{parse_whatis, similar_cmds, wsl_find_cmds, wsl_path_executables, wsl_whatis};
+
+    #[test]
+    #[cfg_attr(not(target_os = "windows"), ignore)] // requires WSL
+    fn test_path_executables() {
+        let cmds = wsl_path_executables(None, None, &["/bin"]).expect("Query executables");
+        dbg!(&cmds);
+
+        assert!(cmds.iter().any(|s| s == "sh"));
+    }
+
+    #[test]
+    #[cfg_attr(not(target_os = "windows"), ignore)] // requires WSL
+    fn test_find_cmds() {
+        let found =
+            wsl_find_cmds(None, None, &["sh", "wslcmd-no-such-cmd"]).expect("Query commands");
+
+        assert_eq!(found, vec!["sh"]);
+    }
+
+    #[test]
+    #[cfg_attr(not(target_os = "windows"), ignore)] // requires WSL
+    fn test_whatis() {
+        let descs = wsl_whatis(None, None, &["sh", "wslcmd-no-such-cmd"]).expect("Query whatis");
+        dbg!(&descs);
+
+        assert!(!descs.contains_key("wslcmd-no-such-cmd"));
+    }
+
+    #[test]
+    fn test_parse_whatis() {
+        let lines: Vec<String> = [
+            "git (1)              - the stupid content tracker",
+            "jq (1) - Command-line JSON processor",
+            "jq (1posix) - other section",
+            "git-lfs (1) - Git extension for versioning large files",
+            "vim: nothing appropriate.",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let descs = parse_whatis(&lines, &["git", "jq", "vim"]);
+
+        assert_eq!(descs.len(), 2);
+        assert_eq!(descs["git"], "the stupid content tracker");
+        assert_eq!(descs["jq"], "Command-line JSON processor");
+    }
+
+    #[test]
+    fn test_similar_cmds() {
+        let candidates: Vec<String> = ["git", "gdb", "gitk", "vim", "make", "cmake", "gti"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        assert_eq!(similar_cmds("gti", &candidates), vec!["git"]);
+        assert_eq!(similar_cmds("gt", &candidates), vec!["git", "gti"]);
+        assert_eq!(similar_cmds("mkae", &candidates), vec!["make"]);
+        assert_eq!(similar_cmds("cmkae", &candidates), vec!["cmake"]);
+        assert!(similar_cmds("emacs", &candidates).is_empty());
+    }
+}
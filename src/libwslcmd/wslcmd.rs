@@ -1,5 +1,6 @@
 use derive_getters::Getters;
 
+use super::wsl_launcher::WslLauncherRef;
 use super::WCPath;
 use super::WCStr;
 
@@ -34,6 +35,10 @@ pub struct WslCmd {
     /// Execute as a detached background process. Useful for GUI binaries.
     #[getter(rename = "get_is_detached")]
     is_detached_proc: bool,
+
+    /// Launcher to spawn WSL process
+    #[getter(skip)]
+    launcher: WslLauncherRef,
 }
 
 impl WslCmd {
@@ -69,6 +74,7 @@ impl WslCmd {
                 username: None,        // default
                 distribution: None,    // default
                 envfiles: [].to_vec(), // default
+                launcher: WslLauncherRef::default(),
             }
         })
     }
@@ -181,6 +187,32 @@ impl WslCmd {
         self
     }
 
+    ///
+    /// Set launcher to spawn WSL process of [`WslCmd`]
+    ///
+    /// # Arguments
+    ///
+    /// * `launcher` - Launcher to spawn `wsl` (Default: `wsl.exe`)
+    ///
+    /// # Return
+    ///
+    /// Self [`WslCmd`] after setting launcher
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let wslcmd: WslCmd = WslCmd::new("command")
+    ///            .expect("New WslCmd")
+    ///            .launcher(WslLauncherRef::new(Rc::new(ShLauncher::new())));
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn launcher(mut self, launcher: WslLauncherRef) -> Self {
+        self.launcher = launcher;
+
+        self
+    }
+
     ///
     /// Execute [`WslCmd`].
     ///
@@ -232,62 +264,15 @@ impl WslCmd {
     #[allow(dead_code)]
     pub fn execute_with_stdin(&self, stdin_input: Option<&str>) -> WslCmdResult {
         use std::io::Write;
-        use std::os::windows::process::CommandExt;
-        use std::process::{Command, Stdio};
-
-        // build and execute command, then get exit code
-        Command::new("wsl")
-            // append arg: username
-            .args({
-                match self.username.as_deref() {
-                    Some(user) => vec!["-u", user], // user defined: additional args
-                    None => vec![],                 // user not defined: no args
-                }
-            })
-            // append arg: distribution
-            .args({
-                match self.distribution.as_deref() {
-                    Some(dist) => vec!["-d", dist], // user defined: additional args
-                    None => vec![],                 // user not defined: no args
-                }
-            })
-            // append arg: start wsl shell commands
-            .arg("--")
-            // append args: load env vars
-            .args(Self::buildcmd_load_envfile_if_exists(
-                // load '/etc/profile', '$HOME/.profile', and files in 'self.envfiles'
-                ["/etc/profile", "$HOME/.profile"]
-                    .iter()
-                    .map(|s| *s)
-                    .chain(self.envfiles.iter().map(|s| s.as_str())),
-            ))
-            // append arg: append wsl command
-            .arg(&self.command)
-            // append args: wsl command args
-            .args(&self.args)
-            // set flag: create as normal mode or detached mode
-            .creation_flags({
-                match self.is_detached_proc {
-                    // https://docs.microsoft.com/en-us/windows/win32/procthread/process-creation-flags
-                    true => 0x08000000,  // detached mode flag - CREATE_NO_WINDOW
-                    false => 0x00000000, // normal mode flag - RESET
-                }
-            })
-            // set stdio - if stdin_input exists, set all to piped
-            .stdin(match stdin_input {
-                Some(_) => Stdio::piped(),
-                None => Stdio::inherit(),
-            })
-            .stdout(match stdin_input {
-                Some(_) => Stdio::piped(),
-                None => Stdio::inherit(),
-            })
-            .stderr(match stdin_input {
-                Some(_) => Stdio::piped(),
-                None => Stdio::inherit(),
-            })
-            // execute command as a bg child process first (attached later if needed)
-            .spawn() // Result<Child>
+
+        // spawn wsl process as a bg child process first (attached later if needed)
+        // if stdin_input exists, set all stdio to piped
+        self.launcher
+            .spawn(
+                &self.wsl_args(),
+                self.is_detached_proc,
+                stdin_input.is_some(),
+            ) // Result<Child>
             // handle with process exit status
             .map_or(WslCmdExitStatus::err(), |mut child| {
                 match self.is_detached_proc {
@@ -302,7 +287,7 @@ impl WslCmd {
                                 .stdin
                                 .take()
                                 // write to stdin if available
-                                .map_or(None, |mut child_stdin| {
+                                .and_then(|mut child_stdin| {
                                     child_stdin.write_all(stdin_input_str.as_bytes()).ok()
                                 })
                                 // if error during previous jobs, return with the error
@@ -312,12 +297,61 @@ impl WslCmd {
                         // wait and extract results
                         child
                             .wait_with_output()
-                            .map_or(WslCmdExitStatus::err(), |o| WslCmdExitStatus::new(o))
+                            .map_or(WslCmdExitStatus::err(), WslCmdExitStatus::new)
                     }
                 }
             })
     }
 
+    ///
+    /// Build arguments of `wsl` to execute [`WslCmd`]
+    ///
+    /// # Return
+    ///
+    /// [`Vec`] of `wsl` arguments: user and distribution options,
+    /// then WSL shell cmdline (after `--`) which loads envfiles and executes the command
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let wsl_args: Vec<String> = WslCmd::new("ls")
+    ///            .expect("New WslCmd")
+    ///            .user("ubuntuuser")
+    ///            .wsl_args();
+    /// // ["-u", "ubuntuuser", "--", "if", "test", "-r", "/etc/profile", ..., "ls"]
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn wsl_args(&self) -> Vec<String> {
+        // arg: username
+        match self.username.as_deref() {
+            Some(user) => vec!["-u", user], // user defined: additional args
+            None => vec![],                 // user not defined: no args
+        }
+        .into_iter()
+        // arg: distribution
+        .chain(match self.distribution.as_deref() {
+            Some(dist) => vec!["-d", dist], // user defined: additional args
+            None => vec![],                 // user not defined: no args
+        })
+        // arg: start wsl shell commands
+        .chain(Some("--"))
+        // args: load env vars
+        .chain(Self::buildcmd_load_envfile_if_exists(
+            // load '/etc/profile', '$HOME/.profile', and files in 'self.envfiles'
+            ["/etc/profile", "$HOME/.profile"]
+                .iter()
+                .copied()
+                .chain(self.envfiles.iter().map(|s| s.as_str())),
+        ))
+        // arg: wsl command
+        .chain(Some(self.command.as_str()))
+        // args: wsl command args
+        .chain(self.args.iter().map(|s| s.as_str()))
+        .map(String::from)
+        .collect()
+    }
+
     // parse command name, to get (detached mode, command)
     // returns None if error (failed to get basename, command name is empty, ...)
     fn parse_cmd<T: WCPath>(binname: &T) -> Option<(String, bool)> {
@@ -408,6 +442,7 @@ pub struct WslCmdExitStatus {
     pub stdout: Option<Vec<u8>>,
 
     /// Printed stderr
+    #[allow(dead_code)]
     pub stderr: Option<Vec<u8>>,
 }
 
@@ -470,9 +505,12 @@ impl WslCmdExitStatus {
 #[cfg(test)]
 /// For module test
 mod test {
+    use super::super::wsl_launcher::{ShLauncher, WslLauncherRef};
     use super::{WslCmd, WslCmdExitStatus, DETACHED_PROC_PREFIX};
+    use std::rc::Rc;
 
     #[test]
+    #[cfg_attr(not(target_os = "windows"), ignore)] // requires WSL
     fn test_execute_true() {
         // create WslCmd & run test
         WslCmd::new("true")
@@ -482,6 +520,7 @@ mod test {
     }
 
     #[test]
+    #[cfg_attr(not(target_os = "windows"), ignore)] // requires WSL
     fn test_execute_false() {
         // create WslCmd & run test
         WslCmd::new("false")
@@ -491,6 +530,7 @@ mod test {
     }
 
     #[test]
+    #[cfg_attr(not(target_os = "windows"), ignore)] // requires WSL
     fn test_execute_false_detached() {
         // create WslCmd & run test
         WslCmd::new(format!("{}false", DETACHED_PROC_PREFIX))
@@ -500,6 +540,7 @@ mod test {
     }
 
     #[test]
+    #[cfg_attr(not(target_os = "windows"), ignore)] // requires WSL
     fn test_execute_wslpath() {
         // create WslCmd & run test
         WslCmd::new("command")
@@ -523,6 +564,7 @@ mod test {
     }
 
     #[test]
+    #[cfg_attr(not(target_os = "windows"), ignore)] // requires WSL
     fn test_execute_cat_with_stdin() {
         const INPUT: &str = "With cat, stdin and stdout should be the same";
 
@@ -545,10 +587,123 @@ mod test {
             )
             .expect("Execute WslCmd - cat")
             .stdout
-            .filter(|s| String::from_utf8_lossy(&s) == INPUT) // check stdout == stdin
+            .filter(|s| String::from_utf8_lossy(s) == INPUT) // check stdout == stdin
             .expect("Validate WslCmd - cat");
     }
 
+    #[test]
+    fn test_wsl_args() {
+        let wsl_args = WslCmd::new("ls")
+            .expect("New WslCmd")
+            .args(&["-l", "dir\\sub"], true)
+            .user("john")
+            .dist("debian")
+            .envfiles(&["/path/env"])
+            .wsl_args();
+
+        // options, then envfiles loaded in order, then command and converted args
+        let envfile_cmds: Vec<String> = ["/etc/profile", "$HOME/.profile", "/path/env"]
+            .iter()
+            .map(|s| format!("if test -r {} ; then . {} ; fi;", s, s))
+            .collect();
+        assert_eq!(wsl_args[..5].join(" "), "-u john -d debian --");
+        assert_eq!(
+            wsl_args[5..].join(" "),
+            format!("{} ls -l dir/sub", envfile_cmds.join(" "))
+        );
+
+        // no options if user and distribution are not set
+        assert_eq!(WslCmd::new("ls").expect("New WslCmd").wsl_args()[0], "--");
+    }
+
+    #[test]
+    #[cfg_attr(target_os = "windows", ignore)] // requires sh
+    fn test_sh_execute_exit_code() {
+        let (launcher, wslcmd) = sh_wslcmd("true");
+        wslcmd.execute().expect("Execute WslCmd - true");
+        assert_eq!(launcher.get_launched(), vec![wslcmd.wsl_args()]);
+
+        // non-zero exit code is returned as is
+        let (_, wslcmd) = sh_wslcmd("exit");
+        let exit_status = wslcmd
+            .args(&["3"], false)
+            .execute_with_stdin(Some(""))
+            .expect_err("Execute WslCmd - exit 3");
+        assert_eq!(exit_status.code, Some(3));
+
+        // detached: succeeded if executed
+        let (_, wslcmd) = sh_wslcmd(&format!("{}false", DETACHED_PROC_PREFIX));
+        wslcmd.execute().expect("Execute WslCmd - false (detached)");
+    }
+
+    #[test]
+    #[cfg_attr(target_os = "windows", ignore)] // requires sh
+    fn test_sh_execute_cat_with_stdin() {
+        const INPUT: &str = "With cat, stdin and stdout should be the same";
+
+        let (_, wslcmd) = sh_wslcmd("cat");
+        let stdout = wslcmd
+            .execute_with_stdin(Some(INPUT))
+            .expect("Execute WslCmd - cat")
+            .stdout
+            .expect("Stdout of cat");
+
+        assert_eq!(String::from_utf8_lossy(&stdout), INPUT);
+    }
+
+    #[test]
+    #[cfg_attr(target_os = "windows", ignore)] // requires sh
+    fn test_sh_execute_envfiles() {
+        let envfile =
+            std::env::temp_dir().join(format!("wslcmd-test-envfile-{}", std::process::id()));
+        std::fs::write(&envfile, "export WSLCMD_TEST_ENV=loaded\n").expect("Write envfile");
+
+        // envfile is sourced, and missing envfile is skipped
+        let (_, wslcmd) = sh_wslcmd("printenv");
+        let exit_status = wslcmd
+            .args(&["WSLCMD_TEST_ENV"], false)
+            .envfiles(&[
+                envfile.to_str().expect("Envfile str"),
+                "/wslcmd-no-such-file",
+            ])
+            .execute_with_stdin(Some(""));
+        std::fs::remove_file(&envfile).ok();
+
+        let stdout = exit_status
+            .expect("Execute WslCmd - printenv")
+            .stdout
+            .expect("Stdout of printenv");
+        assert_eq!(String::from_utf8_lossy(&stdout), "loaded\n");
+    }
+
+    #[test]
+    #[cfg_attr(target_os = "windows", ignore)] // requires sh
+    fn test_sh_execute_args_conversion() {
+        // backslashes are converted to '/', and absolute paths are passed through wslpath
+        let (launcher, wslcmd) = sh_wslcmd("echo");
+        let stdout = wslcmd
+            .args(&["dir\\sub", "/abs/path"], true)
+            .execute_with_stdin(Some(""))
+            .expect("Execute WslCmd - echo")
+            .stdout
+            .expect("Stdout of echo");
+
+        assert_eq!(String::from_utf8_lossy(&stdout), "dir/sub /abs/path\n");
+        assert!(launcher.get_launched()[0]
+            .iter()
+            .any(|s| s == "$(wslpath '/abs/path')"));
+    }
+
+    // create WslCmd, launched with local sh
+    fn sh_wslcmd(cmdname: &str) -> (Rc<ShLauncher>, WslCmd) {
+        let launcher = Rc::new(ShLauncher::new());
+        let wslcmd = WslCmd::new(cmdname)
+            .expect("New WslCmd")
+            .launcher(WslLauncherRef::new(launcher.clone()));
+
+        (launcher, wslcmd)
+    }
+
     // print
     fn print_stdout_stderr(e: &WslCmdExitStatus) {
         e.stdout
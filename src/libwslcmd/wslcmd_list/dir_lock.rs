@@ -0,0 +1,167 @@
+use std::fs::{File, TryLockError};
+use std::io;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::super::link_fs::LinkFs;
+
+/// Filename of lock file in link directory
+pub(super) const LOCK_FILENAME: &str = "wslcmd.lock";
+
+/// Interval to retry acquiring the lock
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug)]
+/// Exclusive lock on a link directory, shared by all WslCmd processes.
+/// The lock is released when dropped (or when the process exits).
+pub(super) struct DirLock {
+    /// Opened lock file, which holds the lock ([`None`] if the filesystem is not shared)
+    file: Option<File>,
+
+    /// Path of lock file
+    path: PathBuf,
+}
+
+impl DirLock {
+    // acquire the lock of dir, waiting for other processes until timeout
+    // returns Err(TimedOut) if the lock is still held by others after timeout
+    pub(super) fn acquire(fs: &dyn LinkFs, dir: &Path, timeout: Duration) -> io::Result<Self> {
+        let path = dir.join(LOCK_FILENAME);
+        let file = match fs.open_lock_file(&path)? {
+            Some(file) => file,
+            None => return Ok(Self { file: None, path }),
+        };
+        let started = Instant::now();
+
+        loop {
+            match file.try_lock() {
+                Ok(_) => {
+                    return Ok(Self {
+                        file: Some(file),
+                        path,
+                    })
+                }
+                Err(TryLockError::Error(e)) => return Err(e),
+                Err(TryLockError::WouldBlock) if started.elapsed() >= timeout => {
+                    return Err(Error::new(
+                        ErrorKind::TimedOut,
+                        format!(
+                            "Another wslcmd operation is in progress (lock file: {})",
+                            path.display()
+                        ),
+                    ))
+                }
+                Err(TryLockError::WouldBlock) => thread::sleep(LOCK_RETRY_INTERVAL),
+            }
+        }
+    }
+
+    // acquire the lock of dir only if nobody holds it
+    // returns None if held by others, or failed to open lock file
+    #[allow(dead_code)]
+    pub(super) fn try_acquire(fs: &dyn LinkFs, dir: &Path) -> Option<Self> {
+        Self::acquire(fs, dir, Duration::from_secs(0)).ok()
+    }
+
+    // get path of lock file
+    #[allow(dead_code)]
+    pub(super) fn get_path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for DirLock {
+    fn drop(&mut self) {
+        // lock file itself is kept, so that others waiting on it are not confused
+        self.file.as_ref().map(|file| file.unlock().ok());
+    }
+}
+
+#[cfg(test)]
+/// For module test
+mod test {
+    use super::super::super::link_fs::StdFs;
+    use super::super::test::{clean_tmpdir, copy_tmpbin, init_tmpdir};
+    use super::super::WslCmdList;
+    use super::DirLock;
+    use std::io::ErrorKind;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+    use std::time::{Duration, Instant};
+    use std::{env, fs, thread};
+
+    /// Env var to run the test as a child process holding the lock of the dir
+    const ENV_LOCK_HOLDER: &str = "WSLCMD_TEST_LOCK_HOLDER_DIR";
+
+    /// Time for the child process to hold the lock
+    const HOLD_TIME: Duration = Duration::from_millis(1500);
+
+    #[test]
+    fn test_lock_multiprocess() {
+        const TMPDIR_POSTFIX: &str = "wslcmd-dir-lock";
+
+        // child process: hold the lock for a while, then exit
+        if let Ok(dir) = env::var(ENV_LOCK_HOLDER) {
+            let dir = PathBuf::from(dir);
+            let _lock =
+                DirLock::acquire(&StdFs, &dir, Duration::from_secs(10)).expect("Child lock");
+            fs::write(dir.join("locked"), b"").expect("Child marker");
+            thread::sleep(HOLD_TIME);
+            return;
+        }
+
+        // init tmpdir
+        let tmpdir = init_tmpdir(TMPDIR_POSTFIX).expect("Tmp dir initialize");
+        let (binpath, _) = copy_tmpbin(&tmpdir, None).expect("Bin initialize");
+        let mut wslcmd_list = WslCmdList::new(&binpath).expect("New WslCmdList");
+
+        // spawn this test again as a child process, which holds the lock
+        let test_name = format!(
+            "{}::test_lock_multiprocess",
+            module_path!().splitn(2, "::").nth(1).unwrap_or_default()
+        );
+        let mut child = Command::new(env::current_exe().expect("Test binary"))
+            .args([test_name.as_str(), "--exact", "--test-threads=1"])
+            .env(ENV_LOCK_HOLDER, &tmpdir)
+            .spawn()
+            .expect("Spawn child");
+        wait_for(&tmpdir.join("locked"), Duration::from_secs(10));
+
+        // fail to link while locked by child, and nothing is changed
+        wslcmd_list.set_lock_timeout(Duration::from_millis(100));
+        let e = wslcmd_list
+            .link_wslcmd(&"git")
+            .expect_err("Link while locked");
+        assert_eq!(e.kind(), ErrorKind::TimedOut);
+        assert!(e.to_string().contains("in progress"));
+        assert!(!wslcmd_list.get_cmdlist().contains("git"));
+        assert!(DirLock::try_acquire(&StdFs, &tmpdir).is_none());
+
+        // wait until child releases the lock, then succeed
+        wslcmd_list.set_lock_timeout(Duration::from_secs(10));
+        wslcmd_list
+            .link_wslcmd(&"git")
+            .expect("Link after unlocked");
+        assert!(wslcmd_list.get_cmdlist().contains("git"));
+        assert!(child.wait().expect("Wait child").success());
+
+        // lock is released after operation
+        let lock = DirLock::try_acquire(&StdFs, &tmpdir).expect("Lock after operation");
+        assert!(lock.get_path().exists());
+        drop(lock);
+
+        // clean tmpdir
+        clean_tmpdir(TMPDIR_POSTFIX);
+    }
+
+    // wait until the file is created
+    fn wait_for(path: &Path, timeout: Duration) {
+        let started = Instant::now();
+        while !path.exists() {
+            assert!(started.elapsed() < timeout, "Timed out waiting for child");
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+}
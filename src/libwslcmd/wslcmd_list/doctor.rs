@@ -0,0 +1,448 @@
+use derive_getters::Getters;
+use serde::Serialize;
+use std::ffi::OsStr;
+use std::io;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use super::super::link_fs::FileKind;
+use super::super::WCPath;
+use super::{WslCmdList, BINARY_EXTENSION, DETACHED_PROC_PREFIX};
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+/// Kind of problem of a file in the WslCmd dir
+pub enum LinkIssueKind {
+    /// Detached link (`.cmd.exe`) whose command link (`cmd.exe`) is removed
+    OrphanedDetached,
+
+    /// Command link (`cmd.exe`) whose detached link (`.cmd.exe`) is removed
+    MissingDetached,
+
+    /// Link chain pointing to a different WslCmd binary
+    ForeignBinary,
+
+    /// Link chain pointing to a WslCmd binary which does not exist
+    MissingBinary,
+
+    /// Link to the WslCmd binary, without the binary extension
+    WrongExtension,
+
+    /// Symlink of which the target does not exist
+    DanglingLink,
+
+    /// Link recorded in the manifest, of which all link files are missing
+    MissingLink,
+}
+
+impl LinkIssueKind {
+    ///
+    /// Get name of [`LinkIssueKind`], which is same as the serialized one
+    ///
+    #[allow(dead_code)]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::OrphanedDetached => "orphaned-detached",
+            Self::MissingDetached => "missing-detached",
+            Self::ForeignBinary => "foreign-binary",
+            Self::MissingBinary => "missing-binary",
+            Self::WrongExtension => "wrong-extension",
+            Self::DanglingLink => "dangling-link",
+            Self::MissingLink => "missing-link",
+        }
+    }
+
+    ///
+    /// Get description of [`LinkIssueKind`]
+    ///
+    #[allow(dead_code)]
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::OrphanedDetached => "Detached link without its command link",
+            Self::MissingDetached => "Command link without its detached link",
+            Self::ForeignBinary => "Link chain pointing to a different WslCmd binary",
+            Self::MissingBinary => "Link chain pointing to a missing WslCmd binary",
+            Self::WrongExtension => "Link to WslCmd binary with a wrong extension",
+            Self::DanglingLink => "Dangling symlink",
+            Self::MissingLink => "Link in the manifest, of which link files are missing",
+        }
+    }
+
+    ///
+    /// Check if the issue can be fixed by [`WslCmdList::fix_issue`]
+    ///
+    #[allow(dead_code)]
+    pub fn is_fixable(&self) -> bool {
+        !matches!(self, Self::ForeignBinary)
+    }
+}
+
+#[derive(Getters, Serialize, Debug, Clone)]
+/// A problem of a file in the WslCmd dir, found by [`WslCmdList::diagnose`]
+pub struct LinkIssue {
+    /// Kind of the issue
+    #[getter(rename = "get_kind")]
+    kind: LinkIssueKind,
+
+    /// Path of the problematic file
+    #[getter(rename = "get_path")]
+    path: PathBuf,
+
+    /// Target of the problematic link, after following the link chain
+    #[getter(rename = "get_target")]
+    target: Option<PathBuf>,
+}
+
+impl WslCmdList {
+    ///
+    /// Scan the link dir and find problematic links related to WslCmd
+    ///
+    /// # Return
+    ///
+    /// List of found issues, sorted by path
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let issues: Vec<LinkIssue> = wslcmd_list.diagnose();
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn diagnose(&self) -> Vec<LinkIssue> {
+        let mut issues: Vec<LinkIssue> = self
+            .fs
+            .read_dir(&self.linkdir)
+            .unwrap_or_default()
+            .into_iter()
+            // only symlinks, other than the binary itself
+            .filter(|(pb, kind)| {
+                *kind == FileKind::Symlink && pb.wcpath_filename() != self.binpath.wcpath_filename()
+            })
+            .filter_map(|(pb, _)| self.diagnose_link(pb))
+            // links in manifest, of which link files are missing
+            .chain(
+                self.manifest
+                    .get_links()
+                    .keys()
+                    .filter_map(|linkname| self.cmd_pathbuf(linkname).ok())
+                    .filter(|pb_cmd| self.missing_linkname(pb_cmd).is_some())
+                    .map(|pb_cmd| LinkIssue {
+                        kind: LinkIssueKind::MissingLink,
+                        path: pb_cmd,
+                        target: None,
+                    }),
+            )
+            .collect();
+        issues.sort_by(|a, b| a.path.cmp(&b.path));
+
+        issues
+    }
+
+    ///
+    /// Fix an issue found by [`diagnose`](Self::diagnose), by repairing or removing the link
+    ///
+    /// # Arguments
+    ///
+    /// * `issue` - An issue to fix
+    ///
+    /// # Return
+    ///
+    /// [`Ok`]\([`()`](unit)\) if succeeded, [`Err`]\([`Error`]\) if failed or not fixable
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// for issue in wslcmd_list.diagnose() {
+    ///     wslcmd_list.fix_issue(&issue).ok();
+    /// }
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn fix_issue(&mut self, issue: &LinkIssue) -> io::Result<()> {
+        self.with_journal("fix", |this| {
+            match issue.kind {
+                // remove link, with its manifest entry if no link file remains
+                LinkIssueKind::OrphanedDetached | LinkIssueKind::DanglingLink => this
+                    .fs
+                    .remove_file(&issue.path)
+                    .and_then(|_| match this.missing_linkname(&cmd_path(&issue.path)) {
+                        Some(linkname) => this.forget_missing_link(&linkname),
+                        None => Ok(()),
+                    }),
+
+                // remove link
+                LinkIssueKind::WrongExtension => this.fs.remove_file(&issue.path),
+
+                // re-create link files of the link in manifest
+                LinkIssueKind::MissingLink => this.relink_missing(&issue.path),
+
+                // re-create detached link to the binary
+                LinkIssueKind::MissingDetached => this.relink_detached(&issue.path),
+
+                // remove detached link, then re-create it to the binary
+                LinkIssueKind::MissingBinary => this
+                    .fs
+                    .remove_file(&detached_path(&issue.path)?)
+                    .and_then(|_| this.relink_detached(&issue.path)),
+
+                // links of other binaries are not touched
+                LinkIssueKind::ForeignBinary => {
+                    Err(Error::other("Links of other WslCmd binary cannot be fixed"))
+                }
+            }
+            // refresh wslcmd list if succeeded
+            .map(|_| {
+                this.refresh_wslcmd_list(true);
+            })
+        })
+    }
+
+    // check if the symlink has a problem
+    pub(super) fn diagnose_link(&self, pb_link: PathBuf) -> Option<LinkIssue> {
+        let new_issue = |kind, target| {
+            Some(LinkIssue {
+                kind,
+                path: pb_link.clone(),
+                target,
+            })
+        };
+
+        let filename = pb_link.wcpath_filename()?;
+        let pb_target = pb_link.with_file_name(self.fs.read_link(&pb_link).ok()?);
+        let pb_canonical = self.fs.canonicalize(&pb_link).ok();
+
+        match (
+            pb_link.extension().and_then(OsStr::to_str) == Some(BINARY_EXTENSION),
+            filename.strip_prefix(DETACHED_PROC_PREFIX),
+        ) {
+            // non-binary extension: problem only if pointing to the binary
+            (false, _) => match pb_canonical == Some(self.orig_binpath.clone()) {
+                true => new_issue(LinkIssueKind::WrongExtension, pb_canonical),
+                false => None,
+            },
+
+            // detached link
+            (true, Some(filename_cmd)) => match pb_canonical {
+                // pointing to the binary: problem if command link does not exist
+                Some(pb) if pb == self.orig_binpath => {
+                    match self.fs.is_symlink(&pb_link.with_file_name(filename_cmd))
+                        || self.is_detached_only(filename_cmd)
+                    {
+                        true => None,
+                        false => new_issue(LinkIssueKind::OrphanedDetached, Some(pb)),
+                    }
+                }
+                // links of other binaries
+                Some(_) => None,
+                // dangling: problem of command link if exists
+                None => match self.fs.is_symlink(&pb_link.with_file_name(filename_cmd))
+                    || !self.is_wslcmd_link(&pb_link, &pb_target)
+                {
+                    true => None,
+                    false => new_issue(LinkIssueKind::DanglingLink, Some(pb_target)),
+                },
+            },
+
+            // command link, following the chain (cmd -> .cmd -> bin)
+            (true, None) if pb_target == detached_path(&pb_link).ok()? => {
+                match (self.fs.is_symlink(&pb_target), pb_canonical) {
+                    // detached link does not exist
+                    (false, _) => new_issue(LinkIssueKind::MissingDetached, Some(pb_target)),
+                    // valid link chain
+                    (true, Some(pb)) if pb == self.orig_binpath => None,
+                    // link chain of another binary
+                    (true, Some(pb)) => new_issue(LinkIssueKind::ForeignBinary, Some(pb)),
+                    // binary does not exist
+                    (true, None) => new_issue(
+                        LinkIssueKind::MissingBinary,
+                        self.fs
+                            .read_link(&pb_target)
+                            .ok()
+                            .map(|pb| pb_target.with_file_name(pb)),
+                    ),
+                }
+            }
+
+            // other symlinks: problem if dangling, and created by WslCmd
+            // (symlinks of the user or other programs are not touched)
+            (true, None) => {
+                match pb_canonical.is_none() && self.is_wslcmd_link(&pb_link, &pb_target) {
+                    true => new_issue(LinkIssueKind::DanglingLink, Some(pb_target)),
+                    false => None,
+                }
+            }
+        }
+    }
+
+    // check if the symlink was created by WslCmd:
+    // recorded in the manifest, or pointing to a WslCmd binary or its detached link
+    fn is_wslcmd_link(&self, pb_link: &Path, pb_target: &Path) -> bool {
+        let linkname = pb_link
+            .wcpath_basename()
+            .map(|s| s.trim_start_matches(DETACHED_PROC_PREFIX));
+        let target_filename = pb_target.wcpath_filename();
+
+        linkname.is_some_and(|s| self.manifest.get(s).is_some())
+            || target_filename.is_some_and(|s| {
+                Some(s) == self.binpath.wcpath_filename()
+                    || Some(s) == self.orig_binpath.wcpath_filename()
+                    || detached_path(pb_link).is_ok_and(|pb| pb == pb_target)
+            })
+    }
+
+    // check if the link is recorded as detached-only, which has no command link
+    fn is_detached_only(&self, filename_cmd: &str) -> bool {
+        Path::new(filename_cmd)
+            .file_stem()
+            .and_then(OsStr::to_str)
+            .and_then(|s| self.manifest.get(s))
+            .is_some_and(|meta| !meta.get_detached().has_link())
+    }
+
+    // re-create link files of the missing link, with its kind and detached mode in manifest
+    fn relink_missing(&mut self, pb_cmd: &Path) -> io::Result<()> {
+        let meta = {
+            self.missing_linkname(pb_cmd)
+                .and_then(|linkname| self.manifest.get(&linkname))
+                .ok_or(Error::new(ErrorKind::NotFound, "Link is not missing"))?
+        };
+
+        self.create_link_chain(pb_cmd, *meta.get_kind(), *meta.get_detached())
+            // save manifest, after unflagged as missing
+            .and_then(|_| {
+                self.refresh_wslcmd_list(true);
+                self.manifest.save()
+            })
+    }
+
+    // create detached link of the command link, pointing to the binary
+    fn relink_detached(&self, pb_cmd: &Path) -> io::Result<()> {
+        self.fs.symlink_file(
+            // target: origbin (relative if possible)
+            &self.bin_link_target()?,
+            // symlink file: wslcmd_detached (absolute)
+            &detached_path(pb_cmd)?,
+        )
+    }
+}
+
+// get path of command link of the link (Ex: '.cmd.exe' -> 'cmd.exe')
+fn cmd_path(pb_link: &Path) -> PathBuf {
+    pb_link
+        .wcpath_filename()
+        .map_or(pb_link.to_path_buf(), |s| {
+            pb_link.with_file_name(s.trim_start_matches(DETACHED_PROC_PREFIX))
+        })
+}
+
+// get path of detached link of the command link
+pub(super) fn detached_path(pb_cmd: &Path) -> io::Result<PathBuf> {
+    pb_cmd
+        .wcpath_filename()
+        .map(|s| pb_cmd.with_file_name(wslcmd_detached_bin!(s)))
+        .ok_or(Error::new(ErrorKind::InvalidInput, "Invalid cmdname"))
+}
+
+#[cfg(test)]
+/// For module test
+mod test {
+    use super::super::super::link_fs::LinkFs;
+    use super::super::test::{clean_tmpdir, copy_tmpbin, init_mem_fs, init_tmpdir};
+    use super::super::WslCmdList;
+    use super::{detached_path, LinkIssueKind};
+    use std::fs;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_diagnose_and_fix() {
+        const TMPDIR_POSTFIX: &str = "wslcmd-doctor";
+
+        // init tmpdir
+        let tmpdir = init_tmpdir(TMPDIR_POSTFIX).expect("Tmp dir initialize");
+        let (bin1, _) = copy_tmpbin(&tmpdir, None).expect("Bin initialize");
+        let mut wslcmd_list = WslCmdList::new(&bin1).expect("New WslCmdList");
+        assert!(wslcmd_list.diagnose().is_empty());
+
+        // break links
+        for cmd in &["orphan", "missing", "valid"] {
+            wslcmd_list.link_wslcmd(cmd).expect("Link");
+        }
+        fs::remove_file(tmpdir.join("orphan.exe")).expect("Break link");
+        fs::remove_file(detached_path(&tmpdir.join("missing.exe")).unwrap()).expect("Break link");
+
+        // check found issues
+        let issues = wslcmd_list.diagnose();
+        dbg!(&issues);
+        assert_eq!(
+            issues.iter().map(|i| *i.get_kind()).collect::<Vec<_>>(),
+            vec![
+                LinkIssueKind::OrphanedDetached,
+                LinkIssueKind::MissingDetached
+            ]
+        );
+
+        // fix, then check if all fixed
+        for issue in &issues {
+            wslcmd_list.fix_issue(issue).expect("Fix issue");
+        }
+        assert!(wslcmd_list.diagnose().is_empty());
+        assert!(wslcmd_list.get_cmdlist().contains("missing"));
+        assert!(!wslcmd_list.get_cmdlist().contains("orphan"));
+
+        // clean tmpdir
+        clean_tmpdir(TMPDIR_POSTFIX);
+    }
+
+    #[test]
+    fn test_mem_fs_dangling() {
+        let (fs, binpath, linkdir) = init_mem_fs();
+        let mut wslcmd_list =
+            WslCmdList::with_fs(&binpath, &linkdir, fs.clone()).expect("New WslCmdList");
+
+        // dangling symlinks of WslCmd: to a missing binary, and to a missing detached link
+        // dangling symlinks of others: to any other missing file
+        let symlink = |link: &str, target: PathBuf| {
+            fs.symlink_file(&target, &linkdir.join(link))
+                .expect("Symlink");
+        };
+        symlink(
+            "stale.exe",
+            PathBuf::from("..").join("old").join("wslcmd.exe"),
+        );
+        symlink(
+            ".orphan.exe",
+            PathBuf::from("..").join("old").join("wslcmd.exe"),
+        );
+        symlink(
+            "tool.exe",
+            PathBuf::from("..").join("other").join("tool.exe"),
+        );
+        symlink(
+            ".hidden.exe",
+            PathBuf::from("..").join("other").join("hidden.exe"),
+        );
+        symlink("notes.txt", PathBuf::from("missing.txt"));
+
+        let issues = wslcmd_list.diagnose();
+        assert_eq!(
+            issues
+                .iter()
+                .map(|i| (*i.get_kind(), i.get_path().clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (LinkIssueKind::DanglingLink, linkdir.join(".orphan.exe")),
+                (LinkIssueKind::DanglingLink, linkdir.join("stale.exe")),
+            ]
+        );
+
+        // fix: only dangling symlinks of WslCmd are removed
+        for issue in &issues {
+            wslcmd_list.fix_issue(issue).expect("Fix issue");
+        }
+        assert!(wslcmd_list.diagnose().is_empty());
+        assert!(!fs.entry_exists(&linkdir.join("stale.exe")));
+        assert!(["tool.exe", ".hidden.exe", "notes.txt"]
+            .iter()
+            .all(|s| fs.is_symlink(&linkdir.join(s))));
+    }
+}
@@ -0,0 +1,237 @@
+use std::ffi::OsStr;
+use std::io;
+use std::io::{Error, ErrorKind};
+use std::path::PathBuf;
+
+use super::super::{WCPath, WslCmdManifest};
+use super::dir_lock::LOCK_FILENAME;
+use super::{LinkResults, WslCmdList};
+
+/// Name of the environment file loaded on execution, inside the wslcmd exe dir
+const PROFILE_FILENAME: &str = "profile";
+
+impl WslCmdList {
+    ///
+    /// Install the binary to another directory, and re-create all current links there
+    ///
+    /// The binary is copied with the same filename, along with the `profile` environment file
+    /// if it exists (and not in the directory yet). Then links are re-created with their metadata (prefix, description, kind, detached mode
+    /// and tags). Current binary and links are left as they were.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - A directory to install (created if not exists)
+    ///
+    /// # Return
+    ///
+    /// [`Ok`]\(([`WslCmdList`], [`Vec`])\) of the installed binary,
+    /// and (link name, result) of each link sorted by link name.
+    /// If failed to re-create a link, other links are still re-created.
+    ///
+    /// [`Err`]\([`Error`]\) if the directory is current one, or failed to copy the binary
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let (wslcmd_list_new, results) = wslcmd_list.install_to(&"D:\\WslCmd\\bin")?;
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn install_to<T: WCPath>(&mut self, dir: &T) -> io::Result<(WslCmdList, LinkResults)> {
+        let dir = dir
+            .wcpath_clone_to_pathbuf()
+            .ok_or(Error::new(ErrorKind::InvalidInput, "Invalid directory"))?;
+        let binpath_new = self
+            .binpath
+            .wcpath_filename()
+            .map(|s| dir.join(s))
+            .ok_or(Error::new(ErrorKind::InvalidInput, "Invalid binary name"))?;
+        let dir_canonical = self.fs.canonicalize(&dir).ok();
+        [Some(self.linkdir.as_path()), self.binpath.wcpath_parent()]
+            .iter()
+            .flatten()
+            .all(|p| dir_canonical.is_none() || self.fs.canonicalize(p).ok() != dir_canonical)
+            .then_some(())
+            .ok_or(Error::new(
+                ErrorKind::InvalidInput,
+                "Already installed in the directory",
+            ))?;
+
+        // copy the binary and profile next to it, then load the list of it
+        self.fs.create_dir_all(&dir)?;
+        self.fs.copy_new(&self.orig_binpath, &binpath_new)?;
+        let profile = self.orig_binpath.with_file_name(PROFILE_FILENAME);
+        let profile_new = dir.join(PROFILE_FILENAME);
+        let profile_copied = match self.fs.is_file(&profile) && !self.fs.entry_exists(&profile_new)
+        {
+            true => self
+                .fs
+                .copy_new(&profile, &profile_new)
+                .map(|_| Some(profile_new)),
+            false => Ok(None),
+        }
+        // if failed, remove the copied binary
+        .inspect_err(|_| {
+            self.fs.remove_file(&binpath_new).ok();
+        })?;
+        let mut wslcmd_list_new = Self::with_fs_ref(&binpath_new, &dir, self.fs.clone())
+            .ok_or(Error::new(
+                ErrorKind::InvalidData,
+                "Failed to load WslCmd list of the installed binary",
+            ))
+            // if failed, remove the copied binary and profile
+            .inspect_err(|_| {
+                profile_copied.iter().for_each(|pb| {
+                    self.fs.remove_file(pb).ok();
+                });
+                self.fs.remove_file(&binpath_new).ok();
+            })?;
+
+        // re-create each link, as one operation of the new journal
+        let link_set = self.export_link_set();
+        let results = wslcmd_list_new.with_journal("install", |this| {
+            Ok(link_set
+                .get_links()
+                .iter()
+                .map(|entry| {
+                    let result = this.link_wslcmd_with(&entry.cmdname(), &entry.link_options());
+                    (entry.get_name().clone(), result)
+                })
+                .collect())
+        })?;
+
+        Ok((wslcmd_list_new, results))
+    }
+
+    ///
+    /// Uninstall: remove all links of the binary, then its config files if `purge` is set
+    ///
+    /// Backups of the links are restored. Config files are the manifest, list index and journal
+    /// of the binary, and also the lock file and the `profile` file next to the binary,
+    /// if no other binary uses the link directory.
+    /// The binary itself is not removed, as it may be running.
+    ///
+    /// # Arguments
+    ///
+    /// * `purge` - Remove config files of the binary
+    ///
+    /// # Return
+    ///
+    /// [`Ok`]\([`Vec`]\) of removed config files,
+    /// [`Err`]\([`Error`]\) if failed to unlink any link (nothing is unlinked), or remove config files
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let removed = wslcmd_list.uninstall(true)?;
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn uninstall(&mut self, purge: bool) -> io::Result<Vec<PathBuf>> {
+        let linknames: Vec<String> = self.get_cmdlist().iter().cloned().collect();
+        self.with_journal("uninstall", |this| this.unlink_wslcmd_batch(&linknames))?;
+        if !purge {
+            return Ok(vec![]);
+        }
+
+        // lock file and profile are shared by all binaries of the link dir
+        let manifest_path = self.manifest.get_path().clone();
+        let is_shared = self
+            .fs
+            .read_dir(&self.linkdir)
+            .unwrap_or_default()
+            .into_iter()
+            .any(|(pb, _)| {
+                pb != manifest_path
+                    && pb.extension().and_then(OsStr::to_str) == Some(WslCmdManifest::EXTENSION)
+            });
+        let mut paths = vec![manifest_path, self.index_path(), self.journal_path()];
+        if !is_shared {
+            paths.push(self.linkdir.join(LOCK_FILENAME));
+
+            // profile is next to the binary, which may serve other link dirs
+            let profile_path = self.binpath.with_file_name(PROFILE_FILENAME);
+            if profile_path.parent() == Some(self.linkdir.as_path()) {
+                paths.push(profile_path);
+            }
+        }
+
+        paths
+            .into_iter()
+            .filter(|pb| self.fs.entry_exists(pb))
+            .map(|pb| self.fs.remove_file(&pb).map(|_| pb))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+/// For module test
+mod test {
+    use super::super::super::link_fs::LinkFs;
+    use super::super::super::LinkKind;
+    use super::super::test::init_mem_fs;
+    use super::super::{LinkOptions, WslCmdList};
+
+    #[test]
+    fn test_mem_fs_install() {
+        let (fs, binpath, linkdir) = init_mem_fs();
+        let mut wslcmd_list =
+            WslCmdList::with_fs(&binpath, &linkdir, fs.clone()).expect("New WslCmdList");
+        wslcmd_list.link_wslcmd(&"git").expect("Link");
+        wslcmd_list
+            .link_wslcmd_with(
+                &"find",
+                &LinkOptions::new()
+                    .prefix("w-")
+                    .kind(LinkKind::Copy)
+                    .tags(&["tools"]),
+            )
+            .expect("Link with options");
+        fs.write(&binpath.with_file_name("profile"), b"export A=1")
+            .expect("Write profile");
+
+        // install to another dir, with links, metadata and profile
+        let dir = linkdir.parent().expect("Parent").join("installed");
+        let (mut wslcmd_list_new, results) = wslcmd_list.install_to(&dir).expect("Install");
+        assert!(results.iter().all(|(_, result)| result.is_ok()));
+        assert!(fs.is_file(&dir.join("wslcmd.exe")));
+        assert_eq!(
+            fs.read_to_string(&dir.join("profile")).ok().as_deref(),
+            Some("export A=1")
+        );
+        let mut linknames: Vec<String> = wslcmd_list_new.get_cmdlist().iter().cloned().collect();
+        linknames.sort();
+        assert_eq!(linknames, vec!["git", "w-find"]);
+        let meta = wslcmd_list_new
+            .get_link_meta("w-find")
+            .expect("Meta")
+            .clone();
+        assert_eq!(*meta.get_kind(), LinkKind::Copy);
+        assert_eq!(meta.get_tags(), &vec!["tools".to_string()]);
+        assert_eq!(wslcmd_list.get_cmdlist().len(), 2);
+
+        // install again to the same dirs is refused
+        assert!(wslcmd_list.install_to(&dir).is_err());
+        assert!(wslcmd_list.install_to(&linkdir).is_err());
+
+        // uninstall: links are removed, and undone by the journal
+        assert!(wslcmd_list.uninstall(false).expect("Uninstall").is_empty());
+        assert!(wslcmd_list.get_cmdlist().is_empty());
+        wslcmd_list.undo_operations(1).expect("Undo");
+        assert_eq!(wslcmd_list.get_cmdlist().len(), 2);
+
+        // purge: config files are removed, leaving only the binary
+        let removed = wslcmd_list_new.uninstall(true).expect("Purge");
+        assert!(removed.iter().any(|pb| pb.ends_with("wslcmd.toml")));
+        assert!(removed.iter().any(|pb| pb.ends_with("wslcmd.journal")));
+        assert!(removed.iter().any(|pb| pb.ends_with("profile")));
+        assert_eq!(
+            fs.read_dir(&dir)
+                .expect("Read dir")
+                .into_iter()
+                .map(|(pb, _)| pb)
+                .collect::<Vec<_>>(),
+            vec![dir.join("wslcmd.exe")]
+        );
+    }
+}
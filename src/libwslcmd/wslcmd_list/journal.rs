@@ -0,0 +1,395 @@
+use derive_getters::Getters;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::io::{Error, ErrorKind};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::super::LinkMeta;
+use super::{LinkOptions, WslCmdList};
+
+/// Extension of journal file, placed next to the manifest (Ex: 'wslcmd.journal')
+const JOURNAL_EXTENSION: &str = "journal";
+
+/// Max number of operations kept in the journal (older ones are dropped)
+const MAX_JOURNAL_ENTRIES: usize = 100;
+
+#[derive(Getters, Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// An operation recorded in the journal, with links changed by it
+pub struct JournalEntry {
+    /// Time of the operation (seconds since unix epoch)
+    #[getter(rename = "get_time")]
+    time: u64,
+
+    /// Name of the operation (Ex: 'unlink')
+    #[getter(rename = "get_operation")]
+    operation: String,
+
+    /// Links changed by the operation
+    #[getter(rename = "get_changes")]
+    changes: Vec<LinkChange>,
+}
+
+#[derive(Getters, Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// Change of a link by an operation, with its metadata before and after
+pub struct LinkChange {
+    /// Link name
+    #[getter(rename = "get_name")]
+    name: String,
+
+    /// Metadata before the operation ([`None`] if the link was added)
+    #[getter(rename = "get_before")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    before: Option<LinkMeta>,
+
+    /// Metadata after the operation ([`None`] if the link was removed)
+    #[getter(rename = "get_after")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    after: Option<LinkMeta>,
+}
+
+impl JournalEntry {
+    ///
+    /// Format time of the operation in UTC (Ex: '2021-09-01 12:34:56 UTC')
+    ///
+    #[allow(dead_code)]
+    pub fn format_time(&self) -> String {
+        let (days, secs) = (self.time / 86400, self.time % 86400);
+        let (year, month, day) = civil_from_days(days as i64);
+
+        format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+            year,
+            month,
+            day,
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60
+        )
+    }
+}
+
+impl LinkChange {
+    ///
+    /// Get kind of the change (`added`, `removed`, `changed`)
+    ///
+    #[allow(dead_code)]
+    pub fn action(&self) -> &'static str {
+        match (&self.before, &self.after) {
+            (None, _) => "added",
+            (_, None) => "removed",
+            _ => "changed",
+        }
+    }
+}
+
+impl WslCmdList {
+    ///
+    /// Get operations recorded in the journal, from the oldest to the latest
+    ///
+    /// # Return
+    ///
+    /// [`Ok`]\([`Vec`]\) of [`JournalEntry`] (Empty if nothing is recorded),
+    /// [`Err`]\([`Error`]\) if failed to read the journal
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// for entry in wslcmd_list.get_journal()?.iter().rev() {
+    ///     println!("{} {}", entry.format_time(), entry.get_operation());
+    /// }
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn get_journal(&self) -> io::Result<Vec<JournalEntry>> {
+        match self.fs.read_to_string(&self.journal_path()) {
+            // broken lines are skipped, not to lose all history
+            Ok(s) => Ok(s
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(vec![]),
+            Err(e) => Err(e),
+        }
+    }
+
+    ///
+    /// Undo the latest operations recorded in the journal, and remove them from the journal
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - Number of operations to undo
+    ///
+    /// # Return
+    ///
+    /// [`Ok`]\([`Vec`]\) of undone [`JournalEntry`], from the latest,
+    /// [`Err`]\([`Error`]\) if nothing to undo, links were changed after the operation,
+    /// or failed to undo (operations undone before the failure are kept undone)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let undone = wslcmd_list.undo_operations(1)?;
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn undo_operations(&mut self, count: usize) -> io::Result<Vec<JournalEntry>> {
+        self.with_lock(|this| {
+            let mut entries = this.get_journal()?;
+            if entries.is_empty() {
+                return Err(Error::new(ErrorKind::NotFound, "No operation to undo"));
+            }
+
+            let mut undone = vec![];
+            while undone.len() < count {
+                let entry = match entries.pop() {
+                    Some(entry) => entry,
+                    None => break,
+                };
+                if let Err(e) = this.revert_journal_entry(&entry) {
+                    entries.push(entry.clone());
+                    this.write_journal(&entries)?;
+                    return Err(Error::new(
+                        e.kind(),
+                        format!(
+                            "Failed to undo '{}' at {}: {}",
+                            entry.operation,
+                            entry.format_time(),
+                            e
+                        ),
+                    ));
+                }
+                undone.push(entry);
+            }
+            this.write_journal(&entries)?;
+
+            Ok(undone)
+        })
+    }
+
+    // record links changed from links_before to the journal, if any
+    pub(super) fn record_journal(
+        &mut self,
+        operation: &str,
+        links_before: &BTreeMap<String, LinkMeta>,
+    ) -> io::Result<()> {
+        let links_after = self.manifest.get_links();
+        let mut names: Vec<&String> = links_before.keys().chain(links_after.keys()).collect();
+        names.sort();
+        names.dedup();
+
+        let changes: Vec<LinkChange> = names
+            .into_iter()
+            .filter(|name| links_before.get(*name) != links_after.get(*name))
+            .map(|name| LinkChange {
+                name: name.clone(),
+                before: links_before.get(name).cloned(),
+                after: links_after.get(name).cloned(),
+            })
+            .collect();
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        let mut entries = self.get_journal()?;
+        entries.push(JournalEntry {
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            operation: operation.to_string(),
+            changes,
+        });
+        let n_drop = entries.len().saturating_sub(MAX_JOURNAL_ENTRIES);
+
+        self.write_journal(&entries[n_drop..])
+    }
+
+    // revert changes of the entry: remove links added or changed, then re-create links removed or changed
+    // fails before changing anything if any link was changed after the operation
+    fn revert_journal_entry(&mut self, entry: &JournalEntry) -> io::Result<()> {
+        if let Some(change) = entry
+            .changes
+            .iter()
+            .find(|change| self.manifest.get(&change.name) != change.after.as_ref())
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Link '{}' was changed after the operation", change.name),
+            ));
+        }
+
+        // links only with metadata changed are reverted in the manifest, without re-creating
+        let is_relinked = |change: &LinkChange| match (&change.before, &change.after) {
+            (Some(before), Some(after)) => {
+                before.get_kind() != after.get_kind()
+                    || before.get_detached() != after.get_detached()
+            }
+            _ => true,
+        };
+
+        entry
+            .changes
+            .iter()
+            .filter(|change| change.after.is_some() && is_relinked(change))
+            .try_for_each(|change| self.unlink_wslcmd(&change.name))?;
+        entry.changes.iter().try_for_each(|change| {
+            let before = match &change.before {
+                Some(before) => before,
+                None => return Ok(()),
+            };
+            if is_relinked(change) {
+                let cmdname = before
+                    .get_prefix()
+                    .as_deref()
+                    .and_then(|prefix| change.name.strip_prefix(prefix))
+                    .unwrap_or(&change.name);
+                self.link_wslcmd_with(&cmdname, &link_options_of(before))?;
+            }
+            // restore all fields, including created time and backups
+            self.manifest.insert(&change.name, before.clone());
+            self.manifest.save()
+        })
+    }
+
+    // overwrite the journal with entries
+    fn write_journal(&self, entries: &[JournalEntry]) -> io::Result<()> {
+        let lines: Vec<String> = entries
+            .iter()
+            .filter_map(|entry| serde_json::to_string(entry).ok())
+            .collect();
+
+        self.fs
+            .write(&self.journal_path(), lines.join("\n").as_bytes())
+    }
+
+    // get path of journal file: (linkdir)/(bin basename).journal
+    pub(super) fn journal_path(&self) -> PathBuf {
+        self.manifest.get_path().with_extension(JOURNAL_EXTENSION)
+    }
+}
+
+// get LinkOptions to re-create the link of meta
+// existing files are moved to backups again, if they were on the first link
+fn link_options_of(meta: &LinkMeta) -> LinkOptions {
+    LinkOptions::new()
+        .prefix(meta.get_prefix().as_deref())
+        .description(meta.get_description().as_deref())
+        .kind(*meta.get_kind())
+        .detached(*meta.get_detached())
+        .tags(meta.get_tags())
+        .force(!meta.get_backups().is_empty())
+}
+
+// convert days since unix epoch to (year, month, day) of proleptic Gregorian calendar
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+
+    (yoe + era * 400 + (month <= 2) as i64, month, day)
+}
+
+#[cfg(test)]
+/// For module test
+mod test {
+    use super::super::super::link_fs::{mem_root, LinkFs, MemFs};
+    use super::super::{LinkOptions, WslCmdList};
+    use super::JournalEntry;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_journal_undo() {
+        // link commands on in-memory fs
+        let fs = Rc::new(MemFs::new());
+        let dir = mem_root().join("bin");
+        fs.create_dir_all(&dir).expect("Dir initialize");
+        fs.write_new(&dir.join("wslcmd.exe"), b"wslcmd")
+            .expect("Bin initialize");
+        let mut wslcmd_list =
+            WslCmdList::with_fs(&dir.join("wslcmd.exe"), &dir, fs).expect("New WslCmdList");
+        let linked = |wslcmd_list: &mut WslCmdList| {
+            let mut linknames: Vec<String> = wslcmd_list.get_cmdlist().iter().cloned().collect();
+            linknames.sort();
+            linknames
+        };
+
+        // every mutating operation is recorded, with changed links
+        wslcmd_list
+            .link_wslcmd_batch(&["git", "make"], &LinkOptions::new().description("Tools"))
+            .expect("Link");
+        wslcmd_list.unlink_wslcmd(&"make").expect("Unlink");
+        wslcmd_list
+            .update_link_tags(&"git", &["vcs"], &[] as &[&str])
+            .expect("Tag");
+        wslcmd_list.rename_wslcmd(&"git", &"g").expect("Rename");
+        assert!(wslcmd_list.unlink_wslcmd(&"emacs").is_err());
+        let journal = wslcmd_list.get_journal().expect("Journal");
+        assert_eq!(
+            journal
+                .iter()
+                .map(|entry| entry.get_operation().as_str())
+                .collect::<Vec<&str>>(),
+            vec!["link", "unlink", "tag", "rename"]
+        );
+        assert_eq!(
+            journal[3]
+                .get_changes()
+                .iter()
+                .map(|change| (change.get_name().as_str(), change.action()))
+                .collect::<Vec<(&str, &str)>>(),
+            vec![("g", "added"), ("git", "removed")]
+        );
+
+        // undo rename, then tag and unlink, with metadata restored
+        let created = journal[0].get_changes()[1]
+            .get_after()
+            .as_ref()
+            .map(|meta| *meta.get_created());
+        wslcmd_list.undo_operations(1).expect("Undo rename");
+        assert_eq!(linked(&mut wslcmd_list), vec!["git"]);
+        let undone = wslcmd_list.undo_operations(2).expect("Undo tag, unlink");
+        assert_eq!(undone.len(), 2);
+        assert_eq!(linked(&mut wslcmd_list), vec!["git", "make"]);
+        assert!(wslcmd_list
+            .get_link_meta("git")
+            .is_some_and(|meta| meta.get_tags().is_empty()));
+        let meta = wslcmd_list.get_link_meta("make").expect("Meta").clone();
+        assert_eq!(meta.get_description().as_deref(), Some("Tools"));
+        assert_eq!(Some(*meta.get_created()), created);
+        assert_eq!(wslcmd_list.get_journal().expect("Journal").len(), 1);
+
+        // undo is refused if links were changed after the operation, without journal
+        wslcmd_list.unlink_wslcmd(&"make").expect("Unlink");
+        let journal = wslcmd_list.get_journal().expect("Journal");
+        wslcmd_list
+            .write_journal(&journal[..1])
+            .expect("Drop from journal");
+        assert!(wslcmd_list.undo_operations(1).is_err());
+        assert_eq!(linked(&mut wslcmd_list), vec!["git"]);
+        wslcmd_list.write_journal(&journal).expect("Write journal");
+
+        // undo all operations, then nothing to undo
+        wslcmd_list.undo_operations(10).expect("Undo all");
+        assert!(linked(&mut wslcmd_list).is_empty());
+        assert!(wslcmd_list.undo_operations(1).is_err());
+    }
+
+    #[test]
+    fn test_journal_format_time() {
+        let entry = |time: u64| JournalEntry {
+            time,
+            operation: String::new(),
+            changes: vec![],
+        };
+
+        assert_eq!(entry(0).format_time(), "1970-01-01 00:00:00 UTC");
+        assert_eq!(entry(951782400).format_time(), "2000-02-29 00:00:00 UTC");
+        assert_eq!(entry(1630499696).format_time(), "2021-09-01 12:34:56 UTC");
+    }
+}
@@ -0,0 +1,225 @@
+use derive_getters::Getters;
+use serde::Serialize;
+use std::collections::HashSet;
+
+use super::super::{DetachedMode, LinkKind, LinkMeta};
+use super::doctor::detached_path;
+use super::{link_file_paths, WslCmdList, BINARY_EXTENSION};
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+/// Health of a linked command
+pub enum LinkHealth {
+    /// All link files exist, and point to current binary
+    Ok,
+
+    /// Some link files of the link kind are missing
+    MissingFiles,
+
+    /// Link files point to a different or missing WslCmd binary, or are dangling
+    Broken,
+}
+
+impl LinkHealth {
+    ///
+    /// Get name of [`LinkHealth`], which is same as the serialized one
+    ///
+    #[allow(dead_code)]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::MissingFiles => "missing-files",
+            Self::Broken => "broken",
+        }
+    }
+}
+
+#[derive(Getters, Serialize, Debug, Clone, PartialEq)]
+/// Details of a linked command, listed by [`WslCmdList::list_links`]
+pub struct LinkInfo {
+    /// Link name, including namespace prefix, user and distribution (Ex: 'w-git!!debian')
+    #[getter(rename = "get_name")]
+    name: String,
+
+    /// WSL command name, after stripping namespace prefix
+    #[getter(rename = "get_command")]
+    command: String,
+
+    /// WSL user of the link
+    #[getter(rename = "get_user")]
+    user: Option<String>,
+
+    /// WSL distribution of the link
+    #[getter(rename = "get_dist")]
+    dist: Option<String>,
+
+    /// Namespace prefix of the link
+    #[getter(rename = "get_prefix")]
+    prefix: Option<String>,
+
+    /// Kind of link files
+    #[getter(rename = "get_kind")]
+    kind: LinkKind,
+
+    /// Which of the link and the detached link are created
+    #[getter(rename = "get_detached")]
+    detached: DetachedMode,
+
+    /// If the detached companion link (`.cmd.exe`) exists
+    #[getter(rename = "get_has_detached")]
+    has_detached: bool,
+
+    /// Health of link files
+    #[getter(rename = "get_health")]
+    health: LinkHealth,
+
+    /// Description of the link
+    #[getter(rename = "get_description")]
+    description: Option<String>,
+
+    /// Tags of the link
+    #[getter(rename = "get_tags")]
+    tags: Vec<String>,
+}
+
+impl WslCmdList {
+    ///
+    /// Get details of all linked commands, decoded from link names and the manifest
+    ///
+    /// Links in the manifest of which link files are partially or all missing
+    /// (Ex: command link without its detached link) are also listed, with their health.
+    ///
+    /// # Return
+    ///
+    /// [`Vec`] of [`LinkInfo`], sorted by link name
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// for info in wslcmd_list.list_links() {
+    ///     println!("{} -> {}", info.get_name(), info.get_command());
+    /// }
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn list_links(&mut self) -> Vec<LinkInfo> {
+        // linked commands, and links recorded in the manifest
+        let mut linknames: Vec<String> = {
+            let mut linknames: HashSet<String> = self.get_cmdlist().clone();
+            linknames.extend(self.manifest.get_links().keys().cloned());
+            linknames.into_iter().collect()
+        };
+        linknames.sort();
+
+        linknames
+            .into_iter()
+            .map(|name| {
+                let meta = self
+                    .manifest
+                    .get(&name)
+                    .cloned()
+                    .unwrap_or_else(|| LinkMeta::new(&name, None));
+                let (kind, detached) = (*meta.get_kind(), *meta.get_detached());
+                let pb_cmd = self.linkdir.join(wslcmd_with_ext!(name));
+                let pb_files = link_file_paths(&pb_cmd, kind, detached);
+
+                LinkInfo {
+                    has_detached: kind.shim_extensions().is_empty()
+                        && detached_path(&pb_cmd).is_ok_and(|pb| self.fs.entry_exists(&pb)),
+                    health: match (
+                        pb_files.iter().all(|pb| self.fs.entry_exists(pb)),
+                        // diagnose only symlinks of the link
+                        pb_files
+                            .iter()
+                            .filter(|pb| self.fs.is_symlink(pb))
+                            .any(|pb| self.diagnose_link(pb.clone()).is_some()),
+                    ) {
+                        (false, _) => LinkHealth::MissingFiles,
+                        (true, true) => LinkHealth::Broken,
+                        (true, false) => LinkHealth::Ok,
+                    },
+                    name,
+                    command: meta.get_command().clone(),
+                    user: meta.get_user().clone(),
+                    dist: meta.get_dist().clone(),
+                    prefix: meta.get_prefix().clone(),
+                    kind,
+                    detached,
+                    description: meta.get_description().clone(),
+                    tags: meta.get_tags().clone(),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+/// For module test
+mod test {
+    use super::super::super::link_fs::LinkFs;
+    use super::super::super::{DetachedMode, LinkKind};
+    use super::super::test::init_mem_fs;
+    use super::super::{LinkOptions, WslCmdList};
+    use super::LinkHealth;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_mem_fs_list_links() {
+        let (fs, binpath, linkdir) = init_mem_fs();
+        let mut wslcmd_list =
+            WslCmdList::with_fs(&binpath, &linkdir, fs.clone()).expect("New WslCmdList");
+        wslcmd_list.link_wslcmd(&"git!john!debian").expect("Link");
+        wslcmd_list
+            .link_wslcmd_with(
+                &"code",
+                &LinkOptions::new()
+                    .prefix("w-")
+                    .detached(DetachedMode::NoDetached)
+                    .tags(&["gui"]),
+            )
+            .expect("Link with options");
+        wslcmd_list
+            .link_wslcmd_with(&"make", &LinkOptions::new().kind(LinkKind::Copy))
+            .expect("Link copy");
+
+        // decoded fields, sorted by link name
+        let infos = wslcmd_list.list_links();
+        assert_eq!(
+            infos
+                .iter()
+                .map(|info| info.get_name().as_str())
+                .collect::<Vec<&str>>(),
+            vec!["git!john!debian", "make", "w-code"]
+        );
+        assert_eq!(infos[0].get_command(), "git");
+        assert_eq!(infos[0].get_user().as_deref(), Some("john"));
+        assert_eq!(infos[0].get_dist().as_deref(), Some("debian"));
+        assert!(*infos[0].get_has_detached());
+        assert_eq!(infos[2].get_command(), "code");
+        assert_eq!(infos[2].get_prefix().as_deref(), Some("w-"));
+        assert!(!*infos[2].get_has_detached());
+        assert_eq!(infos[2].get_tags(), &vec!["gui".to_string()]);
+        assert!(infos
+            .iter()
+            .all(|info| *info.get_health() == LinkHealth::Ok));
+
+        // symlinks of others are not listed
+        fs.symlink_file(
+            &PathBuf::from("..").join("other").join("tool.exe"),
+            &linkdir.join("tool.exe"),
+        )
+        .expect("Unrelated symlink");
+        assert_eq!(wslcmd_list.list_links().len(), 3);
+
+        // partial link chain: command link without its detached link
+        fs.remove_file(&linkdir.join(".git!john!debian.exe"))
+            .expect("Remove detached");
+        let infos = wslcmd_list.list_links();
+        let info = infos
+            .iter()
+            .find(|info| info.get_name() == "git!john!debian")
+            .expect("Find partial link");
+        assert!(!*info.get_has_detached());
+        assert_eq!(*info.get_health(), LinkHealth::MissingFiles);
+    }
+}
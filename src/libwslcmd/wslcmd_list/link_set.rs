@@ -0,0 +1,411 @@
+use derive_getters::Getters;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io;
+use std::io::{Error, ErrorKind};
+
+use super::super::{is_valid_prefix, DetachedMode, LinkKind};
+use super::{link_file_paths, LinkOptions, WslCmdList};
+
+/// Format name of link set file
+const LINK_SET_FORMAT: &str = "wslcmd-link-set";
+
+/// Current version of link set file format
+const LINK_SET_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+/// Set of links, exported to (or imported from) a JSON file
+pub struct LinkSet {
+    /// Format name, which must be [`LINK_SET_FORMAT`]
+    format: String,
+
+    /// Format version
+    version: u32,
+
+    /// Links in the set
+    links: Vec<LinkSetEntry>,
+}
+
+#[derive(Getters, Serialize, Deserialize, Debug, Clone, PartialEq)]
+/// A link in [`LinkSet`]
+pub struct LinkSetEntry {
+    /// Link name, including namespace prefix, user and distribution (Ex: 'w-git!!debian')
+    #[getter(rename = "get_name")]
+    name: String,
+
+    /// Namespace prefix of the link
+    #[getter(rename = "get_prefix")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    prefix: Option<String>,
+
+    /// Description of the link
+    #[getter(rename = "get_description")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+
+    /// Kind of link files
+    #[getter(rename = "get_kind")]
+    #[serde(default)]
+    kind: LinkKind,
+
+    /// Which of the link and the detached link are created
+    #[getter(rename = "get_detached")]
+    #[serde(default, skip_serializing_if = "DetachedMode::is_both")]
+    detached: DetachedMode,
+
+    /// Tags of the link
+    #[getter(rename = "get_tags")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+}
+
+impl LinkSetEntry {
+    ///
+    /// Get command name to link, without namespace prefix
+    ///
+    #[allow(dead_code)]
+    pub fn cmdname(&self) -> &str {
+        self.prefix
+            .as_deref()
+            .and_then(|prefix| self.name.strip_prefix(prefix))
+            .unwrap_or(&self.name)
+    }
+
+    ///
+    /// Get [`LinkOptions`] to link this entry
+    ///
+    #[allow(dead_code)]
+    pub fn link_options(&self) -> LinkOptions {
+        LinkOptions::new()
+            .prefix(self.prefix.as_deref())
+            .description(self.description.as_deref())
+            .kind(self.kind)
+            .detached(self.detached)
+            .tags(&self.tags)
+    }
+
+    // check if the entry can be linked: valid prefix, which leaves non-empty cmdname
+    fn is_valid(&self) -> bool {
+        !self.name.is_empty()
+            && self.prefix.as_deref().is_none_or(|prefix| {
+                is_valid_prefix(prefix)
+                    && self.name.len() > prefix.len()
+                    && self.name.starts_with(prefix)
+            })
+    }
+}
+
+impl LinkSet {
+    ///
+    /// Parse [`LinkSet`] from JSON str
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - JSON str of link set file
+    ///
+    /// # Return
+    ///
+    /// [`Ok`]\([`LinkSet`]\) if succeeded,
+    /// [`Err`]\([`Error`]\) if failed to parse, or format is unknown or newer
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let link_set = LinkSet::from_json(&std::fs::read_to_string("tools.json")?)?;
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn from_json(s: &str) -> io::Result<Self> {
+        let link_set = serde_json::from_str::<Self>(s)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))?;
+
+        match (link_set.format.as_str(), link_set.version) {
+            (LINK_SET_FORMAT, v) if v <= LINK_SET_VERSION => Ok(link_set),
+            (LINK_SET_FORMAT, _) => Err(Error::new(
+                ErrorKind::InvalidData,
+                "Link set was written by a newer version of WslCmd",
+            )),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                "Unknown link set format",
+            )),
+        }
+    }
+
+    ///
+    /// Serialize [`LinkSet`] to pretty JSON str
+    ///
+    #[allow(dead_code)]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    ///
+    /// Get links in the set
+    ///
+    #[allow(dead_code)]
+    pub fn get_links(&self) -> &[LinkSetEntry] {
+        &self.links
+    }
+}
+
+#[derive(Getters, Debug, Default)]
+/// Changes to apply a [`LinkSet`], planned by [`WslCmdList::plan_import`]
+pub struct ImportPlan {
+    /// Links to create
+    #[getter(rename = "get_to_link")]
+    to_link: Vec<LinkSetEntry>,
+
+    /// Link names to remove (only on sync)
+    #[getter(rename = "get_to_unlink")]
+    to_unlink: Vec<String>,
+
+    /// Link names which are already linked as the same kind
+    #[getter(rename = "get_unchanged")]
+    unchanged: Vec<String>,
+
+    /// Links which cannot be applied, with reasons
+    #[getter(rename = "get_conflicts")]
+    conflicts: Vec<(LinkSetEntry, String)>,
+}
+
+impl WslCmdList {
+    ///
+    /// Export all links to a [`LinkSet`]
+    ///
+    /// # Return
+    ///
+    /// A [`LinkSet`] of all links, sorted by link name
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// println!("{}", wslcmd_list.export_link_set().to_json());
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn export_link_set(&mut self) -> LinkSet {
+        let mut names: Vec<String> = self.get_cmdlist().iter().cloned().collect();
+        names.sort();
+
+        LinkSet {
+            format: LINK_SET_FORMAT.to_string(),
+            version: LINK_SET_VERSION,
+            links: names
+                .into_iter()
+                .map(|name| {
+                    let meta = self.manifest.get(&name).cloned().unwrap_or_default();
+                    LinkSetEntry {
+                        name,
+                        prefix: meta.get_prefix().clone(),
+                        description: meta.get_description().clone(),
+                        kind: *meta.get_kind(),
+                        detached: *meta.get_detached(),
+                        tags: meta.get_tags().clone(),
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    ///
+    /// Plan changes to apply a [`LinkSet`], without changing anything
+    ///
+    /// # Arguments
+    ///
+    /// * `link_set` - A link set to import
+    /// * `sync`     - If set, links not in the set are planned to be removed
+    ///
+    /// # Return
+    ///
+    /// [`ImportPlan`] of the link set
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let plan = wslcmd_list.plan_import(&link_set, false);
+    /// wslcmd_list.apply_import(&plan)?;
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn plan_import(&mut self, link_set: &LinkSet, sync: bool) -> ImportPlan {
+        let linked = self.get_cmdlist().clone();
+        let mut plan = ImportPlan::default();
+        let mut names_found = HashSet::new();
+
+        link_set.links.iter().for_each(|entry| {
+            let conflict = |reason: &str| Some((entry.clone(), reason.to_string()));
+
+            let found = match self.manifest.get(&entry.name) {
+                _ if !entry.is_valid() => conflict("Invalid link name or prefix"),
+                _ if !names_found.insert(entry.name.clone()) => conflict("Duplicated link name"),
+
+                // already linked: unchanged if same kind and detached mode
+                Some(meta) if linked.contains(&entry.name) => {
+                    match (
+                        *meta.get_kind() == entry.kind,
+                        *meta.get_detached() == entry.detached,
+                    ) {
+                        (true, true) => {
+                            plan.unchanged.push(entry.name.clone());
+                            None
+                        }
+                        (false, _) => {
+                            conflict(&format!("Already linked as '{}'", meta.get_kind().name()))
+                        }
+                        (true, false) => conflict(&format!(
+                            "Already linked with detached mode '{}'",
+                            meta.get_detached().name()
+                        )),
+                    }
+                }
+
+                // not linked: conflict if any file exists with the link name
+                _ => match self.cmd_pathbuf(&entry.name) {
+                    Ok(pb_cmd)
+                        if std::iter::once(pb_cmd.clone())
+                            .chain(link_file_paths(&pb_cmd, entry.kind, entry.detached))
+                            .any(|pb| self.fs.entry_exists(&pb)) =>
+                    {
+                        conflict("File already exists")
+                    }
+                    Ok(_) => {
+                        plan.to_link.push(entry.clone());
+                        None
+                    }
+                    Err(_) => conflict("Invalid link name or prefix"),
+                },
+            };
+            plan.conflicts.extend(found);
+        });
+
+        // links not in the set
+        if sync {
+            plan.to_unlink = linked
+                .into_iter()
+                .filter(|name| !names_found.contains(name))
+                .collect();
+            plan.to_unlink.sort();
+        }
+
+        plan
+    }
+
+    ///
+    /// Apply an [`ImportPlan`] as one operation: create links to link, then remove links to unlink
+    ///
+    /// Conflicts of the plan are skipped. If failed to link or unlink any of them,
+    /// all changes are rolled back, and the operation is recorded to the journal as one,
+    /// so that it can be undone at once.
+    ///
+    /// # Arguments
+    ///
+    /// * `plan` - A plan from [`WslCmdList::plan_import`]
+    ///
+    /// # Return
+    ///
+    /// [`Ok`]\([`()`](unit)\) if all succeeded,
+    /// [`Err`]\([`Error`]\) with the failed link name if failed (nothing is changed)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let plan = wslcmd_list.plan_import(&link_set, true);
+    /// wslcmd_list.apply_import(&plan)?;
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn apply_import(&mut self, plan: &ImportPlan) -> io::Result<()> {
+        // remove created links, from the last one
+        let unlink_all = |this: &mut Self, entries: &[LinkSetEntry]| {
+            entries.iter().rev().for_each(|entry| {
+                this.unlink_wslcmd(entry.get_name()).ok();
+            });
+        };
+
+        self.with_journal("import", |this| {
+            // link each entry with its options, or remove created links if failed
+            (0..plan.to_link.len()).try_for_each(|i| {
+                let entry = &plan.to_link[i];
+                this.link_wslcmd_batch(&[entry.cmdname()], &entry.link_options())
+                    .inspect_err(|_| unlink_all(this, &plan.to_link[..i]))
+            })?;
+
+            // unlink all links not in the set, or remove created links if failed
+            this.unlink_wslcmd_batch(&plan.to_unlink)
+                .inspect_err(|_| unlink_all(this, &plan.to_link))
+        })
+    }
+}
+
+#[cfg(test)]
+/// For module test
+mod test {
+    use super::super::super::LinkKind;
+    use super::super::test::{clean_tmpdir, copy_tmpbin, init_tmpdir};
+    use super::super::{LinkOptions, WslCmdList};
+    use super::LinkSet;
+
+    #[test]
+    fn test_export_import() {
+        const TMPDIR_POSTFIX: &str = "wslcmd-link-set";
+        const TMPDIR_POSTFIX_2: &str = "wslcmd-link-set-2";
+
+        // init tmpdirs of two machines
+        let tmpdir = init_tmpdir(TMPDIR_POSTFIX).expect("Tmp dir initialize");
+        let tmpdir2 = init_tmpdir(TMPDIR_POSTFIX_2).expect("Tmp dir initialize");
+        let (bin1, _) = copy_tmpbin(&tmpdir, None).expect("Bin initialize");
+        let (bin2, _) = copy_tmpbin(&tmpdir2, None).expect("Bin initialize");
+
+        // export links of bin1
+        let mut wslcmd_list = WslCmdList::new(&bin1).expect("New WslCmdList");
+        wslcmd_list
+            .link_wslcmd_with(
+                &"git!!debian",
+                &LinkOptions::new().prefix("w-").description("Git"),
+            )
+            .expect("Link");
+        wslcmd_list
+            .link_wslcmd_with(&"node", &LinkOptions::new().kind(LinkKind::ShimCmd))
+            .expect("Link");
+        let json = wslcmd_list.export_link_set().to_json();
+        dbg!(&json);
+        let link_set = LinkSet::from_json(&json).expect("Parse link set");
+        assert_eq!(link_set.get_links().len(), 2);
+        assert_eq!(link_set.get_links()[0].get_name(), "node");
+        assert_eq!(link_set.get_links()[1].cmdname(), "git!!debian");
+
+        // plan import on bin1: all unchanged, nothing to unlink on sync
+        let plan = wslcmd_list.plan_import(&link_set, true);
+        assert_eq!(plan.get_unchanged().len(), 2);
+        assert!(plan.get_to_link().is_empty() && plan.get_to_unlink().is_empty());
+
+        // import to bin2 which has a conflict, and another link
+        let mut wslcmd_list2 = WslCmdList::new(&bin2).expect("New WslCmdList");
+        wslcmd_list2.link_wslcmd(&"node").expect("Link");
+        wslcmd_list2.link_wslcmd(&"vim").expect("Link");
+        let plan = wslcmd_list2.plan_import(&link_set, true);
+        dbg!(&plan);
+        assert_eq!(plan.get_to_link().len(), 1);
+        assert_eq!(plan.get_conflicts().len(), 1);
+        assert_eq!(plan.get_to_unlink(), &vec!["vim".to_string()]);
+        wslcmd_list2.apply_import(&plan).expect("Import");
+        let meta = wslcmd_list2.get_link_meta("w-git!!debian").expect("Meta");
+        assert_eq!(meta.get_description().as_deref(), Some("Git"));
+        assert_eq!(meta.get_prefix().as_deref(), Some("w-"));
+        assert!(wslcmd_list2.get_link_meta("vim").is_none());
+
+        // import is undone at once
+        let undone = wslcmd_list2.undo_operations(1).expect("Undo import");
+        assert_eq!(undone.len(), 1);
+        assert!(wslcmd_list2.get_link_meta("w-git!!debian").is_none());
+        assert!(wslcmd_list2.get_link_meta("vim").is_some());
+
+        // unknown format
+        assert!(LinkSet::from_json(r#"{"format":"x","version":1,"links":[]}"#).is_err());
+
+        // clean tmpdir
+        clean_tmpdir(TMPDIR_POSTFIX);
+        clean_tmpdir(TMPDIR_POSTFIX_2);
+    }
+}
@@ -0,0 +1,299 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::super::link_fs::FileKind;
+use super::super::WCPath;
+use super::{WslCmdList, BINARY_EXTENSION, DETACHED_PROC_PREFIX};
+
+/// Extension of list index file, placed next to the manifest (Ex: 'wslcmd.index')
+const INDEX_EXTENSION: &str = "index";
+
+#[derive(Serialize, Deserialize, Debug)]
+/// On-disk index of WslCmd list, valid while mtime of the link dir is not changed
+struct ListIndex {
+    /// Original bin path which links point to
+    orig_binpath: PathBuf,
+
+    /// Modified time of the link dir, when the list was scanned
+    dir_mtime: SystemTime,
+
+    /// Link names
+    links: Vec<String>,
+}
+
+impl WslCmdList {
+    // get list of wslcmd from the on-disk index if valid, or scan the link dir and update the index
+    pub(super) fn indexed_wslcmd_list(&self) -> Option<(HashSet<String>, Option<SystemTime>)> {
+        let index_path = self.index_path();
+
+        // create index file before getting dir mtime, as creating a file changes it
+        let has_index =
+            { self.fs.entry_exists(&index_path) || self.fs.write_new(&index_path, b"").is_ok() };
+        let dir_mtime = self.fs.modified(&self.linkdir).ok();
+
+        // valid index: same binary and same dir mtime
+        let indexed = {
+            self.fs
+                .read_to_string(&index_path)
+                .ok()
+                .and_then(|s| serde_json::from_str::<ListIndex>(&s).ok())
+                .filter(|index| {
+                    index.orig_binpath == self.orig_binpath && Some(index.dir_mtime) == dir_mtime
+                })
+                .map(|index| index.links.into_iter().collect())
+        };
+        if let Some(cmdlist) = indexed {
+            return Some((cmdlist, dir_mtime));
+        }
+
+        // scan, then overwrite index in place (which does not change dir mtime)
+        let cmdlist = self.scan_wslcmd_list()?;
+        dir_mtime.filter(|_| has_index).map(|dir_mtime| {
+            let mut links: Vec<String> = cmdlist.iter().cloned().collect();
+            links.sort();
+            let index = ListIndex {
+                orig_binpath: self.orig_binpath.clone(),
+                dir_mtime,
+                links,
+            };
+
+            serde_json::to_vec(&index)
+                .ok()
+                .and_then(|data| self.fs.write(&index_path, &data).ok())
+                .or_else(|| self.fs.remove_file(&index_path).ok()) // drop broken index
+        });
+
+        Some((cmdlist, dir_mtime))
+    }
+
+    // scan the link dir and classify entries with a single pass,
+    // reading only targets of symlinks, and canonicalizing only distinct targets of detached links
+    pub(super) fn scan_wslcmd_list(&self) -> Option<HashSet<String>> {
+        // all entries: (symlink name -> target), regular file names
+        let mut symlinks: HashMap<String, PathBuf> = HashMap::new();
+        let mut files: HashSet<String> = HashSet::new();
+        self.fs
+            .read_dir(&self.linkdir)
+            .ok()?
+            .into_iter()
+            .for_each(|(pb, kind)| {
+                let name = match pb.wcpath_filename() {
+                    Some(name) => name.to_string(),
+                    None => return,
+                };
+                match kind {
+                    FileKind::Symlink => {
+                        self.fs
+                            .read_link(&pb)
+                            .ok()
+                            .map(|pb| symlinks.insert(name, pb));
+                    }
+                    FileKind::File => {
+                        files.insert(name);
+                    }
+                    FileKind::Dir => (),
+                }
+            });
+
+        // check if target of detached link is the binary, canonicalizing each target only once
+        let binname = self.binpath.wcpath_filename()?;
+        let mut is_bin_target: HashMap<PathBuf, bool> = HashMap::new();
+        let mut points_to_bin = |target: &Path| -> bool {
+            *is_bin_target
+                .entry(target.to_path_buf())
+                .or_insert_with(|| {
+                    self.fs
+                        .canonicalize(&self.linkdir.join(target))
+                        .ok()
+                        .as_ref()
+                        == Some(&self.orig_binpath)
+                })
+        };
+
+        // symlink chains: cmd.exe -> .cmd.exe -> bin
+        let mut cmdlist: HashSet<String> = symlinks
+            .iter()
+            .filter_map(|(name, target)| {
+                let linkname = name.strip_suffix(&format!(".{}", BINARY_EXTENSION))?;
+                let name_detached = wslcmd_detached_bin!(name);
+                let target_detached = symlinks.get(&name_detached)?;
+
+                (!linkname.is_empty()
+                    && !name.starts_with(DETACHED_PROC_PREFIX)
+                    && name != binname
+                    && self.linkdir.join(target) == self.linkdir.join(&name_detached)
+                    && Path::new(target_detached)
+                        .extension()
+                        .and_then(OsStr::to_str)
+                        == Some(BINARY_EXTENSION)
+                    && points_to_bin(target_detached))
+                .then(|| linkname.to_string())
+            })
+            .collect();
+
+        // hardlinks, copies, single links and shim scripts recorded in the manifest
+        cmdlist.extend(
+            self.manifest
+                .get_links()
+                .iter()
+                .filter(|(linkname, meta)| {
+                    let (kind, detached) = (meta.get_kind(), meta.get_detached());
+                    let name = wslcmd_with_ext!(linkname);
+                    // link file without companion: cmd.exe or .cmd.exe
+                    let name_single = match detached.has_link() {
+                        true => name.clone(),
+                        false => wslcmd_detached_bin!(name),
+                    };
+
+                    match (kind.shim_extensions(), detached.is_both()) {
+                        ([], _) if name == binname => false,
+                        ([], true) => {
+                            kind.is_binary_copy()
+                                && files.contains(&name)
+                                && files.contains(&wslcmd_detached_bin!(name))
+                        }
+                        ([], false) => match kind.is_symlink() {
+                            true => symlinks
+                                .get(&name_single)
+                                .is_some_and(|target| points_to_bin(target)),
+                            false => files.contains(&name_single),
+                        },
+                        (exts, _) => exts
+                            .iter()
+                            .all(|ext| files.contains(&format!("{}.{}", linkname, ext))),
+                    }
+                })
+                .filter(|(linkname, _)| !linkname.starts_with(DETACHED_PROC_PREFIX))
+                .map(|(linkname, _)| linkname.clone()),
+        );
+
+        Some(cmdlist)
+    }
+
+    // get path of list index file: (linkdir)/(bin basename).index
+    pub(super) fn index_path(&self) -> PathBuf {
+        self.manifest.get_path().with_extension(INDEX_EXTENSION)
+    }
+}
+
+#[cfg(test)]
+/// For module test
+mod test {
+    use super::super::super::LinkKind;
+    use super::super::test::{clean_tmpdir, copy_tmpbin, init_tmpdir};
+    use super::super::{LinkOptions, WslCmdList};
+    use std::collections::HashSet;
+    use std::fs;
+    use std::time::Instant;
+
+    #[test]
+    fn test_list_index() {
+        const TMPDIR_POSTFIX: &str = "wslcmd-list-index";
+
+        // init tmpdir, with all kinds of links and non-link files
+        let tmpdir = init_tmpdir(TMPDIR_POSTFIX).expect("Tmp dir initialize");
+        let (binpath, _) = copy_tmpbin(&tmpdir, None).expect("Bin initialize");
+        let mut wslcmd_list = WslCmdList::new(&binpath).expect("New WslCmdList");
+        wslcmd_list.link_wslcmd(&"git").expect("Link");
+        wslcmd_list
+            .link_wslcmd_with(&"vim", &LinkOptions::new().kind(LinkKind::Copy))
+            .expect("Link");
+        wslcmd_list
+            .link_wslcmd_with(&"node", &LinkOptions::new().kind(LinkKind::ShimBoth))
+            .expect("Link");
+        fs::write(tmpdir.join("other.exe"), b"other").expect("Dummy file");
+
+        // scanned list is same as the list of file-by-file check
+        let expected: HashSet<String> = tmpdir
+            .read_dir()
+            .expect("Read dir")
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|pb| wslcmd_list.is_wslcmd_file(pb))
+            .filter_map(|pb| Some(pb.file_stem()?.to_str()?.to_string()))
+            .collect();
+        assert_eq!(expected.len(), 3);
+        assert_eq!(wslcmd_list.scan_wslcmd_list(), Some(expected.clone()));
+
+        // index is written, and used by a new instance
+        let (cmdlist, _) = wslcmd_list.indexed_wslcmd_list().expect("Indexed list");
+        assert_eq!(cmdlist, expected);
+        let index_path = wslcmd_list.index_path();
+        assert!(fs::read_to_string(&index_path)
+            .expect("Index")
+            .contains("node"));
+        let mut wslcmd_list = WslCmdList::new(&binpath).expect("New WslCmdList");
+        assert_eq!(wslcmd_list.get_cmdlist(), &expected);
+
+        // index is invalidated when the dir is changed
+        wslcmd_list.unlink_wslcmd(&"git").expect("Unlink");
+        let wslcmd_list = WslCmdList::new(&binpath).expect("New WslCmdList");
+        let (cmdlist, _) = wslcmd_list.indexed_wslcmd_list().expect("Indexed list");
+        assert!(!cmdlist.contains("git") && cmdlist.len() == 2);
+
+        // clean tmpdir
+        clean_tmpdir(TMPDIR_POSTFIX);
+    }
+
+    #[test]
+    #[ignore]
+    // benchmark on a synthetic link dir (run with '--ignored --nocapture')
+    fn bench_list_index() {
+        const TMPDIR_POSTFIX: &str = "wslcmd-list-index-bench";
+        const LINK_COUNT: usize = 300;
+
+        // init tmpdir, with links and as many unrelated files
+        let tmpdir = init_tmpdir(TMPDIR_POSTFIX).expect("Tmp dir initialize");
+        let (binpath, _) = copy_tmpbin(&tmpdir, None).expect("Bin initialize");
+        let mut wslcmd_list = WslCmdList::new(&binpath).expect("New WslCmdList");
+        let cmdnames: Vec<String> = (0..LINK_COUNT).map(|i| format!("cmd{}", i)).collect();
+        wslcmd_list
+            .link_wslcmd_batch(&cmdnames, &LinkOptions::new())
+            .expect("Link");
+        (0..LINK_COUNT).for_each(|i| {
+            fs::write(tmpdir.join(format!("other{}.dll", i)), b"other").expect("Dummy file");
+        });
+
+        // file-by-file check (previous implementation)
+        let started = Instant::now();
+        let count = tmpdir
+            .read_dir()
+            .expect("Read dir")
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|pb| wslcmd_list.is_wslcmd_file(pb))
+            .count();
+        println!(
+            "file-by-file check: {:?} ({} links)",
+            started.elapsed(),
+            count
+        );
+
+        // single pass scan
+        let started = Instant::now();
+        let count = wslcmd_list.scan_wslcmd_list().expect("Scan").len();
+        println!(
+            "single pass scan:   {:?} ({} links)",
+            started.elapsed(),
+            count
+        );
+
+        // on-disk index
+        wslcmd_list.indexed_wslcmd_list().expect("Indexed list");
+        let started = Instant::now();
+        let count = wslcmd_list
+            .indexed_wslcmd_list()
+            .expect("Indexed list")
+            .0
+            .len();
+        println!(
+            "on-disk index:      {:?} ({} links)",
+            started.elapsed(),
+            count
+        );
+
+        // clean tmpdir
+        clean_tmpdir(TMPDIR_POSTFIX);
+    }
+}
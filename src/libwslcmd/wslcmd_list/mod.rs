@@ -0,0 +1,3237 @@
+use super::link_fs::{LinkFs, LinkFsRef, StdFs};
+use super::{is_valid_prefix, DetachedMode, LinkKind, LinkMeta, WCPath, WCStr, WslCmdManifest};
+use super::{similar_cmds, wsl_find_cmds, wsl_path_executables};
+use std::collections::{BTreeMap, HashSet};
+use std::ffi::OsStr;
+use std::io;
+use std::io::{Error, ErrorKind};
+use std::ops::*;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, SystemTime};
+
+use super::DETACHED_PROC_PREFIX;
+
+/// Extension of Windows binary
+const BINARY_EXTENSION: &str = "exe";
+
+/// Extension appended to existing files, which are moved aside on forced linking
+const BACKUP_EXTENSION: &str = "wslcmd-backup";
+
+macro_rules! wslcmd_with_ext {
+    ($label:expr) => {
+        format!("{}.{}", $label, BINARY_EXTENSION)
+    };
+}
+
+macro_rules! wslcmd_detached_bin {
+    ($label:expr) => {
+        format!("{}{}", DETACHED_PROC_PREFIX, $label)
+    };
+}
+
+/// Find and fix problematic links
+mod doctor;
+pub use doctor::LinkIssue;
+
+/// Export and import sets of links
+mod link_set;
+pub use link_set::LinkSet;
+
+/// Retarget links of another WslCmd binary
+mod retarget;
+
+/// Select links by name or pattern
+mod select;
+pub use select::CmdSelector;
+
+/// Scan link directory, and cache the list on disk
+mod list_index;
+
+/// Record operations to the journal, and undo them
+mod journal;
+pub use journal::JournalEntry;
+
+/// Install the binary and links to another directory, or uninstall them
+mod install;
+
+/// Details of linked commands
+mod link_info;
+pub use link_info::{LinkHealth, LinkInfo};
+
+/// Lock link directory among WslCmd processes
+mod dir_lock;
+use dir_lock::DirLock;
+
+type LinkResults = Vec<(String, io::Result<()>)>;
+
+/// Default timeout to wait for other WslCmd processes holding the lock
+const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug)]
+/// Read dir and load all wslcmds, and manage the list
+pub struct WslCmdList {
+    /// Path of target bin
+    binpath: PathBuf,
+
+    /// Path of original bin, after following all symlinks
+    orig_binpath: PathBuf,
+
+    /// Directory where links are placed (Default: directory of target bin)
+    linkdir: PathBuf,
+
+    /// Latest WslCmd list
+    cmdlist_cached: HashSet<String>,
+
+    /// Time of WslCmd list data
+    cmdlist_cached_time: Option<SystemTime>,
+
+    /// Manifest storing metadata of links
+    manifest: WslCmdManifest,
+
+    /// Lock of linkdir, held during a mutating operation
+    lock: Option<DirLock>,
+
+    /// Filesystem where links are placed
+    fs: LinkFsRef,
+
+    /// Timeout to wait for the lock of linkdir
+    lock_timeout: Duration,
+}
+
+#[derive(Debug, Default, Clone)]
+/// Options for linking a new WSL command
+pub struct LinkOptions {
+    /// Namespace prefix of the link
+    prefix: Option<String>,
+
+    /// Description of the link
+    description: Option<String>,
+
+    /// Kind of link files
+    kind: LinkKind,
+
+    /// Which of the link and the detached link are created
+    detached: DetachedMode,
+
+    /// Check that the WSL command exists before linking
+    verify: bool,
+
+    /// Move existing files with the link name to backups, instead of failing
+    force: bool,
+
+    /// Tags of the link
+    tags: Vec<String>,
+}
+
+impl LinkOptions {
+    ///
+    /// Create new [`LinkOptions`] with default options
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let opts = LinkOptions::new().prefix("w-").description("Find of WSL");
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Set namespace prefix of the link
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - A namespace prefix. The link is named as `prefix` + `cmdname`.
+    ///
+    #[allow(dead_code)]
+    pub fn prefix<T: WCStr>(mut self, prefix: T) -> Self {
+        self.prefix = prefix.wcstr_clone_to_string();
+
+        self
+    }
+
+    ///
+    /// Set description of the link, which is stored in the manifest
+    ///
+    /// # Arguments
+    ///
+    /// * `description` - Description of the link
+    ///
+    #[allow(dead_code)]
+    pub fn description<T: WCStr>(mut self, description: T) -> Self {
+        self.description = description.wcstr_clone_to_string();
+
+        self
+    }
+
+    ///
+    /// Set kind of link files (Default: [`LinkKind::Symlink`])
+    ///
+    /// Use [`LinkKind::Hardlink`] or [`LinkKind::Copy`]
+    /// if symlinks cannot be created (No Developer Mode or admin privileges).
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - Kind of link files
+    ///
+    #[allow(dead_code)]
+    pub fn kind(mut self, kind: LinkKind) -> Self {
+        self.kind = kind;
+
+        self
+    }
+
+    ///
+    /// Set which of the link and the detached link are created (Default: [`DetachedMode::Both`])
+    ///
+    /// Shim scripts have no detached link, so only [`DetachedMode::Both`] is valid for them.
+    ///
+    /// # Arguments
+    ///
+    /// * `detached` - Detached mode of the link
+    ///
+    #[allow(dead_code)]
+    pub fn detached(mut self, detached: DetachedMode) -> Self {
+        self.detached = detached;
+
+        self
+    }
+
+    ///
+    /// Set whether to check that the WSL command exists before linking (Default: `false`)
+    ///
+    /// The command is found with `command -v`, as the WSL user and distribution
+    /// of the link name. If not found, linking fails with similar commands on WSL PATH.
+    ///
+    /// # Arguments
+    ///
+    /// * `verify` - Check the WSL command before linking
+    ///
+    #[allow(dead_code)]
+    pub fn verify(mut self, verify: bool) -> Self {
+        self.verify = verify;
+
+        self
+    }
+
+    ///
+    /// Set whether to move existing files with the link name to backups (Default: `false`)
+    ///
+    /// Files which are not WslCmd links (Ex: 'make.exe' of another program) are renamed
+    /// to '(file).wslcmd-backup', recorded in the manifest, and restored when the link is removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `force` - Back up existing files instead of failing
+    ///
+    #[allow(dead_code)]
+    pub fn force(mut self, force: bool) -> Self {
+        self.force = force;
+
+        self
+    }
+
+    ///
+    /// Set tags of the link, which are stored in the manifest to group links
+    ///
+    /// # Arguments
+    ///
+    /// * `tags` - Tags of the link (Ex: `["build", "ci"]`)
+    ///
+    #[allow(dead_code)]
+    pub fn tags<T: WCStr>(mut self, tags: &[T]) -> Self {
+        self.tags = tags
+            .iter()
+            .filter_map(WCStr::wcstr_clone_to_string)
+            .collect();
+
+        self
+    }
+}
+
+impl WslCmdList {
+    ///
+    /// Create new [`WslCmdList`]
+    ///
+    /// # Arguments
+    ///
+    /// * `binpath` - A target wslcmd bin path to get list of wslcmd
+    ///
+    /// # Return
+    ///
+    /// A newly created [`Some`]\([`WslCmdList`]\), initialized with wslcmd list.
+    ///
+    /// [`None`] if failed to initialize [`WslCmdList`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let wslcmd_list = WslCmdList::new(&"/path/to/target/exe");
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn new<T: WCPath>(binpath: &T) -> Option<Self> {
+        Self::with_dir(binpath, &binpath.wcpath_parent())
+    }
+
+    ///
+    /// Create new [`WslCmdList`], of which links are placed in the given directory
+    ///
+    /// The manifest (with namespace prefixes) is also stored in the link directory,
+    /// so that one binary can be shared by multiple link directories.
+    ///
+    /// # Arguments
+    ///
+    /// * `binpath` - A target wslcmd bin path, which links point to
+    /// * `linkdir` - An existing directory to place links
+    ///
+    /// # Return
+    ///
+    /// A newly created [`Some`]\([`WslCmdList`]\), initialized with wslcmd list.
+    ///
+    /// [`None`] if failed to initialize [`WslCmdList`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let wslcmd_list = WslCmdList::with_dir(&"/path/to/target/exe", &"/path/to/link/dir");
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn with_dir<T: WCPath, U: WCPath>(binpath: &T, linkdir: &U) -> Option<Self> {
+        Self::with_fs(binpath, linkdir, Rc::new(StdFs))
+    }
+
+    ///
+    /// Create new [`WslCmdList`] on the given filesystem
+    ///
+    /// All files of links and the manifest are accessed through `fs`,
+    /// so that link logic can be tested on an in-memory filesystem.
+    ///
+    /// # Arguments
+    ///
+    /// * `binpath` - A target wslcmd bin path, which links point to
+    /// * `linkdir` - An existing directory to place links
+    /// * `fs` - Filesystem of both paths
+    ///
+    /// # Return
+    ///
+    /// A newly created [`Some`]\([`WslCmdList`]\), initialized with wslcmd list.
+    ///
+    /// [`None`] if failed to initialize [`WslCmdList`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let wslcmd_list = WslCmdList::with_fs(&"/path/to/target/exe", &"/path/to/link/dir", Rc::new(StdFs));
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn with_fs<T: WCPath, U: WCPath>(
+        binpath: &T,
+        linkdir: &U,
+        fs: Rc<dyn LinkFs>,
+    ) -> Option<Self> {
+        Self::with_fs_ref(binpath, linkdir, LinkFsRef::new(fs))
+    }
+
+    // create new WslCmdList on the shared filesystem
+    fn with_fs_ref<T: WCPath, U: WCPath>(binpath: &T, linkdir: &U, fs: LinkFsRef) -> Option<Self> {
+        // initialize basic info
+        let binpath = binpath.wcpath_clone_to_pathbuf()?;
+        let orig_binpath = fs.canonicalize(&binpath).ok()?;
+        let linkdir = linkdir
+            .wcpath_clone_to_pathbuf()
+            .filter(|pb| fs.is_dir(pb))?;
+        let manifest = {
+            WslCmdManifest::load_with(&linkdir.join(binpath.wcpath_filename()?), fs.clone()).ok()?
+        };
+
+        // build struct instance
+        let mut ret_self = Self {
+            binpath,
+            orig_binpath,
+            linkdir,
+            cmdlist_cached: HashSet::new(), // dummy
+            cmdlist_cached_time: None,      // dummy
+            manifest,
+            lock: None,
+            fs,
+            lock_timeout: DEFAULT_LOCK_TIMEOUT,
+        };
+        ret_self.refresh_wslcmd_list(true); // refresh cmdlist and time
+
+        Some(ret_self)
+    }
+
+    ///
+    /// Link a new WSL command to current binary
+    ///
+    /// # Arguments
+    ///
+    /// * `cmdname` - A target command basename to link with WSL shell
+    ///
+    /// # Return
+    ///
+    /// [`Ok`]\([`()`](unit)\) if succeeded, [`Err`]\([`Error`]\) if failed
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let result = wslcmd_list.link_wslcmd("emacs");
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn link_wslcmd<T: WCPath>(&mut self, cmdname: &T) -> io::Result<()> {
+        self.link_wslcmd_with(cmdname, &LinkOptions::new())
+    }
+
+    ///
+    /// Link a new WSL command to current binary, with a namespace prefix
+    ///
+    /// The link is named as `prefix` + `cmdname`,
+    /// and the prefix is stripped from the link name when executed.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix`  - A namespace prefix of the link
+    /// * `cmdname` - A target command basename to link with WSL shell
+    ///
+    /// # Return
+    ///
+    /// [`Ok`]\([`()`](unit)\) if succeeded, [`Err`]\([`Error`]\) if failed
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// // creates 'w-find.exe', which executes 'find' of WSL
+    /// let result = wslcmd_list.link_wslcmd_with_prefix("w-", "find");
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn link_wslcmd_with_prefix<T: WCPath>(
+        &mut self,
+        prefix: &str,
+        cmdname: &T,
+    ) -> io::Result<()> {
+        self.link_wslcmd_with(cmdname, &LinkOptions::new().prefix(prefix))
+    }
+
+    ///
+    /// Link a new WSL command to current binary, with options
+    ///
+    /// Metadata of the link is recorded to the manifest,
+    /// and the link is removed again if failed to record it.
+    ///
+    /// # Arguments
+    ///
+    /// * `cmdname` - A target command basename to link with WSL shell
+    /// * `opts`    - Options for the link
+    ///
+    /// # Return
+    ///
+    /// [`Ok`]\([`()`](unit)\) if succeeded, [`Err`]\([`Error`]\) if failed
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let result = wslcmd_list.link_wslcmd_with(
+    ///     "emacs",
+    ///     &LinkOptions::new().description("Emacs of WSL"),
+    /// );
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn link_wslcmd_with<T: WCPath>(
+        &mut self,
+        cmdname: &T,
+        opts: &LinkOptions,
+    ) -> io::Result<()> {
+        self.with_journal("link", |this| {
+            // build link name: prefix + cmdname
+            let linkname = this.linkname_with(cmdname, opts)?;
+            check_tags(&opts.tags)?;
+
+            // check that the WSL command exists, if required
+            if opts.verify {
+                this.verify_wslcmds(std::slice::from_ref(&linkname), opts.prefix.as_deref())
+                    .map_err(|(_, e)| e)?;
+            }
+
+            // create new PathBuf of cmd
+            this.cmd_pathbuf(&linkname)
+                // Ok if given cmd is not wslcmd file
+                .and_then(|pb_cmd| {
+                    // bool expression
+                    (!this.is_wslcmd_file(&pb_cmd))
+                        // bool -> Result
+                        .then_some(pb_cmd)
+                        .ok_or(Error::new(
+                            ErrorKind::AlreadyExists,
+                            "WslCmd already exists for given cmdname",
+                        ))
+                })
+                // move existing files aside if forced, then create new link chain
+                // (wslcmd -> wslcmd_detached -> origbin)
+                .and_then(|pb_cmd| {
+                    let backups = match opts.force {
+                        true => {
+                            this.backup_files(&link_file_paths(&pb_cmd, opts.kind, opts.detached))?
+                        }
+                        false => vec![],
+                    };
+                    this.create_link_chain(&pb_cmd, opts.kind, opts.detached)
+                        .map(|_| (pb_cmd, backups.clone()))
+                        .inspect_err(|_| {
+                            // if failed, restore progress (move backups back)
+                            this.restore_backups(&backups).ok();
+                        })
+                })
+                // record metadata to manifest
+                .and_then(|(pb_cmd, backups)| {
+                    // backups of the missing link with the same name are kept
+                    let backups_missing = {
+                        this.manifest
+                            .get(&linkname)
+                            .filter(|meta| *meta.get_missing())
+                            .map(|meta| meta.get_backups().clone())
+                            .unwrap_or_default()
+                    };
+                    let meta = LinkMeta::new(&linkname, opts.prefix.as_deref())
+                        .description(opts.description.as_deref())
+                        .kind(opts.kind)
+                        .detached(opts.detached)
+                        .backups([backups_missing, backups.clone()].concat())
+                        .tags(opts.tags.clone());
+                    let meta_prev = this.manifest.insert(&linkname, meta);
+
+                    this.manifest.save().inspect_err(|_| {
+                        // if failed, restore manifest and clean progress (remove created links)
+                        match meta_prev {
+                            Some(meta_prev) => this.manifest.insert(&linkname, meta_prev),
+                            None => this.manifest.remove(&linkname),
+                        };
+                        this.remove_link_chain(&pb_cmd, opts.kind, opts.detached)
+                            .ok();
+                        this.restore_backups(&backups).ok();
+                    })
+                })
+                // refresh wslcmd list if succeeded
+                .map(|_| {
+                    this.refresh_wslcmd_list(true);
+                })
+        })
+    }
+
+    ///
+    /// Unlink an existing WSL command link
+    ///
+    /// # Arguments
+    ///
+    /// * `cmdname` - A target command basename to unlink with WSL shell
+    ///
+    /// # Return
+    ///
+    /// [`Ok`]\([`()`](unit)\) if succeeded, [`Err`]\([`Error`]\) if failed
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let result = wslcmd_list.unlink_wslcmd("emacs");
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn unlink_wslcmd<T: WCPath>(&mut self, cmdname: &T) -> io::Result<()> {
+        self.with_journal("unlink", |this| {
+            // link files are all missing: forget the link recorded in manifest
+            if let Some(linkname) = {
+                this.cmd_pathbuf(cmdname)
+                    .ok()
+                    .and_then(|pb_cmd| this.missing_linkname(&pb_cmd))
+            } {
+                return this.forget_missing_link(&linkname);
+            }
+
+            // create new PathBuf of cmd
+            this.cmd_pathbuf(cmdname)
+                // Ok if given cmd file (or its detached link or shim script) exists
+                .and_then(|pb_cmd| {
+                    // bool expression
+                    {
+                        let (kind, detached) = this.link_layout(&pb_cmd);
+                        this.fs.exists(&pb_cmd)
+                            || link_file_paths(&pb_cmd, kind, detached)
+                                .iter()
+                                .any(|pb| this.fs.exists(pb))
+                    }
+                    // bool -> Result
+                    .then_some(pb_cmd)
+                    .ok_or(Error::new(
+                        ErrorKind::AlreadyExists,
+                        "WslCmd file does not exist",
+                    ))
+                })
+                // Ok if given cmd is wslcmd file
+                .and_then(|pb_cmd| {
+                    // bool expression
+                    this.is_wslcmd_file(&pb_cmd)
+                        // bool -> Result
+                        .then_some(pb_cmd)
+                        .ok_or(Error::new(
+                            ErrorKind::AlreadyExists,
+                            "WslCmd already exists for given cmdname",
+                        ))
+                })
+                // remove wslcmd link chain
+                .and_then(|pb_cmd| {
+                    let (kind, detached) = this.link_layout(&pb_cmd);
+                    this.remove_link_chain(&pb_cmd, kind, detached)
+                        .map(|_| (pb_cmd, kind, detached))
+                })
+                // remove metadata from manifest
+                .and_then(|(pb_cmd, kind, detached)| {
+                    let linkname = {
+                        pb_cmd
+                            .wcpath_basename()
+                            .ok_or(Error::other("Invalid cmdname"))?
+                    };
+
+                    match this.manifest.remove(linkname) {
+                        Some(meta) => {
+                            let backups = meta.get_backups().clone();
+                            this.manifest
+                                .save()
+                                // restore files backed up on linking
+                                .and_then(|_| this.restore_backups(&backups))
+                                .inspect_err(|_| {
+                                    // if failed, restore manifest and restore progress (re-link removed links)
+                                    this.manifest.insert(linkname, meta);
+                                    this.manifest.save().ok();
+                                    this.create_link_chain(&pb_cmd, kind, detached).ok();
+                                })
+                        }
+                        None => Ok(()),
+                    }
+                })
+                // refresh wslcmd list if succeeded
+                .map(|_| {
+                    this.refresh_wslcmd_list(true);
+                })
+        })
+    }
+
+    ///
+    /// Rename an existing WSL command link, with its metadata
+    ///
+    /// # Arguments
+    ///
+    /// * `cmdname`     - A linked command basename to rename
+    /// * `cmdname_new` - A new command basename
+    ///
+    /// # Return
+    ///
+    /// [`Ok`]\([`()`](unit)\) if succeeded, [`Err`]\([`Error`]\) if failed.
+    /// If failed, all changes are rolled back.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let result = wslcmd_list.rename_wslcmd("python", "python!!debian");
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn rename_wslcmd<T: WCPath, U: WCPath>(
+        &mut self,
+        cmdname: &T,
+        cmdname_new: &U,
+    ) -> io::Result<()> {
+        self.with_journal("rename", |this| {
+            this.duplicate_wslcmd(cmdname, cmdname_new, true)
+        })
+    }
+
+    ///
+    /// Copy an existing WSL command link to a new name, with its metadata
+    ///
+    /// # Arguments
+    ///
+    /// * `cmdname`     - A linked command basename to copy
+    /// * `cmdname_new` - A new command basename
+    ///
+    /// # Return
+    ///
+    /// [`Ok`]\([`()`](unit)\) if succeeded, [`Err`]\([`Error`]\) if failed.
+    /// If failed, all changes are rolled back.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let result = wslcmd_list.copy_wslcmd("python", "python!!debian");
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn copy_wslcmd<T: WCPath, U: WCPath>(
+        &mut self,
+        cmdname: &T,
+        cmdname_new: &U,
+    ) -> io::Result<()> {
+        self.with_journal("copy", |this| {
+            this.duplicate_wslcmd(cmdname, cmdname_new, false)
+        })
+    }
+
+    ///
+    /// Convert an existing link to another detached mode, re-creating its link files
+    ///
+    /// # Arguments
+    ///
+    /// * `cmdname`  - A linked command name
+    /// * `detached` - New detached mode of the link
+    ///
+    /// # Return
+    ///
+    /// [`Ok`]\([`()`](unit)\) if succeeded (or already in the mode), [`Err`]\([`Error`]\) if failed.
+    /// If failed, the link is left as it was.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// // replace 'git.exe' -> '.git.exe' -> bin with 'git.exe' -> bin
+    /// let result = wslcmd_list.set_detached_mode("git", DetachedMode::NoDetached);
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn set_detached_mode<T: WCPath>(
+        &mut self,
+        cmdname: &T,
+        detached: DetachedMode,
+    ) -> io::Result<()> {
+        self.with_journal("convert", |this| {
+            // Ok if given cmd is wslcmd file
+            let pb_cmd = this.cmd_pathbuf(cmdname).and_then(|pb_cmd| {
+                this.is_wslcmd_file(&pb_cmd)
+                    .then_some(pb_cmd)
+                    .ok_or(Error::new(ErrorKind::NotFound, "WslCmd does not exist"))
+            })?;
+            let linkname = {
+                pb_cmd
+                    .wcpath_basename()
+                    .map(String::from)
+                    .ok_or(Error::new(ErrorKind::InvalidInput, "Invalid cmdname"))?
+            };
+            let (kind, detached_prev) = this.link_layout(&pb_cmd);
+            kind.shim_extensions()
+                .is_empty()
+                .then_some(())
+                .ok_or(Error::new(
+                    ErrorKind::InvalidInput,
+                    "Shim script has no detached link",
+                ))?;
+            if detached == detached_prev {
+                return Ok(());
+            }
+            let meta_prev = {
+                this.manifest
+                    .get(&linkname)
+                    .cloned()
+                    .unwrap_or_else(|| LinkMeta::new(&linkname, None))
+            };
+
+            // remove existing link files
+            this.remove_link_chain(&pb_cmd, kind, detached_prev)
+                // create link files of new mode
+                .and_then(|_| {
+                    this.create_link_chain(&pb_cmd, kind, detached)
+                        .inspect_err(|_| {
+                            // if failed, restore progress (re-create removed links)
+                            this.create_link_chain(&pb_cmd, kind, detached_prev).ok();
+                        })
+                })
+                // record new mode to manifest
+                .and_then(|_| {
+                    this.manifest
+                        .insert(&linkname, meta_prev.clone().detached(detached));
+                    this.manifest.save().inspect_err(|_| {
+                        // if failed, restore manifest and links
+                        this.manifest.insert(&linkname, meta_prev);
+                        this.remove_link_chain(&pb_cmd, kind, detached).ok();
+                        this.create_link_chain(&pb_cmd, kind, detached_prev).ok();
+                    })
+                })
+                // refresh wslcmd list if succeeded
+                .map(|_| {
+                    this.refresh_wslcmd_list(true);
+                })
+        })
+    }
+
+    ///
+    /// Link new WSL commands all at once, with the same options
+    ///
+    /// All commands are validated before linking any of them,
+    /// and all created links are removed again if failed to link any of them.
+    ///
+    /// # Arguments
+    ///
+    /// * `cmdnames` - Target command basenames to link with WSL shell
+    /// * `opts`     - Options for all links
+    ///
+    /// # Return
+    ///
+    /// [`Ok`]\([`()`](unit)\) if all succeeded,
+    /// [`Err`]\([`Error`]\) with the failed command name if failed (nothing is linked)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let result = wslcmd_list.link_wslcmd_batch(&["git", "emacs"], &LinkOptions::new());
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn link_wslcmd_batch<T: WCPath>(
+        &mut self,
+        cmdnames: &[T],
+        opts: &LinkOptions,
+    ) -> io::Result<()> {
+        self.with_journal("link", |this| {
+            // validate all before linking
+            let mut names_found = HashSet::new();
+            let linknames = cmdnames
+                .iter()
+                .map(|cmdname| {
+                    let linkname = this.linkname_with(cmdname, opts)?;
+                    this.check_linkable(&linkname, opts)
+                        .and_then(|_| {
+                            names_found
+                                .insert(linkname.clone())
+                                .then_some(())
+                                .ok_or(Error::new(ErrorKind::InvalidInput, "Duplicated cmdname"))
+                        })
+                        .map(|_| linkname.clone())
+                        .map_err(|e| batch_error(&linkname, e))
+                })
+                .collect::<io::Result<Vec<String>>>()?;
+
+            // check that all WSL commands exist, if required
+            if opts.verify {
+                this.verify_wslcmds(&linknames, opts.prefix.as_deref())
+                    .map_err(|(linkname, e)| batch_error(&linkname, e))?;
+            }
+            let opts = &opts.clone().verify(false); // already checked
+
+            // link all, or remove created links if failed
+            (0..cmdnames.len()).try_for_each(|i| {
+                this.link_wslcmd_with(&cmdnames[i], opts).map_err(|e| {
+                    linknames[..i].iter().rev().for_each(|linkname| {
+                        this.unlink_wslcmd(linkname).ok();
+                    });
+                    batch_error(&linknames[i], e)
+                })
+            })
+        })
+    }
+
+    ///
+    /// Unlink existing WSL command links all at once
+    ///
+    /// All commands are validated before unlinking any of them,
+    /// and all removed links are restored with their metadata if failed to unlink any of them.
+    ///
+    /// # Arguments
+    ///
+    /// * `cmdnames` - Target command basenames to unlink
+    ///
+    /// # Return
+    ///
+    /// [`Ok`]\([`()`](unit)\) if all succeeded,
+    /// [`Err`]\([`Error`]\) with the failed command name if failed (nothing is unlinked)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let result = wslcmd_list.unlink_wslcmd_batch(&["git", "emacs"]);
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn unlink_wslcmd_batch<T: WCPath>(&mut self, cmdnames: &[T]) -> io::Result<()> {
+        self.with_journal("unlink", |this| {
+            // validate all before unlinking, keeping metadata to restore
+            let mut names_found = HashSet::new();
+            let links = cmdnames
+                .iter()
+                .map(|cmdname| {
+                    let linkname = cmdname
+                        .wcpath_filename()
+                        .map(String::from)
+                        .ok_or(Error::new(ErrorKind::InvalidInput, "Invalid cmdname"))?;
+                    this.cmd_pathbuf(&linkname)
+                        .and_then(|pb_cmd| {
+                            (this.is_wslcmd_file(&pb_cmd)
+                                || this.missing_linkname(&pb_cmd).is_some())
+                            .then_some(())
+                            .ok_or(Error::new(ErrorKind::NotFound, "WslCmd does not exist"))
+                        })
+                        .and_then(|_| {
+                            names_found
+                                .insert(linkname.clone())
+                                .then_some(())
+                                .ok_or(Error::new(ErrorKind::InvalidInput, "Duplicated cmdname"))
+                        })
+                        .map(|_| {
+                            let meta = this
+                                .manifest
+                                .get(&linkname)
+                                .cloned()
+                                .unwrap_or_else(|| LinkMeta::new(&linkname, None));
+                            (linkname.clone(), meta)
+                        })
+                        .map_err(|e| batch_error(&linkname, e))
+                })
+                .collect::<io::Result<Vec<(String, LinkMeta)>>>()?;
+
+            // unlink all, or restore removed links if failed
+            (0..links.len()).try_for_each(|i| {
+                this.unlink_wslcmd(&links[i].0).map_err(|e| {
+                    links[..i].iter().rev().for_each(|(linkname, meta)| {
+                        this.restore_wslcmd(linkname, meta.clone()).ok();
+                    });
+                    batch_error(&links[i].0, e)
+                })
+            })
+        })
+    }
+
+    ///
+    /// Get list of WSL command links
+    ///
+    /// # Return
+    ///
+    /// List of WSL commands
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let cmdlist: &HashSet<String> = wslcmd_list.get_cmdlist();
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn get_cmdlist(&mut self) -> &HashSet<String> {
+        &self.refresh_wslcmd_list(false).cmdlist_cached
+    }
+
+    ///
+    /// Get path of target bin, which links point to
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let binpath: &PathBuf = wslcmd_list.get_binpath();
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn get_binpath(&self) -> &PathBuf {
+        &self.binpath
+    }
+
+    ///
+    /// Get directory where links are placed
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let linkdir: &PathBuf = wslcmd_list.get_linkdir();
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn get_linkdir(&self) -> &PathBuf {
+        &self.linkdir
+    }
+
+    ///
+    /// Get metadata of a linked WSL command, recorded in the manifest
+    ///
+    /// # Arguments
+    ///
+    /// * `cmdname` - A linked command name
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let meta: Option<&LinkMeta> = wslcmd_list.get_link_meta("emacs");
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn get_link_meta(&mut self, cmdname: &str) -> Option<&LinkMeta> {
+        self.refresh_wslcmd_list(false).manifest.get(cmdname)
+    }
+
+    ///
+    /// Add or remove tags of a linked WSL command, which are stored in the manifest
+    ///
+    /// # Arguments
+    ///
+    /// * `cmdname`     - A linked command name
+    /// * `tags_add`    - Tags to add
+    /// * `tags_remove` - Tags to remove
+    ///
+    /// # Return
+    ///
+    /// [`Ok`]\([`()`](unit)\) if succeeded, [`Err`]\([`Error`]\) if the link does not exist,
+    /// any tag is invalid, or failed to save the manifest
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let result = wslcmd_list.update_link_tags("gcc", &["build"], &["experiments"]);
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn update_link_tags<T: WCPath, U: WCStr, V: WCStr>(
+        &mut self,
+        cmdname: &T,
+        tags_add: &[U],
+        tags_remove: &[V],
+    ) -> io::Result<()> {
+        let tags_add: Vec<String> = {
+            tags_add
+                .iter()
+                .filter_map(WCStr::wcstr_clone_to_string)
+                .collect()
+        };
+        let tags_remove: Vec<&str> = tags_remove.iter().filter_map(WCStr::wcstr_as_ref).collect();
+        check_tags(&tags_add)?;
+
+        self.with_journal("tag", |this| {
+            // Ok if given cmd is wslcmd file
+            let linkname = this.cmd_pathbuf(cmdname).and_then(|pb_cmd| {
+                this.is_wslcmd_file(&pb_cmd)
+                    .then(|| pb_cmd.wcpath_basename().map(String::from))
+                    .flatten()
+                    .ok_or(Error::new(ErrorKind::NotFound, "WslCmd does not exist"))
+            })?;
+            let meta_prev = {
+                this.manifest
+                    .get(&linkname)
+                    .cloned()
+                    .unwrap_or_else(|| LinkMeta::new(&linkname, None))
+            };
+            let tags = {
+                meta_prev
+                    .get_tags()
+                    .iter()
+                    .filter(|s| !tags_remove.contains(&s.as_str()))
+                    .cloned()
+                    .chain(tags_add.iter().cloned())
+                    .collect()
+            };
+
+            // record new tags to manifest
+            this.manifest
+                .insert(&linkname, meta_prev.clone().tags(tags));
+            this.manifest.save().inspect_err(|_| {
+                // if failed, restore manifest
+                this.manifest.insert(&linkname, meta_prev);
+            })
+        })
+    }
+
+    ///
+    /// Set timeout to wait for other WslCmd processes operating on the same link directory
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - Timeout to wait for the lock of link directory (Default: 10 secs)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// wslcmd_list.set_lock_timeout(Duration::from_secs(30));
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn set_lock_timeout(&mut self, timeout: Duration) {
+        self.lock_timeout = timeout;
+    }
+
+    // run mutating operation while holding the lock of linkdir
+    // manifest is reloaded after locked, as other processes may have changed them
+    // returns Err(TimedOut) if another process holds the lock until timeout
+    fn with_lock<R, F: FnOnce(&mut Self) -> io::Result<R>>(&mut self, f: F) -> io::Result<R> {
+        // already locked by outer operation
+        if self.lock.is_some() {
+            return f(self);
+        }
+
+        self.lock = Some(DirLock::acquire(
+            &*self.fs,
+            &self.linkdir,
+            self.lock_timeout,
+        )?);
+        let ret = WslCmdManifest::load_with(self.manifest.get_path(), self.fs.clone()).and_then(
+            |manifest| {
+                self.manifest = manifest;
+                self.refresh_wslcmd_list(true);
+                f(self)
+            },
+        );
+        self.lock = None; // unlock
+
+        ret
+    }
+
+    // run mutating operation with with_lock, then record changed links to the journal
+    // changes are recorded even if failed in the middle, as some may not be rolled back
+    fn with_journal<R, F: FnOnce(&mut Self) -> io::Result<R>>(
+        &mut self,
+        operation: &str,
+        f: F,
+    ) -> io::Result<R> {
+        // recorded by outer operation
+        if self.lock.is_some() {
+            return f(self);
+        }
+
+        self.with_lock(|this| {
+            let links_before = this.manifest.get_links().clone();
+            let ret = f(this);
+            this.record_journal(operation, &links_before).ok(); // bypass err of journal
+
+            ret
+        })
+    }
+
+    // create new link from existing link with its metadata, then remove existing one if rename
+    // if failed in the middle, all progress is rolled back
+    fn duplicate_wslcmd<T: WCPath, U: WCPath>(
+        &mut self,
+        cmdname: &T,
+        cmdname_new: &U,
+        is_rename: bool,
+    ) -> io::Result<()> {
+        // (pb_cmd, pb_cmd_new): Ok if given cmd is wslcmd file, and new cmd is not
+        let (pb_cmd, pb_cmd_new) = {
+            self.cmd_pathbuf(cmdname)
+                .and_then(|pb_cmd| {
+                    self.is_wslcmd_file(&pb_cmd)
+                        .then_some(pb_cmd)
+                        .ok_or(Error::new(ErrorKind::NotFound, "WslCmd does not exist"))
+                })
+                .and_then(|pb_cmd| {
+                    self.cmd_pathbuf(cmdname_new)
+                        // Ok if new cmd is not wslcmd file
+                        .and_then(|pb_cmd_new| {
+                            (!self.is_wslcmd_file(&pb_cmd_new))
+                                .then_some((pb_cmd, pb_cmd_new))
+                                .ok_or(Error::new(
+                                    ErrorKind::AlreadyExists,
+                                    "WslCmd already exists for given new cmdname",
+                                ))
+                        })
+                })?
+        };
+        let (linkname, linkname_new) = {
+            pb_cmd
+                .wcpath_basename()
+                .zip(pb_cmd_new.wcpath_basename())
+                .map(|(s, s_new)| (s.to_string(), s_new.to_string()))
+                .ok_or(Error::new(ErrorKind::InvalidInput, "Invalid cmdname"))?
+        };
+
+        // metadata of new link, with the same kind of link files
+        // (backups of existing files belong to the existing link name)
+        let (kind, detached) = self.link_layout(&pb_cmd);
+        let backups = {
+            self.manifest
+                .get(&linkname)
+                .map(|meta| meta.get_backups().clone())
+                .unwrap_or_default()
+        };
+        // namespace prefix of the link is kept, if the new name still starts with it
+        let prefix = {
+            self.manifest
+                .get(&linkname)
+                .and_then(|meta| meta.get_prefix().clone())
+        };
+        let meta_new = {
+            let prefix_new = prefix.as_deref().filter(|prefix| {
+                linkname_new.len() > prefix.len() && linkname_new.starts_with(prefix)
+            });
+            match self.manifest.get(&linkname) {
+                Some(meta) if is_rename => meta.renamed(&linkname_new, prefix_new),
+                Some(meta) => LinkMeta::new(&linkname_new, prefix_new)
+                    .description(meta.get_description().as_deref())
+                    .tags(meta.get_tags().clone()),
+                None => LinkMeta::new(&linkname_new, prefix_new),
+            }
+            .kind(kind)
+            .detached(detached)
+            .backups(vec![])
+        };
+
+        // create new link chain
+        self.create_link_chain(&pb_cmd_new, kind, detached)
+            // record metadata to manifest
+            .and_then(|_| {
+                self.manifest.insert(&linkname_new, meta_new);
+                let meta = match is_rename {
+                    true => self.manifest.remove(&linkname),
+                    false => None,
+                };
+
+                self.manifest.save().inspect_err(|_| {
+                    // if failed, restore manifest and clean progress (remove created links)
+                    self.manifest.remove(&linkname_new);
+                    meta.map(|meta| self.manifest.insert(&linkname, meta));
+                    self.remove_link_chain(&pb_cmd_new, kind, detached).ok();
+                })
+            })
+            // remove existing link chain if rename
+            .and_then(|_| match is_rename {
+                true => self
+                    .remove_link_chain(&pb_cmd, kind, detached)
+                    .inspect_err(|_| {
+                        // if failed, restore manifest and clean progress (remove created links)
+                        self.manifest.remove(&linkname_new).map(|meta_new| {
+                            self.manifest.insert(
+                                &linkname,
+                                meta_new
+                                    .renamed(&linkname, prefix.as_deref())
+                                    .backups(backups.clone()),
+                            )
+                        });
+                        self.manifest.save().ok();
+                        self.remove_link_chain(&pb_cmd_new, kind, detached).ok();
+                    })
+                    // restore files backed up for existing link name, which is now free
+                    .map(|_| {
+                        self.restore_backups(&backups).ok();
+                    }),
+                false => Ok(()),
+            })
+            // refresh wslcmd list if succeeded
+            .map(|_| {
+                self.refresh_wslcmd_list(true);
+            })
+    }
+
+    // build link name from cmdname and options: prefix + cmdname
+    // returns Err if invalid prefix or cmdname
+    fn linkname_with<T: WCPath>(&self, cmdname: &T, opts: &LinkOptions) -> io::Result<String> {
+        opts.prefix
+            .as_deref()
+            .is_none_or(is_valid_prefix)
+            .then(|| cmdname.wcpath_filename())
+            .flatten()
+            .map(|s_cmd| format!("{}{}", opts.prefix.as_deref().unwrap_or_default(), s_cmd))
+            .ok_or(Error::new(ErrorKind::InvalidInput, "Invalid cmdname"))
+    }
+
+    // check that WSL commands of links exist, with a single query for each WSL user and distribution
+    // returns Err((linkname, e)) of the first command not found, with similar commands on WSL PATH
+    fn verify_wslcmds(
+        &self,
+        linknames: &[String],
+        prefix: Option<&str>,
+    ) -> Result<(), (String, Error)> {
+        // (user, dist) -> [(linkname, command)]
+        let mut cmds_of = BTreeMap::<_, Vec<(String, String)>>::new();
+        linknames.iter().for_each(|linkname| {
+            let meta = LinkMeta::new(linkname, prefix);
+            cmds_of
+                .entry((meta.get_user().clone(), meta.get_dist().clone()))
+                .or_default()
+                .push((linkname.clone(), meta.get_command().clone()));
+        });
+
+        cmds_of.iter().try_for_each(|((user, dist), cmds)| {
+            let found = wsl_find_cmds(
+                user.as_deref(),
+                dist.as_deref(),
+                &cmds.iter().map(|(_, cmd)| cmd.as_str()).collect::<Vec<_>>(),
+            )
+            .ok_or_else(|| {
+                (
+                    cmds[0].0.clone(),
+                    Error::other(
+                        "Failed to query WSL for the command (Check the user and distribution)",
+                    ),
+                )
+            })?;
+
+            match cmds.iter().find(|(_, cmd)| !found.contains(cmd)) {
+                Some((linkname, cmd)) => {
+                    // suggest similar commands on WSL PATH
+                    let similar = {
+                        wsl_path_executables(user.as_deref(), dist.as_deref(), &[] as &[&str])
+                            .map(|names| similar_cmds(cmd, &names))
+                            .unwrap_or_default()
+                    };
+                    let msg = match similar.is_empty() {
+                        true => format!("Command '{}' is not found in WSL", cmd),
+                        false => format!(
+                            "Command '{}' is not found in WSL (Did you mean: {}?)",
+                            cmd,
+                            similar.join(", ")
+                        ),
+                    };
+
+                    Err((linkname.clone(), Error::new(ErrorKind::NotFound, msg)))
+                }
+                None => Ok(()),
+            }
+        })
+    }
+
+    // check if a new link with the options can be created with the link name
+    // returns Err if invalid link name, or any file of the link already exists (unless forced)
+    fn check_linkable(&self, linkname: &str, opts: &LinkOptions) -> io::Result<PathBuf> {
+        self.cmd_pathbuf(&linkname).and_then(|pb_cmd| {
+            let pb_files = link_file_paths(&pb_cmd, opts.kind, opts.detached);
+
+            match (
+                self.is_wslcmd_file(&pb_cmd),
+                !opts.force && pb_files.iter().any(|pb| self.fs.entry_exists(pb)),
+            ) {
+                (true, _) => Err(Error::new(
+                    ErrorKind::AlreadyExists,
+                    "WslCmd already exists for given cmdname",
+                )),
+                (false, true) => Err(Error::new(
+                    ErrorKind::AlreadyExists,
+                    "File already exists for given cmdname",
+                )),
+                (false, false) => Ok(pb_cmd),
+            }
+        })
+    }
+
+    // re-create removed link with its metadata, moving restored backups aside again
+    fn restore_wslcmd(&mut self, linkname: &str, meta: LinkMeta) -> io::Result<()> {
+        self.cmd_pathbuf(&linkname)
+            .and_then(|pb_cmd| {
+                self.backup_files(
+                    &meta
+                        .get_backups()
+                        .iter()
+                        .map(|s| self.linkdir.join(s))
+                        .collect::<Vec<PathBuf>>(),
+                )
+                .map(|_| pb_cmd)
+            })
+            // link files of missing link are not re-created
+            .and_then(|pb_cmd| match meta.get_missing() {
+                true => Ok(()),
+                false => self.create_link_chain(&pb_cmd, *meta.get_kind(), *meta.get_detached()),
+            })
+            .and_then(|_| {
+                self.manifest.insert(linkname, meta);
+                self.manifest.save()
+            })
+            .map(|_| {
+                self.refresh_wslcmd_list(true);
+            })
+    }
+
+    // get link name of the link recorded in manifest, if all of its link files are missing
+    fn missing_linkname(&self, pb_cmd: &Path) -> Option<String> {
+        let linkname = pb_cmd.wcpath_basename()?;
+
+        self.manifest
+            .get(linkname)
+            .filter(|meta| {
+                *meta.get_missing()
+                    && !link_file_paths(pb_cmd, *meta.get_kind(), *meta.get_detached())
+                        .iter()
+                        .any(|pb| self.fs.entry_exists(pb))
+            })
+            .map(|_| linkname.to_string())
+    }
+
+    // remove missing link from manifest, then restore files backed up on linking
+    fn forget_missing_link(&mut self, linkname: &str) -> io::Result<()> {
+        let meta = {
+            self.manifest
+                .remove(linkname)
+                .ok_or(Error::new(ErrorKind::NotFound, "WslCmd does not exist"))?
+        };
+
+        self.manifest
+            .save()
+            .and_then(|_| self.restore_backups(meta.get_backups()))
+            .inspect_err(|_| {
+                // if failed, restore manifest
+                self.manifest.insert(linkname, meta.clone());
+                self.manifest.save().ok();
+            })
+            .map(|_| {
+                self.refresh_wslcmd_list(true);
+            })
+    }
+
+    // create PathBuf of cmd from cmdname: join filename of cmdname to linkdir
+    // returns Err if invalid cmdname
+    fn cmd_pathbuf<T: WCPath>(&self, cmdname: &T) -> io::Result<PathBuf> {
+        {
+            cmdname
+                .wcpath_filename() // get filename only, discarding possible parent dir name
+                .map(|s_cmd| wslcmd_with_ext!(s_cmd)) // append extension to cmdname
+                .map(|s_file| self.linkdir.join(s_file)) // to path inside linkdir
+                .ok_or(Error::new(ErrorKind::InvalidInput, "Invalid cmdname"))
+        }
+        // Ok if valid cmdname
+        .and_then(|pb_cmd| {
+            self.orig_binpath
+                .wcpath_basename()
+                .and_then(|s_orig| pb_cmd.wcpath_filename().map(|s_cmd| (s_orig, s_cmd)))
+                // bool expression
+                .is_some_and(|(s_orig, s_cmd)| {
+                    {
+                        // cmdname is not the same with orig binname
+                        s_orig != s_cmd
+                    }
+                    .bitand({
+                        // cmdname is not detached cmd name pattern (starts with cmdname_delim
+                        !s_cmd.starts_with(DETACHED_PROC_PREFIX)
+                    })
+                })
+                // bool -> Result
+                .then_some(pb_cmd)
+                .ok_or(Error::new(ErrorKind::InvalidInput, "Invalid cmdname"))
+        })
+    }
+
+    // move existing files of given paths aside: (file) -> (file).wslcmd-backup
+    // returns filenames of moved files. if failed, moved files are moved back
+    fn backup_files(&self, pb_files: &[PathBuf]) -> io::Result<Vec<String>> {
+        pb_files
+            .iter()
+            .filter(|pb| self.fs.entry_exists(pb))
+            .try_fold(vec![], |mut backups, pb| {
+                let s_file = {
+                    pb.wcpath_filename()
+                        .map(String::from)
+                        .ok_or(Error::new(ErrorKind::InvalidInput, "Invalid filename"))
+                };
+                match s_file.and_then(|s_file| {
+                    let pb_backup = backup_path(&self.linkdir, &s_file);
+                    match self.fs.entry_exists(&pb_backup) {
+                        true => Err(Error::new(
+                            ErrorKind::AlreadyExists,
+                            format!("Backup file already exists: {}", pb_backup.display()),
+                        )),
+                        false => self.fs.rename(pb, &pb_backup).map(|_| s_file),
+                    }
+                }) {
+                    Ok(s_file) => {
+                        backups.push(s_file);
+                        Ok(backups)
+                    }
+                    // if failed, restore progress (move backups back)
+                    Err(e) => {
+                        self.restore_backups(&backups).ok();
+                        Err(e) // bypass err
+                    }
+                }
+            })
+    }
+
+    // move backups of given filenames back: (file).wslcmd-backup -> (file)
+    // missing backups are skipped. if failed, restored files are moved to backups again
+    fn restore_backups(&self, filenames: &[String]) -> io::Result<()> {
+        filenames
+            .iter()
+            .filter(|s| self.fs.entry_exists(&backup_path(&self.linkdir, s)))
+            .try_fold(vec![], |mut restored, s_file| {
+                let pb_file = self.linkdir.join(s_file);
+                match self
+                    .fs
+                    .rename(&backup_path(&self.linkdir, s_file), &pb_file)
+                {
+                    Ok(_) => {
+                        restored.push(s_file);
+                        Ok(restored)
+                    }
+                    // if failed, restore progress (move restored files to backups again)
+                    Err(e) => {
+                        restored.iter().for_each(|s| {
+                            self.fs
+                                .rename(&self.linkdir.join(s), &backup_path(&self.linkdir, s))
+                                .ok();
+                        });
+                        Err(e) // bypass err
+                    }
+                }
+            })
+            .map(|_| ())
+    }
+
+    // create new link chain (wslcmd -> wslcmd_detached -> origbin)
+    // for hardlink and copy, both wslcmd and wslcmd_detached are made from origbin directly
+    // without companion, only wslcmd or wslcmd_detached is made to origbin directly
+    // if failed, created links are removed
+    fn create_link_chain(
+        &self,
+        pb_cmd: &Path,
+        kind: LinkKind,
+        detached: DetachedMode,
+    ) -> io::Result<()> {
+        // shim scripts instead of link chain, which have no detached link
+        if !kind.shim_extensions().is_empty() {
+            return match detached.is_both() {
+                true => self.create_shims(pb_cmd, kind.shim_extensions()),
+                false => Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "Shim script cannot be linked without detached link",
+                )),
+            };
+        }
+
+        // single link file (wslcmd -> origbin, or wslcmd_detached -> origbin)
+        if !detached.is_both() {
+            let pb_target = self.bin_link_target()?;
+            return link_file_paths(pb_cmd, kind, detached)
+                .iter()
+                .try_for_each(|pb| self.create_link_file(&pb_target, pb, kind));
+        }
+
+        let wslcmd_detached_filename = wslcmd_detached_bin!(
+            // wslcmd filename
+            pb_cmd
+                .wcpath_filename()
+                .ok_or(Error::other("Invalid cmdname"))?
+        );
+
+        // first create link (wslcmd_detached -> origbin)
+        self.create_link_file(
+            // target: origbin (relative if possible)
+            &self.bin_link_target()?,
+            // link file: wslcmd_detached (absolute)
+            &pb_cmd.with_file_name(&wslcmd_detached_filename),
+            kind,
+        )
+        // if succeeded, create another link (wslcmd -> wslcmd_detached)
+        .and_then(|()| {
+            self.create_link_file(
+                // target: wslcmd_detached (relative)
+                &wslcmd_detached_filename,
+                // link file: wslcmd (absolute)
+                pb_cmd,
+                kind,
+            )
+            // if second failed, clean progress (remove first created link)
+            .inspect_err(|_| {
+                self.fs
+                    .remove_file(
+                        // remove wslcmd_detached
+                        &pb_cmd.with_file_name(&wslcmd_detached_filename),
+                    )
+                    .ok();
+            })
+        })
+    }
+
+    // remove link chain (wslcmd -> wslcmd_detached -> origbin)
+    // if failed, removed links are restored
+    fn remove_link_chain(
+        &self,
+        pb_cmd: &Path,
+        kind: LinkKind,
+        detached: DetachedMode,
+    ) -> io::Result<()> {
+        // shim scripts instead of link chain
+        if !kind.shim_extensions().is_empty() {
+            return self.remove_shims(pb_cmd, kind.shim_extensions());
+        }
+
+        // single link file
+        if !detached.is_both() {
+            return link_file_paths(pb_cmd, kind, detached)
+                .iter()
+                .try_for_each(|pb| self.fs.remove_file(pb));
+        }
+
+        let wslcmd_detached_filename = wslcmd_detached_bin!(
+            // wslcmd filename
+            pb_cmd
+                .wcpath_filename()
+                .ok_or(Error::other("Invalid cmdname"))?
+        );
+
+        // remove pb_cmd and pb_cmd_detached
+        {
+            // remove wslcmd (wslcmd -> wslcmd_detached)
+            self.fs.remove_file(pb_cmd)
+        }
+        .and_then(|_| {
+            // remove wslcmd_detached (wslcmd_detached -> orig) if succeeded before
+            self.fs
+                .remove_file(&pb_cmd.with_file_name(&wslcmd_detached_filename))
+                .inspect_err(|_| {
+                    // if second failed, do restore progress (re-link first removed link)
+                    self.create_link_file(
+                        // target: wslcmd_detached (relative)
+                        &wslcmd_detached_filename,
+                        // link file: wslcmd (absolute)
+                        pb_cmd,
+                        kind,
+                    )
+                    .ok();
+                })
+        })
+    }
+
+    // create a link file of given kind
+    // symlink points to the relative target, while hardlink and copy are made from origbin
+    fn create_link_file<T: AsRef<Path>>(
+        &self,
+        target: T,
+        pb_link: &Path,
+        kind: LinkKind,
+    ) -> io::Result<()> {
+        match kind {
+            LinkKind::Symlink => self.fs.symlink_file(target.as_ref(), pb_link),
+            LinkKind::Hardlink => self.fs.hard_link(&self.orig_binpath, pb_link),
+            LinkKind::Copy => self.fs.copy_new(&self.orig_binpath, pb_link),
+            // shim scripts are created by create_shims
+            LinkKind::ShimCmd | LinkKind::ShimPs1 | LinkKind::ShimBoth => Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Shim script is not a link file",
+            )),
+        }
+    }
+
+    // create shim scripts of given extensions, next to pb_cmd
+    // if failed, created scripts are removed
+    fn create_shims(&self, pb_cmd: &Path, exts: &[&str]) -> io::Result<()> {
+        exts.iter()
+            .try_fold(vec![], |mut pb_created, ext| {
+                let pb_shim = pb_cmd.with_extension(ext);
+                match self.write_shim(&pb_shim) {
+                    Ok(_) => {
+                        pb_created.push(pb_shim);
+                        Ok(pb_created)
+                    }
+                    // if failed, clean progress (remove created scripts)
+                    Err(e) => {
+                        pb_created.iter().for_each(|pb| {
+                            self.fs.remove_file(pb).ok();
+                        });
+                        Err(e) // bypass err
+                    }
+                }
+            })
+            .map(|_| ())
+    }
+
+    // remove shim scripts of given extensions, next to pb_cmd
+    // if failed, removed scripts are restored
+    fn remove_shims(&self, pb_cmd: &Path, exts: &[&str]) -> io::Result<()> {
+        exts.iter()
+            .try_fold(vec![], |mut pb_removed, ext| {
+                let pb_shim = pb_cmd.with_extension(ext);
+                match self.fs.remove_file(&pb_shim) {
+                    Ok(_) => {
+                        pb_removed.push(pb_shim);
+                        Ok(pb_removed)
+                    }
+                    // if failed, restore progress (re-write removed scripts)
+                    Err(e) => {
+                        pb_removed.iter().for_each(|pb| {
+                            self.write_shim(pb).ok();
+                        });
+                        Err(e) // bypass err
+                    }
+                }
+            })
+            .map(|_| ())
+    }
+
+    // write a new shim script, which runs origbin with the command name of the script
+    //   Ex) 'git.cmd' -> "%~dp0wslcmd.exe" run "git" %*
+    // if linkdir is not the bin dir, the script passes its dir to find namespace prefixes
+    //   Ex) 'git.cmd' -> "%~dp0..\bin\wslcmd.exe" run --dir "%~dp0." "git" %*
+    fn write_shim(&self, pb_shim: &Path) -> io::Result<()> {
+        let pb_target = self.bin_link_target()?;
+        let (s_bin, s_cmd) = {
+            pb_target
+                .to_str()
+                .zip(pb_shim.file_stem().and_then(OsStr::to_str))
+                .ok_or(Error::new(ErrorKind::InvalidInput, "Invalid cmdname"))?
+        };
+        let is_bindir = pb_target.parent() == Some(Path::new(""));
+
+        let script = match pb_shim.extension().and_then(OsStr::to_str) {
+            // batch: escape '%'
+            Some("cmd") => format!(
+                concat!(
+                    "@echo off\r\n",
+                    "rem WslCmd shim of '{2}'\r\n",
+                    "\"{0}\" run {1}\"{2}\" %*\r\n",
+                    "exit /b %ERRORLEVEL%\r\n",
+                ),
+                match pb_target.is_absolute() {
+                    true => s_bin.replace("%", "%%"),
+                    false => format!("%~dp0{}", s_bin.replace("%", "%%")),
+                },
+                match is_bindir {
+                    true => "",
+                    false => "--dir \"%~dp0.\" ",
+                },
+                s_cmd.replace("%", "%%"),
+            ),
+            // powershell: escape single quote
+            Some("ps1") => format!(
+                concat!(
+                    "# WslCmd shim of '{2}'\r\n",
+                    "& {0} run {1}'{2}' @args\r\n",
+                    "exit $LASTEXITCODE\r\n",
+                ),
+                match pb_target.is_absolute() {
+                    true => format!("'{}'", s_bin.replace("'", "''")),
+                    false => format!("(Join-Path $PSScriptRoot '{}')", s_bin.replace("'", "''")),
+                },
+                match is_bindir {
+                    true => "",
+                    false => "--dir $PSScriptRoot ",
+                },
+                s_cmd.replace("'", "''"),
+            ),
+            _ => return Err(Error::new(ErrorKind::InvalidInput, "Invalid shim type")),
+        };
+
+        // create new file only if not exists
+        self.fs.write_new(pb_shim, script.as_bytes())
+    }
+
+    // get target path of links to origbin, from linkdir
+    //   - bin filename if linkdir is the bin dir
+    //   - relative path if both are on the same root (Ex: '..\bin\wslcmd.exe')
+    //   - absolute path if not
+    fn bin_link_target(&self) -> io::Result<PathBuf> {
+        let s_bin = {
+            self.binpath
+                .wcpath_filename()
+                .ok_or(Error::other("Invalid exe name"))?
+        };
+
+        match (
+            self.fs.canonicalize(&self.linkdir).ok(),
+            self.binpath
+                .wcpath_parent()
+                .and_then(|p| self.fs.canonicalize(p).ok()),
+        ) {
+            (Some(linkdir), Some(bindir)) if linkdir == bindir => Ok(PathBuf::from(s_bin)),
+            (Some(linkdir), Some(bindir)) => Ok(relative_path(&linkdir, &bindir).join(s_bin)),
+            _ => Err(Error::new(ErrorKind::NotFound, "Invalid link dir")),
+        }
+    }
+
+    // get kind of link files and detached mode of linked cmd, recorded in the manifest
+    fn link_layout(&self, pb_cmd: &Path) -> (LinkKind, DetachedMode) {
+        pb_cmd
+            .wcpath_basename()
+            .and_then(|s| self.manifest.get(s))
+            .map_or(Default::default(), |meta| {
+                (*meta.get_kind(), *meta.get_detached())
+            })
+    }
+
+    // refresh wslcmd list to latest. returns ref of mut self for chaining.
+    fn refresh_wslcmd_list(&mut self, force_refresh: bool) -> &mut Self {
+        match force_refresh {
+            true => self.wslcmd_list(),
+            false => self.get_wslcmd_list_if_changed(),
+        }
+        .map(|(cmdlist, cmdlist_time)| {
+            self.cmdlist_cached = cmdlist;
+            self.cmdlist_cached_time = cmdlist_time;
+        })
+        // reconcile manifest with refreshed list, if refreshed
+        // (not saved here, as reading links should not rewrite config: saved on the next change)
+        .map(|_| self.manifest.reconcile(&self.cmdlist_cached));
+
+        self
+    }
+
+    // get list of wslcmd only if linkdir is changed
+    fn get_wslcmd_list_if_changed(&self) -> Option<(HashSet<String>, Option<SystemTime>)> {
+        self.fs
+            // get last modified time
+            .modified(&self.linkdir)
+            .ok()
+            // Some(t) if to be refreshed
+            .filter(|t_dir| {
+                // check if dir mtime is later than the time of cmdlist
+                self.cmdlist_cached_time
+                    .is_none_or(|t_list| t_dir.gt(&t_list))
+            })
+            // return tuple (cmdlist, dir_mtime) if to be refreshed
+            .and_then(|_| self.wslcmd_list())
+    }
+
+    // get list of wslcmd from the on-disk index, or from the fs directly if outdated
+    fn wslcmd_list(&self) -> Option<(HashSet<String>, Option<SystemTime>)> {
+        self.indexed_wslcmd_list()
+    }
+
+    // check if given path is wslcmd link
+    fn is_wslcmd_file<T: WCPath>(&self, binpath: &T) -> bool {
+        // binpath -> (pathbuf_target, pathbuf_followed)
+        {
+            binpath.wcpath_as_path().and_then(|p| {
+                Some((
+                    // pb_symlink
+                    p.to_path_buf(),
+                    // pb_target
+                    self.fs
+                        .read_link(p)
+                        .map_or(None, |pb| Some(p.with_file_name(pb.wcpath_as_ref()?)))?,
+                ))
+            })
+        }
+        // check if wslcmd file using two pathbufs
+        .and_then(
+            // check if pb_symlink is wslcmd
+            |(pb_symlink, pb_target)| {
+                // check if both symlink/target pb is symlink to the self bin
+                {
+                    [&pb_symlink, &pb_target]
+                        .iter()
+                        .map(|pb| {
+                            // bool expression
+                            {
+                                // OsStr extension == expected bin extension
+                                pb.extension().and_then(OsStr::to_str)? == BINARY_EXTENSION
+                            }
+                            .bitand({
+                                // ... and pointing to same bin
+                                self.fs.canonicalize(pb).ok()? == self.orig_binpath
+                            })
+                            .bitand(
+                                // ... and not the original bin itself
+                                pb.wcpath_filename()? != self.binpath.wcpath_filename()?,
+                            )
+                            .then_some(()) // bool to Option
+                        })
+                        .all(|pred| pred.is_some())
+                }
+                // check if target is non-detached WslCmd
+                .bitand({
+                    // bool expression
+                    {
+                        // not starting with DETACHED_PROC_PREFIX
+                        !pb_symlink
+                            .wcpath_filename()?
+                            .starts_with(DETACHED_PROC_PREFIX)
+                    }
+                    .bitand(
+                        // link behind the current file is detached bin symlink
+                        pb_target
+                            == pb_target.with_file_name(wslcmd_detached_bin!(
+                                pb_symlink.wcpath_filename()?
+                            )),
+                    )
+                })
+                .then_some(()) // final bool to Option
+            },
+        )
+        .is_some()
+            || self.is_copied_wslcmd_file(binpath)
+            || self.is_single_wslcmd_file(binpath)
+            || self.is_shim_wslcmd_file(binpath)
+    }
+
+    // check if given path is wslcmd shim script (or the bin path of it),
+    // which is recorded in the manifest
+    fn is_shim_wslcmd_file<T: WCPath>(&self, binpath: &T) -> bool {
+        binpath
+            .wcpath_as_path()
+            .and_then(|p| {
+                Some((
+                    p,
+                    p.extension().and_then(OsStr::to_str)?,
+                    p.file_stem().and_then(OsStr::to_str)?,
+                ))
+            })
+            .and_then(|(p, s_ext, s_base)| {
+                let exts = self.manifest.get(s_base)?.get_kind().shim_extensions();
+
+                // bool expression
+                {
+                    // recorded as shim
+                    !exts.is_empty()
+                }
+                .bitand({
+                    // ... and extension is bin extension or one of shim extensions
+                    s_ext == BINARY_EXTENSION || exts.contains(&s_ext)
+                })
+                .bitand({
+                    // ... and not detached one
+                    !s_base.starts_with(DETACHED_PROC_PREFIX)
+                })
+                .bitand({
+                    // ... and all shim scripts are regular files
+                    exts.iter()
+                        .all(|ext| self.fs.is_file(&p.with_extension(ext)))
+                })
+                .then_some(())
+            })
+            .is_some()
+    }
+
+    // check if given path is wslcmd hardlink or copy, which is recorded in the manifest
+    fn is_copied_wslcmd_file<T: WCPath>(&self, binpath: &T) -> bool {
+        binpath
+            .wcpath_as_path()
+            .and_then(|p| {
+                Some((
+                    p,
+                    p.file_name().and_then(OsStr::to_str)?,
+                    p.file_stem().and_then(OsStr::to_str)?,
+                ))
+            })
+            .is_some_and(|(p, s_file, s_base)| {
+                // bool expression
+                {
+                    // extension == expected bin extension
+                    p.extension().and_then(OsStr::to_str) == Some(BINARY_EXTENSION)
+                }
+                .bitand({
+                    // ... and not detached one, nor the original bin itself
+                    !s_file.starts_with(DETACHED_PROC_PREFIX)
+                        && Some(s_file) != self.binpath.wcpath_filename()
+                })
+                .bitand({
+                    // ... and recorded as hardlink or copy, with detached link
+                    self.manifest.get(s_base).is_some_and(|meta| {
+                        meta.get_kind().is_binary_copy() && meta.get_detached().is_both()
+                    })
+                })
+                .bitand({
+                    // ... and both wslcmd and wslcmd_detached are regular files
+                    [
+                        p.to_path_buf(),
+                        p.with_file_name(wslcmd_detached_bin!(s_file)),
+                    ]
+                    .iter()
+                    .all(|pb| self.fs.is_file(pb))
+                })
+            })
+    }
+
+    // check if given path is wslcmd link without its companion (no-detached or detached-only),
+    // which is recorded in the manifest
+    fn is_single_wslcmd_file<T: WCPath>(&self, binpath: &T) -> bool {
+        binpath
+            .wcpath_as_path()
+            .and_then(|p| {
+                Some((
+                    p,
+                    p.file_name().and_then(OsStr::to_str)?,
+                    self.manifest.get(p.file_stem().and_then(OsStr::to_str)?)?,
+                ))
+            })
+            .is_some_and(|(p, s_file, meta)| {
+                let (kind, detached) = (*meta.get_kind(), *meta.get_detached());
+
+                // bool expression
+                {
+                    // extension == expected bin extension
+                    p.extension().and_then(OsStr::to_str) == Some(BINARY_EXTENSION)
+                }
+                .bitand({
+                    // ... and not detached one, nor the original bin itself
+                    !s_file.starts_with(DETACHED_PROC_PREFIX)
+                        && Some(s_file) != self.binpath.wcpath_filename()
+                })
+                .bitand({
+                    // ... and recorded as a link file without companion
+                    kind.shim_extensions().is_empty() && !detached.is_both()
+                })
+                .bitand({
+                    // ... and the link file is a symlink to the bin, or a regular file
+                    link_file_paths(p, kind, detached)
+                        .iter()
+                        .all(|pb| match kind.is_symlink() {
+                            true => {
+                                self.fs.is_symlink(pb)
+                                    && self.fs.canonicalize(pb).ok().as_ref()
+                                        == Some(&self.orig_binpath)
+                            }
+                            false => self.fs.is_file(pb),
+                        })
+                })
+            })
+    }
+}
+
+// add the failed command name to the error of batch operation
+fn batch_error(linkname: &str, e: Error) -> Error {
+    Error::new(e.kind(), format!("'{}': {}", linkname, e))
+}
+
+// check if all tags are valid
+// returns Err(InvalidInput) with the first invalid tag
+fn check_tags<T: AsRef<str>>(tags: &[T]) -> io::Result<()> {
+    match tags.iter().find(|s| !LinkMeta::is_valid_tag(s.as_ref())) {
+        Some(s) => Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Invalid tag '{}'", s.as_ref()),
+        )),
+        None => Ok(()),
+    }
+}
+
+// get path of backup of a file in the dir: (dir)/(file).wslcmd-backup
+fn backup_path(dir: &Path, filename: &str) -> PathBuf {
+    dir.join(format!("{}.{}", filename, BACKUP_EXTENSION))
+}
+
+// get paths of all files of the link chain of given kind and detached mode
+//   Ex) symlink: [cmd.exe, .cmd.exe], no-detached: [cmd.exe], shim: [cmd.cmd, cmd.ps1]
+fn link_file_paths(pb_cmd: &Path, kind: LinkKind, detached: DetachedMode) -> Vec<PathBuf> {
+    match kind.shim_extensions() {
+        [] => vec![
+            (detached.has_link(), pb_cmd.to_path_buf()),
+            (
+                detached.has_detached(),
+                pb_cmd.with_file_name(wslcmd_detached_bin!(pb_cmd
+                    .file_name()
+                    .and_then(OsStr::to_str)
+                    .unwrap_or_default())),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(is_created, pb)| is_created.then_some(pb))
+        .collect(),
+        exts => exts.iter().map(|ext| pb_cmd.with_extension(ext)).collect(),
+    }
+}
+
+// get relative path from dir to target dir, both of which are canonicalized
+// returns target dir itself if not on the same root (Ex: different drives)
+//   Ex) ('C:\links', 'C:\Program Files\WslCmd') -> '..\Program Files\WslCmd'
+fn relative_path(dir: &Path, target: &Path) -> PathBuf {
+    let mut it_dir = dir.components().peekable();
+    let mut it_target = target.components().peekable();
+
+    // different roots: absolute path without verbatim prefix (Ex: '\\?\C:\' -> 'C:\')
+    if it_dir.peek() != it_target.peek() {
+        return target
+            .to_str()
+            .and_then(|s| s.strip_prefix(r"\\?\"))
+            .filter(|s| !s.starts_with("UNC\\"))
+            .map_or(target.to_path_buf(), PathBuf::from);
+    }
+
+    // skip common components
+    while it_dir.peek().is_some() && it_dir.peek() == it_target.peek() {
+        it_dir.next();
+        it_target.next();
+    }
+
+    // go up for remaining components of dir, then go down to target
+    it_dir
+        .map(|_| std::path::Component::ParentDir)
+        .chain(it_target)
+        .collect()
+}
+
+#[cfg(test)]
+/// For module test
+mod test {
+    use super::super::link_fs::{mem_root, LinkFs, MemFs};
+    use super::super::{DetachedMode, LinkKind, WCPath, WCStr, WslCmdManifest};
+    use super::{CmdSelector, LinkOptions, WslCmdList, BINARY_EXTENSION, DETACHED_PROC_PREFIX};
+    use std::io;
+    use std::io::{Error, ErrorKind};
+    use std::ops::*;
+    use std::path::{Path, PathBuf};
+    use std::rc::Rc;
+    use std::{collections::HashSet, env, fs, iter::FromIterator};
+
+    const TEST_TMP_DIR: &str = "wslcmd_tmpdir_test-wslcmd-list_";
+
+    #[derive(Debug)]
+    enum TestKind {
+        Link,
+        Unlink,
+    }
+
+    #[test]
+    fn test_link() {
+        const TMPDIR_POSTFIX: &str = "wslcmd-link";
+
+        // init tmpdir
+        let tmpdir = init_tmpdir(TMPDIR_POSTFIX).expect("Tmp dir initialize");
+        let (bin1, _) = copy_tmpbin(&tmpdir, None).expect("Bin initialize");
+
+        // test linking only
+        let mut wslcmd_list = WslCmdList::new(&bin1).expect("New WslCmdList");
+        unit_test_mod(&tmpdir, &mut wslcmd_list, "test", false, TestKind::Link)
+            .expect("Test new link: Needs Windows developer mode enabled or admin privilege");
+
+        // clean tmpdir
+        clean_tmpdir(TMPDIR_POSTFIX);
+    }
+
+    #[test]
+    fn test_unlink() {
+        const TMPDIR_POSTFIX: &str = "wslcmd-unlink";
+
+        // init tmpdir
+        let tmpdir = init_tmpdir(TMPDIR_POSTFIX).expect("Tmp dir initialize");
+        let (bin1, _) = copy_tmpbin(&tmpdir, None).expect("Bin initialize");
+
+        // test linking only
+        let mut wslcmd_list = WslCmdList::new(&bin1).expect("New WslCmdList");
+        unit_test_mod(&tmpdir, &mut wslcmd_list, "test", false, TestKind::Link)
+            .expect("Unlink test prepare");
+        unit_test_mod(&tmpdir, &mut wslcmd_list, "test", false, TestKind::Unlink)
+            .expect("Test unlink");
+
+        // clean tmpdir
+        clean_tmpdir(TMPDIR_POSTFIX);
+    }
+
+    #[test]
+    fn test_list() {
+        const TMPDIR_POSTFIX: &str = "wslcmd-list";
+
+        // init tmpdir
+        let tmpdir = init_tmpdir(TMPDIR_POSTFIX).expect("Tmp dir initialize");
+        let (bin1, _) = copy_tmpbin(&tmpdir, None).expect("Bin initialize");
+        let dummyfile = tmpdir.join("dummy");
+
+        // test linking only
+        let mut wslcmd_list = WslCmdList::new(&bin1).expect("New WslCmdList");
+        unit_test_cmdlist(&mut wslcmd_list, &([] as [&str; 0])); // check when no other file
+        unit_test_mod(&tmpdir, &mut wslcmd_list, "testlink", false, TestKind::Link)
+            .expect("List test prepare");
+        unit_test_cmdlist(&mut wslcmd_list, &["testlink"]);
+        new_dummy_file(&dummyfile); // new dummy file
+        unit_test_cmdlist(&mut wslcmd_list, &["testlink"]);
+
+        // clean tmpdir
+        clean_tmpdir(TMPDIR_POSTFIX);
+    }
+
+    #[test]
+    fn test_manifest() {
+        const TMPDIR_POSTFIX: &str = "wslcmd-manifest";
+
+        // init tmpdir
+        let tmpdir = init_tmpdir(TMPDIR_POSTFIX).expect("Tmp dir initialize");
+        let (bin1, _) = copy_tmpbin(&tmpdir, None).expect("Bin initialize");
+
+        // link with description, then check if recorded
+        let mut wslcmd_list = WslCmdList::new(&bin1).expect("New WslCmdList");
+        wslcmd_list
+            .link_wslcmd_with(
+                &"git!!debian",
+                &LinkOptions::new().description("Git of debian"),
+            )
+            .expect("Link with description");
+        wslcmd_list
+            .link_wslcmd_with_prefix("w-", &"find")
+            .expect("Link with prefix");
+        unit_test_meta(
+            &mut wslcmd_list,
+            "git!!debian",
+            "git",
+            Some("Git of debian"),
+        );
+
+        // check if persisted
+        let mut wslcmd_list = WslCmdList::new(&bin1).expect("New WslCmdList");
+        unit_test_meta(
+            &mut wslcmd_list,
+            "git!!debian",
+            "git",
+            Some("Git of debian"),
+        );
+        unit_test_meta(&mut wslcmd_list, "w-find", "find", None);
+
+        // check if links without manifest are reconciled, but not saved until changed
+        fs::remove_file(wslcmd_list.manifest.get_path()).expect("Remove manifest");
+        let mut wslcmd_list = WslCmdList::new(&bin1).expect("New WslCmdList");
+        unit_test_meta(&mut wslcmd_list, "git!!debian", "git", None);
+        assert!(!wslcmd_list.manifest.get_path().exists());
+
+        // check if removed on unlink
+        wslcmd_list.unlink_wslcmd(&"git!!debian").expect("Unlink");
+        assert!(wslcmd_list.get_link_meta("git!!debian").is_none());
+        assert!(wslcmd_list.manifest.get_path().exists());
+
+        // clean tmpdir
+        clean_tmpdir(TMPDIR_POSTFIX);
+    }
+
+    #[test]
+    fn test_rename_copy() {
+        const TMPDIR_POSTFIX: &str = "wslcmd-rename-copy";
+
+        // init tmpdir
+        let tmpdir = init_tmpdir(TMPDIR_POSTFIX).expect("Tmp dir initialize");
+        let (bin1, _) = copy_tmpbin(&tmpdir, None).expect("Bin initialize");
+        let mut wslcmd_list = WslCmdList::new(&bin1).expect("New WslCmdList");
+        wslcmd_list
+            .link_wslcmd_with(&"python", &LinkOptions::new().description("Python"))
+            .expect("Link with description");
+
+        // rename, then check if metadata is kept
+        wslcmd_list
+            .rename_wslcmd(&"python", &"python!!debian")
+            .expect("Rename");
+        assert!(!wslcmd_list.get_cmdlist().contains("python"));
+        assert!(wslcmd_list.get_cmdlist().contains("python!!debian"));
+        unit_test_meta(&mut wslcmd_list, "python!!debian", "python", Some("Python"));
+        assert_eq!(
+            wslcmd_list
+                .get_link_meta("python!!debian")
+                .and_then(|meta| meta.get_dist().clone()),
+            Some("debian".to_string())
+        );
+
+        // copy, then check if both exist
+        wslcmd_list
+            .copy_wslcmd(&"python!!debian", &"py")
+            .expect("Copy");
+        assert!(wslcmd_list.get_cmdlist().contains("python!!debian"));
+        unit_test_meta(&mut wslcmd_list, "py", "py", Some("Python"));
+
+        // existing or missing names should fail without changes
+        let cmdlist_before = wslcmd_list.get_cmdlist().clone();
+        assert!(wslcmd_list.rename_wslcmd(&"py", &"python!!debian").is_err());
+        assert!(wslcmd_list.copy_wslcmd(&"nonexistent", &"other").is_err());
+        assert_eq!(wslcmd_list.get_cmdlist(), &cmdlist_before);
+
+        // clean tmpdir
+        clean_tmpdir(TMPDIR_POSTFIX);
+    }
+
+    #[test]
+    fn test_link_kind() {
+        const TMPDIR_POSTFIX: &str = "wslcmd-link-kind";
+
+        // init tmpdir
+        let tmpdir = init_tmpdir(TMPDIR_POSTFIX).expect("Tmp dir initialize");
+        let (bin1, _) = copy_tmpbin(&tmpdir, None).expect("Bin initialize");
+        let mut wslcmd_list = WslCmdList::new(&bin1).expect("New WslCmdList");
+
+        // link with hardlinks and copies
+        for (cmd, kind) in &[("hl", LinkKind::Hardlink), ("cp", LinkKind::Copy)] {
+            wslcmd_list
+                .link_wslcmd_with(cmd, &LinkOptions::new().kind(*kind))
+                .expect("Link with kind");
+        }
+        wslcmd_list
+            .link_wslcmd_with(&"cp", &LinkOptions::new().kind(LinkKind::Copy))
+            .expect_err("Link existing cmd");
+
+        // check if listed as wslcmd, and detected as link on execution
+        let mut wslcmd_list = WslCmdList::new(&bin1).expect("New WslCmdList");
+        for (cmd, kind) in &[("hl", LinkKind::Hardlink), ("cp", LinkKind::Copy)] {
+            assert!(wslcmd_list.get_cmdlist().contains(*cmd));
+            assert_eq!(
+                wslcmd_list.get_link_meta(cmd).map(|meta| *meta.get_kind()),
+                Some(*kind)
+            );
+            for linkname in &[cmd.to_string(), format!("{}{}", DETACHED_PROC_PREFIX, cmd)] {
+                assert!(WslCmdManifest::find_link_meta(&tmpdir, linkname, None)
+                    .is_some_and(|meta| meta.get_kind().is_binary_copy()));
+            }
+        }
+        let binname = bin1.wcpath_basename().expect("Bin name");
+        assert!(WslCmdManifest::find_link_meta(&tmpdir, binname, None).is_none());
+
+        // rename keeps kind, and unlink removes all files
+        wslcmd_list.rename_wslcmd(&"cp", &"cp2").expect("Rename");
+        assert_eq!(
+            wslcmd_list
+                .get_link_meta("cp2")
+                .map(|meta| *meta.get_kind()),
+            Some(LinkKind::Copy)
+        );
+        for cmd in &["hl", "cp2"] {
+            wslcmd_list.unlink_wslcmd(cmd).expect("Unlink");
+        }
+        assert!(wslcmd_list.get_cmdlist().is_empty());
+        assert_eq!(
+            tmpdir
+                .wcpath_read_dir()
+                .unwrap_or_default()
+                .iter()
+                .filter(|pb| !is_manifest_file(pb))
+                .count(),
+            1 // only bin
+        );
+
+        // clean tmpdir
+        clean_tmpdir(TMPDIR_POSTFIX);
+    }
+
+    #[test]
+    fn test_shim() {
+        const TMPDIR_POSTFIX: &str = "wslcmd-shim";
+
+        // init tmpdir
+        let tmpdir = init_tmpdir(TMPDIR_POSTFIX).expect("Tmp dir initialize");
+        let (bin1, _) = copy_tmpbin(&tmpdir, None).expect("Bin initialize");
+        let mut wslcmd_list = WslCmdList::new(&bin1).expect("New WslCmdList");
+
+        // create shims, then check scripts
+        wslcmd_list
+            .link_wslcmd_with(&"node", &LinkOptions::new().kind(LinkKind::ShimBoth))
+            .expect("Link with shim");
+        let script_cmd = fs::read_to_string(tmpdir.join("node.cmd")).expect("Read cmd shim");
+        let script_ps1 = fs::read_to_string(tmpdir.join("node.ps1")).expect("Read ps1 shim");
+        assert!(script_cmd.contains(r#"run "node" %*"#));
+        assert!(script_ps1.contains("run 'node' @args"));
+        assert!(!tmpdir.join("node.exe").exists());
+
+        // check if listed, and name conflicts with other kinds
+        let mut wslcmd_list = WslCmdList::new(&bin1).expect("New WslCmdList");
+        assert!(wslcmd_list.get_cmdlist().contains("node"));
+        wslcmd_list
+            .link_wslcmd(&"node")
+            .expect_err("Link existing cmd");
+
+        // unlink removes all scripts
+        wslcmd_list.unlink_wslcmd(&"node").expect("Unlink");
+        assert!(wslcmd_list.get_cmdlist().is_empty());
+        assert!(!tmpdir.join("node.cmd").exists());
+        assert!(!tmpdir.join("node.ps1").exists());
+
+        // clean tmpdir
+        clean_tmpdir(TMPDIR_POSTFIX);
+    }
+
+    #[test]
+    fn test_link_dir() {
+        const TMPDIR_POSTFIX: &str = "wslcmd-link-dir";
+
+        // init tmpdir, with separate bin and link dirs
+        let tmpdir = init_tmpdir(TMPDIR_POSTFIX).expect("Tmp dir initialize");
+        let (bindir, linkdir) = (tmpdir.join("bin"), tmpdir.join("links"));
+        fs::create_dir_all(&bindir).expect("Bin dir initialize");
+        let (bin1, _) = copy_tmpbin(&bindir, None).expect("Bin initialize");
+        assert!(WslCmdList::with_dir(&bin1, &linkdir).is_none()); // not exists yet
+        fs::create_dir_all(&linkdir).expect("Link dir initialize");
+
+        // link on link dir
+        let mut wslcmd_list = WslCmdList::with_dir(&bin1, &linkdir).expect("New WslCmdList");
+        wslcmd_list
+            .link_wslcmd_with_prefix("w-", &"git")
+            .expect("Link");
+        wslcmd_list
+            .link_wslcmd_with(&"node", &LinkOptions::new().kind(LinkKind::ShimCmd))
+            .expect("Link with shim");
+
+        // check if links point to the bin with relative path
+        let pb_detached = linkdir.join(wslcmd_detached_bin!(wslcmd_with_ext!("w-git")));
+        assert_eq!(
+            fs::read_link(&pb_detached).expect("Read link"),
+            PathBuf::from("..")
+                .join("bin")
+                .join(bin1.file_name().unwrap())
+        );
+        assert!(fs::read_to_string(linkdir.join("node.cmd"))
+            .expect("Read cmd shim")
+            .contains(r#"run --dir "%~dp0." "node""#));
+        assert!(wslcmd_list.diagnose().is_empty());
+
+        // list and manifest are separated by dir
+        assert!(WslCmdList::new(&bin1)
+            .expect("New WslCmdList")
+            .get_cmdlist()
+            .is_empty());
+        let mut wslcmd_list = WslCmdList::with_dir(&bin1, &linkdir).expect("New WslCmdList");
+        assert_eq!(wslcmd_list.get_cmdlist().len(), 2);
+        assert_eq!(
+            wslcmd_list
+                .get_link_meta("w-git")
+                .and_then(|meta| meta.get_prefix().as_deref()),
+            Some("w-")
+        );
+        assert!(wslcmd_list.manifest.get_path().starts_with(&linkdir));
+
+        // unlink
+        for cmd in &["w-git", "node"] {
+            wslcmd_list.unlink_wslcmd(cmd).expect("Unlink");
+        }
+        assert!(wslcmd_list.get_cmdlist().is_empty());
+
+        // clean tmpdir
+        clean_tmpdir(TMPDIR_POSTFIX);
+    }
+
+    #[test]
+    fn test_batch() {
+        const TMPDIR_POSTFIX: &str = "wslcmd-batch";
+
+        // init tmpdir
+        let tmpdir = init_tmpdir(TMPDIR_POSTFIX).expect("Tmp dir initialize");
+        let (bin1, _) = copy_tmpbin(&tmpdir, None).expect("Bin initialize");
+        let mut wslcmd_list = WslCmdList::new(&bin1).expect("New WslCmdList");
+        new_dummy_file(&tmpdir.join(wslcmd_with_ext!("foreign")));
+
+        // link all, or nothing
+        wslcmd_list
+            .link_wslcmd_batch(&["a", "b"], &LinkOptions::new())
+            .expect("Link batch");
+        for cmdnames in &[
+            &["c", "a"][..],
+            &["c", "c"],
+            &["c", "foreign"],
+            &["c", ".d"],
+        ] {
+            let e = wslcmd_list
+                .link_wslcmd_batch(cmdnames, &LinkOptions::new())
+                .expect_err("Link batch with invalid cmd");
+            dbg!(&e);
+            assert!(!wslcmd_list.get_cmdlist().contains("c"));
+        }
+
+        // unlink all, or nothing
+        for cmdnames in &[&["a", "c"][..], &["a", "a"], &["a", "foreign"]] {
+            wslcmd_list
+                .unlink_wslcmd_batch(cmdnames)
+                .expect_err("Unlink batch with invalid cmd");
+            assert!(wslcmd_list.get_cmdlist().contains("a"));
+        }
+        wslcmd_list
+            .unlink_wslcmd_batch(&["a", "b"])
+            .expect("Unlink batch");
+        assert!(wslcmd_list.get_cmdlist().is_empty());
+
+        // restore removed link with metadata
+        wslcmd_list
+            .link_wslcmd_with(&"e", &LinkOptions::new().prefix("w-").description("E"))
+            .expect("Link");
+        let meta = wslcmd_list.get_link_meta("w-e").cloned().expect("Meta");
+        wslcmd_list.unlink_wslcmd(&"w-e").expect("Unlink");
+        assert!(wslcmd_list.get_link_meta("w-e").is_none());
+        wslcmd_list
+            .restore_wslcmd("w-e", meta.clone())
+            .expect("Restore");
+        assert_eq!(wslcmd_list.get_link_meta("w-e"), Some(&meta));
+
+        // clean tmpdir
+        clean_tmpdir(TMPDIR_POSTFIX);
+    }
+
+    #[test]
+    // test all pub funcs
+    fn test_overall() {
+        const TMPDIR_POSTFIX: &str = "wslcmd-overall";
+
+        // init tmpdir
+        let tmpdir = init_tmpdir(TMPDIR_POSTFIX).expect("Tmp dir initialize");
+        let (bin1, cmd1) = copy_tmpbin(&tmpdir, None).expect("Bin initialize");
+        let (bin2, cmd2) =
+            copy_tmpbin(&tmpdir, Some(&format!("bin2-{}", cmd1))).expect("Bin initialize");
+
+        // test with bin1 -
+        // validate member funcs
+        let mut wslcmd_list = WslCmdList::new(&bin1).expect("New WslCmdList");
+
+        //  - test progress: bin1 -> emacs, t
+        unit_test_link_wslcmd(
+            &tmpdir,
+            &mut wslcmd_list,
+            &[("t", false), (&cmd1, true), ("emacs", false), (&cmd2, true)],
+        );
+        unit_test_cmdlist(&mut wslcmd_list, &["emacs", "t"]);
+
+        //  - test progress: bin1 -> t
+        unit_test_unlink_wslcmd(
+            &tmpdir,
+            &mut wslcmd_list,
+            &[("a", true), ("emacs", false), (&cmd1, true), (&cmd2, true)],
+        );
+        unit_test_cmdlist(&mut wslcmd_list, &["t"]);
+
+        //  - test progress: bin1 -> (empty)
+        unit_test_unlink_wslcmd(&tmpdir, &mut wslcmd_list, &[("t", false), ("t", true)]);
+        unit_test_cmdlist(&mut wslcmd_list, &([] as [&str; 0]));
+
+        // test with new bin2 -
+        // check if bin1 and bin2 in the same dir are working separated on wslcmd_list
+        let mut wslcmd2_list = WslCmdList::new(&bin2).expect("New WslCmdList");
+
+        //  - test progress: bin1 -> t2, bin2 -> (empty)
+        unit_test_link_wslcmd(&tmpdir, &mut wslcmd_list, &[("t2", false)]);
+        unit_test_cmdlist(&mut wslcmd_list, &["t2"]); // only list for wslcmd_list
+        unit_test_cmdlist(&mut wslcmd2_list, &([] as [&str; 0])); // only list for wslcmd2_list
+
+        //  - test progress: bin1 -> t2, bin2 -> t
+        unit_test_link_wslcmd(
+            &tmpdir,
+            &mut wslcmd2_list,
+            &[("t", false), ("t", true), ("t", true)],
+        );
+
+        unit_test_cmdlist(&mut wslcmd_list, &["t2"]); // only list for wslcmd_list
+        unit_test_cmdlist(&mut wslcmd2_list, &["t"]); // only list for wslcmd2_list
+
+        // clean tmpdir
+        clean_tmpdir(TMPDIR_POSTFIX);
+    }
+
+    // clean and then create tmpdir
+    #[test]
+    fn test_mem_fs_link_unlink() {
+        let (fs, binpath, linkdir) = init_mem_fs();
+        let mut wslcmd_list =
+            WslCmdList::with_fs(&binpath, &linkdir, fs.clone()).expect("New WslCmdList");
+
+        // symlink chain: git.exe -> .git.exe -> ../bin/wslcmd.exe
+        wslcmd_list.link_wslcmd(&"git").expect("Link symlink");
+        assert_eq!(
+            fs.read_link(&linkdir.join("git.exe")).expect("Read link"),
+            PathBuf::from(".git.exe")
+        );
+        assert_eq!(
+            fs.read_link(&linkdir.join(".git.exe")).expect("Read link"),
+            PathBuf::from("..").join("bin").join("wslcmd.exe")
+        );
+
+        // copy and shim script
+        wslcmd_list
+            .link_wslcmd_with(&"vim", &LinkOptions::new().kind(LinkKind::Copy))
+            .expect("Link copy");
+        wslcmd_list
+            .link_wslcmd_with(&"node", &LinkOptions::new().kind(LinkKind::ShimCmd))
+            .expect("Link shim");
+        assert!(fs.is_file(&linkdir.join("vim.exe")) && fs.is_file(&linkdir.join(".vim.exe")));
+        assert!(fs
+            .read_to_string(&linkdir.join("node.cmd"))
+            .expect("Read shim")
+            .contains("wslcmd.exe"));
+        unit_test_cmdlist(&mut wslcmd_list, &["git", "vim", "node"]);
+
+        // unlink all, removing all files of links
+        ["git", "vim", "node"].iter().for_each(|s| {
+            wslcmd_list.unlink_wslcmd(s).expect("Unlink");
+        });
+        unit_test_cmdlist(&mut wslcmd_list, &([] as [&str; 0]));
+        assert!(["git.exe", ".git.exe", "vim.exe", ".vim.exe", "node.cmd"]
+            .iter()
+            .all(|s| !fs.entry_exists(&linkdir.join(s))));
+        assert!(wslcmd_list.unlink_wslcmd(&"git").is_err());
+    }
+
+    #[test]
+    fn test_mem_fs_validation() {
+        let (fs, binpath, linkdir) = init_mem_fs();
+        let mut wslcmd_list =
+            WslCmdList::with_fs(&binpath, &linkdir, fs.clone()).expect("New WslCmdList");
+
+        // link dir must exist
+        assert!(WslCmdList::with_fs(&binpath, &linkdir.join("none"), fs.clone()).is_none());
+
+        // invalid names, existing link, and existing non-link file
+        fs.write_new(&linkdir.join("make.exe"), b"make")
+            .expect("Dummy file");
+        wslcmd_list.link_wslcmd(&"git").expect("Link");
+        [".git", "", "git", "make"].iter().for_each(|s| {
+            assert!(wslcmd_list.link_wslcmd(s).is_err(), "Link '{}'", s);
+        });
+        assert_eq!(
+            fs.read_to_string(&linkdir.join("make.exe")).expect("Read"),
+            "make"
+        );
+
+        // non-link file cannot be unlinked
+        assert!(wslcmd_list.unlink_wslcmd(&"make").is_err());
+        assert!(fs.entry_exists(&linkdir.join("make.exe")));
+        unit_test_cmdlist(&mut wslcmd_list, &["git"]);
+    }
+
+    #[test]
+    fn test_mem_fs_rollback() {
+        let (fs, binpath, linkdir) = init_mem_fs();
+        let mut wslcmd_list =
+            WslCmdList::with_fs(&binpath, &linkdir, fs.clone()).expect("New WslCmdList");
+
+        // second link of the chain fails: first one is removed
+        fs.deny(&linkdir.join("git.exe"));
+        let e = wslcmd_list.link_wslcmd(&"git").expect_err("Link denied");
+        assert_eq!(e.kind(), ErrorKind::PermissionDenied);
+        assert!(!fs.entry_exists(&linkdir.join(".git.exe")));
+        fs.allow(&linkdir.join("git.exe"));
+
+        // manifest cannot be saved: links are removed
+        let pb_manifest = wslcmd_list.manifest.get_path().clone();
+        fs.deny(&pb_manifest);
+        assert!(wslcmd_list
+            .link_wslcmd_with(&"vim", &LinkOptions::new().description("Vim"))
+            .is_err());
+        assert!(!fs.entry_exists(&linkdir.join("vim.exe")));
+        fs.allow(&pb_manifest);
+
+        // batch: nothing is linked if any of them fails
+        fs.deny(&linkdir.join(".b.exe"));
+        assert!(wslcmd_list
+            .link_wslcmd_batch(&["a", "b", "c"], &LinkOptions::new())
+            .is_err());
+        fs.allow(&linkdir.join(".b.exe"));
+        unit_test_cmdlist(&mut wslcmd_list, &([] as [&str; 0]));
+
+        // second removal of the chain fails: first one is restored
+        wslcmd_list.link_wslcmd(&"git").expect("Link");
+        fs.deny(&linkdir.join(".git.exe"));
+        assert!(wslcmd_list.unlink_wslcmd(&"git").is_err());
+        fs.allow(&linkdir.join(".git.exe"));
+        unit_test_cmdlist(&mut wslcmd_list, &["git"]);
+        assert!(wslcmd_list.diagnose().is_empty());
+    }
+
+    #[test]
+    fn test_mem_fs_list() {
+        let (fs, binpath, linkdir) = init_mem_fs();
+        let mut wslcmd_list =
+            WslCmdList::with_fs(&binpath, &linkdir, fs.clone()).expect("New WslCmdList");
+        wslcmd_list.link_wslcmd(&"git").expect("Link");
+        wslcmd_list
+            .link_wslcmd_with(&"vim", &LinkOptions::new().kind(LinkKind::Hardlink))
+            .expect("Link");
+
+        // non-link files, and a link chain of another binary
+        let bin_other = binpath.with_file_name("other.exe");
+        fs.write_new(&bin_other, b"other").expect("Other bin");
+        fs.write_new(&linkdir.join("dummy.exe"), b"dummy")
+            .expect("Dummy file");
+        fs.symlink_file(&bin_other, &linkdir.join(".foreign.exe"))
+            .expect("Foreign link");
+        fs.symlink_file(&PathBuf::from(".foreign.exe"), &linkdir.join("foreign.exe"))
+            .expect("Foreign link");
+        unit_test_cmdlist(&mut wslcmd_list, &["git", "vim"]);
+
+        // new instance gets the same list, from the index
+        let mut wslcmd_list =
+            WslCmdList::with_fs(&binpath, &linkdir, fs.clone()).expect("New WslCmdList");
+        assert!(fs.exists(&linkdir.join("wslcmd.index")));
+        unit_test_cmdlist(&mut wslcmd_list, &["git", "vim"]);
+
+        // list is refreshed after changed by others
+        fs.remove_file(&linkdir.join("git.exe"))
+            .expect("Remove link");
+        unit_test_cmdlist(&mut wslcmd_list, &["vim"]);
+    }
+
+    #[test]
+    fn test_mem_fs_detached_mode() {
+        let (fs, binpath, linkdir) = init_mem_fs();
+        let mut wslcmd_list =
+            WslCmdList::with_fs(&binpath, &linkdir, fs.clone()).expect("New WslCmdList");
+        let bin_target = PathBuf::from("..").join("bin").join("wslcmd.exe");
+        let exists = |s: &str| fs.entry_exists(&linkdir.join(s));
+
+        // no-detached: git.exe -> bin, detached-only: .make.exe -> bin, .vim.exe (copy)
+        wslcmd_list
+            .link_wslcmd_with(
+                &"git",
+                &LinkOptions::new().detached(DetachedMode::NoDetached),
+            )
+            .expect("Link no-detached");
+        wslcmd_list
+            .link_wslcmd_with(
+                &"make",
+                &LinkOptions::new().detached(DetachedMode::DetachedOnly),
+            )
+            .expect("Link detached-only");
+        wslcmd_list
+            .link_wslcmd_with(
+                &"vim",
+                &LinkOptions::new()
+                    .kind(LinkKind::Copy)
+                    .detached(DetachedMode::DetachedOnly),
+            )
+            .expect("Link detached-only copy");
+        assert_eq!(
+            fs.read_link(&linkdir.join("git.exe")).expect("Read link"),
+            bin_target
+        );
+        assert_eq!(
+            fs.read_link(&linkdir.join(".make.exe")).expect("Read link"),
+            bin_target
+        );
+        assert!(fs.is_file(&linkdir.join(".vim.exe")));
+        assert!(!exists(".git.exe") && !exists("make.exe") && !exists("vim.exe"));
+        unit_test_cmdlist(&mut wslcmd_list, &["git", "make", "vim"]);
+
+        // shim scripts have no detached link
+        assert!(wslcmd_list
+            .link_wslcmd_with(
+                &"node",
+                &LinkOptions::new()
+                    .kind(LinkKind::ShimCmd)
+                    .detached(DetachedMode::NoDetached),
+            )
+            .is_err());
+        assert!(!exists("node.cmd"));
+
+        // scanned without index, and detached-only links are not orphaned
+        fs.remove_file(&linkdir.join("wslcmd.index"))
+            .expect("Remove index");
+        let mut wslcmd_list =
+            WslCmdList::with_fs(&binpath, &linkdir, fs.clone()).expect("New WslCmdList");
+        unit_test_cmdlist(&mut wslcmd_list, &["git", "make", "vim"]);
+        assert!(wslcmd_list.diagnose().is_empty());
+
+        // convert between modes
+        wslcmd_list
+            .set_detached_mode(&"git", DetachedMode::Both)
+            .expect("Convert to both");
+        wslcmd_list
+            .set_detached_mode(&"make", DetachedMode::NoDetached)
+            .expect("Convert to no-detached");
+        wslcmd_list
+            .set_detached_mode(&"vim", DetachedMode::Both)
+            .expect("Convert copy to both");
+        assert_eq!(
+            fs.read_link(&linkdir.join("git.exe")).expect("Read link"),
+            PathBuf::from(".git.exe")
+        );
+        assert_eq!(
+            fs.read_link(&linkdir.join("make.exe")).expect("Read link"),
+            bin_target
+        );
+        assert!(!exists(".make.exe"));
+        assert!(fs.is_file(&linkdir.join("vim.exe")) && fs.is_file(&linkdir.join(".vim.exe")));
+        assert_eq!(
+            ["git", "make", "vim"]
+                .iter()
+                .map(|s| wslcmd_list
+                    .get_link_meta(s)
+                    .map(|meta| *meta.get_detached()))
+                .collect::<Vec<_>>(),
+            vec![
+                Some(DetachedMode::Both),
+                Some(DetachedMode::NoDetached),
+                Some(DetachedMode::Both)
+            ]
+        );
+        unit_test_cmdlist(&mut wslcmd_list, &["git", "make", "vim"]);
+        assert!(wslcmd_list
+            .set_detached_mode(&"none", DetachedMode::NoDetached)
+            .is_err());
+
+        // unlink all, removing all files of links
+        ["git", "make", "vim"].iter().for_each(|s| {
+            wslcmd_list.unlink_wslcmd(s).expect("Unlink");
+        });
+        unit_test_cmdlist(&mut wslcmd_list, &([] as [&str; 0]));
+        assert!(["git.exe", ".git.exe", "make.exe", "vim.exe", ".vim.exe"]
+            .iter()
+            .all(|s| !exists(s)));
+    }
+
+    #[test]
+    fn test_mem_fs_force_backup() {
+        let (fs, binpath, linkdir) = init_mem_fs();
+        let mut wslcmd_list =
+            WslCmdList::with_fs(&binpath, &linkdir, fs.clone()).expect("New WslCmdList");
+        let read = |s: &str| fs.read_to_string(&linkdir.join(s)).ok();
+        ["make.exe", "cc.exe", "cc.exe.wslcmd-backup"]
+            .iter()
+            .for_each(|s| {
+                fs.write_new(&linkdir.join(s), s.as_bytes())
+                    .expect("Dummy file");
+            });
+
+        // existing file is moved to backup only if forced
+        assert!(wslcmd_list.link_wslcmd(&"make").is_err());
+        wslcmd_list
+            .link_wslcmd_with(&"make", &LinkOptions::new().force(true))
+            .expect("Link forced");
+        assert_eq!(read("make.exe.wslcmd-backup").as_deref(), Some("make.exe"));
+        assert_eq!(
+            wslcmd_list
+                .get_link_meta("make")
+                .map(|meta| meta.get_backups().clone()),
+            Some(vec!["make.exe".to_string()])
+        );
+        unit_test_cmdlist(&mut wslcmd_list, &["make"]);
+
+        // existing backup is not overwritten, and nothing is linked
+        assert!(wslcmd_list
+            .link_wslcmd_batch(&["git", "cc"], &LinkOptions::new().force(true))
+            .is_err());
+        assert_eq!(read("cc.exe").as_deref(), Some("cc.exe"));
+        unit_test_cmdlist(&mut wslcmd_list, &["make"]);
+
+        // backup is restored when unlinked, or renamed
+        wslcmd_list.unlink_wslcmd(&"make").expect("Unlink");
+        assert_eq!(read("make.exe").as_deref(), Some("make.exe"));
+        assert!(read("make.exe.wslcmd-backup").is_none());
+        wslcmd_list
+            .link_wslcmd_with(&"make", &LinkOptions::new().force(true))
+            .expect("Link forced");
+        wslcmd_list
+            .rename_wslcmd(&"make", &"gmake")
+            .expect("Rename");
+        assert_eq!(read("make.exe").as_deref(), Some("make.exe"));
+        assert!(wslcmd_list
+            .get_link_meta("gmake")
+            .is_some_and(|meta| meta.get_backups().is_empty()));
+        unit_test_cmdlist(&mut wslcmd_list, &["gmake"]);
+    }
+
+    #[test]
+    fn test_mem_fs_missing_link() {
+        let (fs, binpath, linkdir) = init_mem_fs();
+        let mut wslcmd_list =
+            WslCmdList::with_fs(&binpath, &linkdir, fs.clone()).expect("New WslCmdList");
+        let read = |s: &str| fs.read_to_string(&linkdir.join(s)).ok();
+        let is_missing = |wslcmd_list: &mut WslCmdList, s: &str| {
+            wslcmd_list.get_link_meta(s).map(|meta| *meta.get_missing())
+        };
+        fs.write_new(&linkdir.join("make.exe"), b"make.exe")
+            .expect("Dummy file");
+        wslcmd_list
+            .link_wslcmd_with(&"make", &LinkOptions::new().force(true).tags(&["build"]))
+            .expect("Link forced");
+        wslcmd_list.link_wslcmd(&"git").expect("Link");
+        let manifest_saved = read("wslcmd.toml");
+        assert!(manifest_saved.is_some());
+
+        // link files removed outside: entry is kept with backups and tags, without saving
+        ["make.exe", ".make.exe"].iter().for_each(|s| {
+            fs.remove_file(&linkdir.join(s)).expect("Remove link");
+        });
+        unit_test_cmdlist(&mut wslcmd_list, &["git"]);
+        assert_eq!(is_missing(&mut wslcmd_list, "make"), Some(true));
+        let meta = wslcmd_list.get_link_meta("make").expect("Meta").clone();
+        assert_eq!(meta.get_backups(), &vec!["make.exe".to_string()]);
+        assert_eq!(meta.get_tags(), &vec!["build".to_string()]);
+        assert_eq!(read("wslcmd.toml"), manifest_saved);
+
+        // doctor re-creates the link
+        let issues = wslcmd_list.diagnose();
+        assert_eq!(issues.len(), 1);
+        wslcmd_list.fix_issue(&issues[0]).expect("Fix");
+        unit_test_cmdlist(&mut wslcmd_list, &["git", "make"]);
+        assert_eq!(is_missing(&mut wslcmd_list, "make"), Some(false));
+
+        // unlink of missing link restores its backup
+        ["make.exe", ".make.exe"].iter().for_each(|s| {
+            fs.remove_file(&linkdir.join(s)).expect("Remove link");
+        });
+        wslcmd_list.unlink_wslcmd(&"make").expect("Unlink missing");
+        assert!(wslcmd_list.get_link_meta("make").is_none());
+        assert_eq!(read("make.exe").as_deref(), Some("make.exe"));
+        assert!(read("make.exe.wslcmd-backup").is_none());
+        assert!(wslcmd_list.unlink_wslcmd(&"make").is_err());
+    }
+
+    #[test]
+    fn test_mem_fs_tags() {
+        let (fs, binpath, linkdir) = init_mem_fs();
+        let mut wslcmd_list =
+            WslCmdList::with_fs(&binpath, &linkdir, fs.clone()).expect("New WslCmdList");
+        let tags = |wslcmd_list: &mut WslCmdList, s: &str| {
+            wslcmd_list
+                .get_link_meta(s)
+                .map(|meta| meta.get_tags().clone())
+        };
+        let select = |wslcmd_list: &mut WslCmdList, s: &str| {
+            wslcmd_list
+                .select_wslcmds(&CmdSelector::Tag(s.to_string()))
+                .expect("Select")
+        };
+
+        // link with tags, and select by tag
+        wslcmd_list
+            .link_wslcmd_batch(
+                &["gcc", "make", "cmake"],
+                &LinkOptions::new().tags(&["build", "build"]),
+            )
+            .expect("Link with tags");
+        wslcmd_list.link_wslcmd(&"git").expect("Link");
+        assert_eq!(tags(&mut wslcmd_list, "gcc"), Some(vec!["build".into()]));
+        assert_eq!(
+            select(&mut wslcmd_list, "build"),
+            vec!["cmake", "gcc", "make"]
+        );
+
+        // invalid tag is refused
+        assert!(wslcmd_list
+            .link_wslcmd_with(&"ls", &LinkOptions::new().tags(&["a b"]))
+            .is_err());
+        assert!(wslcmd_list
+            .update_link_tags(&"git", &["a,b"], &[] as &[&str])
+            .is_err());
+        unit_test_cmdlist(&mut wslcmd_list, &["gcc", "make", "cmake", "git"]);
+
+        // add and remove tags
+        wslcmd_list
+            .update_link_tags(&"cmake", &["experiments"], &["build"])
+            .expect("Update tags");
+        assert_eq!(
+            tags(&mut wslcmd_list, "cmake"),
+            Some(vec!["experiments".into()])
+        );
+        assert_eq!(select(&mut wslcmd_list, "build"), vec!["gcc", "make"]);
+        assert!(wslcmd_list
+            .update_link_tags(&"emacs", &["build"], &[] as &[&str])
+            .is_err());
+
+        // tags are kept when renamed
+        wslcmd_list
+            .rename_wslcmd(&"cmake", &"cmake3")
+            .expect("Rename");
+        assert_eq!(select(&mut wslcmd_list, "experiments"), vec!["cmake3"]);
+    }
+
+    #[test]
+    fn test_mem_fs_prefix_of_link() {
+        let (fs, binpath, linkdir) = init_mem_fs();
+        let mut wslcmd_list =
+            WslCmdList::with_fs(&binpath, &linkdir, fs.clone()).expect("New WslCmdList");
+        let command_prefix = |wslcmd_list: &mut WslCmdList, s: &str| {
+            wslcmd_list
+                .get_link_meta(s)
+                .map(|meta| (meta.get_command().clone(), meta.get_prefix().clone()))
+        };
+
+        // prefixed link and plain link starting with the same letters
+        wslcmd_list
+            .link_wslcmd_with(&"find", &LinkOptions::new().prefix("w"))
+            .expect("Link with prefix");
+        wslcmd_list.link_wslcmd(&"which").expect("Link");
+        assert_eq!(
+            command_prefix(&mut wslcmd_list, "wfind"),
+            Some(("find".into(), Some("w".into())))
+        );
+        assert_eq!(
+            command_prefix(&mut wslcmd_list, "which"),
+            Some(("which".into(), None))
+        );
+
+        // link without manifest entry (created by older versions) has no prefix
+        fs.symlink_file(&PathBuf::from(".wget.exe"), &linkdir.join("wget.exe"))
+            .and_then(|_| {
+                fs.symlink_file(
+                    &PathBuf::from("..").join("bin").join("wslcmd.exe"),
+                    &linkdir.join(".wget.exe"),
+                )
+            })
+            .expect("Link without manifest");
+        assert_eq!(
+            command_prefix(&mut wslcmd_list, "wget"),
+            Some(("wget".into(), None))
+        );
+
+        // prefix is kept only for the link created with it, on rename
+        wslcmd_list
+            .rename_wslcmd(&"which", &"which!!debian")
+            .expect("Rename");
+        wslcmd_list
+            .rename_wslcmd(&"wfind", &"wfind!!debian")
+            .expect("Rename prefixed");
+        assert_eq!(
+            command_prefix(&mut wslcmd_list, "which!!debian"),
+            Some(("which".into(), None))
+        );
+        assert_eq!(
+            command_prefix(&mut wslcmd_list, "wfind!!debian"),
+            Some(("find".into(), Some("w".into())))
+        );
+
+        // unlinking a prefixed link leaves other links starting with its prefix
+        wslcmd_list.unlink_wslcmd(&"wfind!!debian").expect("Unlink");
+        assert_eq!(
+            command_prefix(&mut wslcmd_list, "which!!debian"),
+            Some(("which".into(), None))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(not(target_os = "windows"), ignore)] // requires WSL
+    fn test_link_verify() {
+        let (fs, binpath, linkdir) = init_mem_fs();
+        let mut wslcmd_list =
+            WslCmdList::with_fs(&binpath, &linkdir, fs.clone()).expect("New WslCmdList");
+        let opts = LinkOptions::new().verify(true);
+
+        // existing command is linked, and missing one is refused with nothing linked
+        wslcmd_list.link_wslcmd_with(&"sh", &opts).expect("Link");
+        let e = wslcmd_list
+            .link_wslcmd_batch(&["ls", "wslcmd-no-such-cmd"], &opts)
+            .expect_err("Link missing command");
+        assert_eq!(e.kind(), ErrorKind::NotFound);
+        assert!(e.to_string().contains("'wslcmd-no-such-cmd'"));
+        unit_test_cmdlist(&mut wslcmd_list, &["sh"]);
+
+        // linked anyway without verification
+        wslcmd_list
+            .link_wslcmd_with(&"wslcmd-no-such-cmd", &opts.verify(false))
+            .expect("Link without verification");
+        unit_test_cmdlist(&mut wslcmd_list, &["sh", "wslcmd-no-such-cmd"]);
+    }
+
+    // init in-memory fs with the binary in 'bin' dir, and empty 'links' dir
+    //   returns (fs, binpath, linkdir)
+    pub fn init_mem_fs() -> (Rc<MemFs>, PathBuf, PathBuf) {
+        let fs = Rc::new(MemFs::new());
+        let (bindir, linkdir) = (mem_root().join("bin"), mem_root().join("links"));
+        fs.create_dir_all(&bindir).expect("Bin dir initialize");
+        fs.create_dir_all(&linkdir).expect("Link dir initialize");
+        fs.write_new(&bindir.join("wslcmd.exe"), b"wslcmd")
+            .expect("Bin initialize");
+
+        (fs, bindir.join("wslcmd.exe"), linkdir)
+    }
+
+    pub(super) fn init_tmpdir(unique_postfix: &str) -> Option<PathBuf> {
+        // clean tmpdir if already exists
+        clean_tmpdir(unique_postfix);
+
+        // create tmpdir
+        Some(env::temp_dir().join(format!("{}{}", TEST_TMP_DIR, unique_postfix)))
+            .and_then(|pb| fs::create_dir_all(&pb).ok().map(|_| pb))
+    }
+
+    pub(super) fn copy_tmpbin(tmpdir: &Path, binname: Option<&str>) -> Option<(PathBuf, String)> {
+        // get current exe
+        env::current_exe()
+            .ok()
+            // map cur-bin -> (dest-bin, cur-bin)
+            .and_then(|pb| {
+                Some((
+                    tmpdir.join(wslcmd_with_ext!(binname.unwrap_or(pb.wcpath_basename()?))),
+                    pb,
+                ))
+            })
+            // copy cur-bin to dest-bin (tmpdir)
+            .and_then(|(pb_dest, pb_cur)| {
+                fs::copy(&pb_cur, &pb_dest).ok()?; // if copy fails, return None
+                Some(pb_dest) // pass dest bin path to next map
+            })
+            // pb_dest to (pb_dest, string_basename)
+            .and_then(|pb_dest| {
+                pb_dest
+                    .wcpath_basename()
+                    .and_then(|s| s.wcstr_to_string())
+                    .map(|s_base| (pb_dest, s_base))
+            })
+    }
+
+    pub(super) fn clean_tmpdir(unique_postfix: &str) -> Option<()> {
+        Some(std::env::temp_dir().join(format!("{}{}", TEST_TMP_DIR, unique_postfix)))
+            .filter(|p| p.exists()) // only if p exists
+            .map(|p| {
+                match p.is_dir() {
+                    true => fs::remove_dir_all(&p),
+                    false => fs::remove_file(&p),
+                }
+                .ok()
+            })?
+    }
+
+    fn unit_test_link_wslcmd(
+        tmpdir: &Path,
+        wslcmd_list: &mut WslCmdList,
+        cmdname_and_shoulderr: &[(&str, bool)],
+    ) {
+        for cur_elem in cmdname_and_shoulderr {
+            unit_test_mod(tmpdir, wslcmd_list, cur_elem.0, cur_elem.1, TestKind::Link)
+                .unwrap_or_else(|e| panic!("unit_test_link_wslcmd(\"{}\"): {:?}", cur_elem.0, e))
+        }
+    }
+
+    fn unit_test_unlink_wslcmd(
+        tmpdir: &Path,
+        wslcmd_list: &mut WslCmdList,
+        cmdname_and_shoulderr: &[(&str, bool)],
+    ) {
+        for cur_elem in cmdname_and_shoulderr {
+            unit_test_mod(
+                tmpdir,
+                wslcmd_list,
+                cur_elem.0,
+                cur_elem.1,
+                TestKind::Unlink,
+            )
+            .unwrap_or_else(|e| panic!("unit_test_unlink_wslcmd(\"{}\"): {:?}", cur_elem.0, e))
+        }
+    }
+
+    fn unit_test_cmdlist<T: WCStr>(wslcmd_list: &mut WslCmdList, expected_result: &[T]) {
+        unit_test_list(wslcmd_list, expected_result).expect("unit_test_cmdlist()")
+    }
+
+    fn unit_test_mod(
+        tmpdir: &Path,
+        wslcmd_list: &mut WslCmdList,
+        cmdname: &str,
+        should_err: bool,
+        testkind: TestKind,
+    ) -> io::Result<()> {
+        dbg!(&tmpdir, &wslcmd_list, &cmdname, &should_err, &testkind);
+
+        // get dir entries before call
+        let dirent_before: HashSet<_> = HashSet::from_iter(
+            tmpdir
+                .wcpath_read_dir()
+                .ok_or(Error::other("Error on prepare"))?
+                .into_iter()
+                .filter(|pb| !is_manifest_file(pb)),
+        );
+
+        // run link_wslcmd
+        let call_result = match testkind {
+            TestKind::Link => wslcmd_list.link_wslcmd(&cmdname),
+            TestKind::Unlink => wslcmd_list.unlink_wslcmd(&cmdname),
+        };
+        dbg!(&call_result);
+
+        // get dir entries after call
+        let dirent_after: HashSet<_> = HashSet::from_iter(
+            tmpdir
+                .wcpath_read_dir()
+                .ok_or(Error::other("Error on prepare"))?
+                .into_iter()
+                .filter(|pb| !is_manifest_file(pb)),
+        );
+
+        // get diff of before <-> after
+        let mut dirent_diff = dirent_before.symmetric_difference(&dirent_after);
+
+        // validate result if worked as expected
+        match should_err {
+            // if the call result should be Err
+            true => {
+                // check if Err
+                {
+                    call_result
+                        .is_err()
+                        .then_some(())
+                        .ok_or(Error::other(concat!(
+                            "Test validation failed: ",
+                            "Function call returned Ok while it should return Err"
+                        )))
+                }
+                // check if changes are as expected
+                .and_then(|_|
+                    // check if no difference before & after the func call
+                    (dirent_diff.by_ref().count() == 0)
+                        .then_some(())
+                        .ok_or(Error::other(concat!(
+                                "Test validation failed: ",
+                                "Directory entries changed after failed job"
+                            ),
+                        )))
+            }
+
+            // if the call result should be Ok
+            false => {
+                // check if Ok
+                {
+                    call_result
+                        .is_ok()
+                        .then_some(())
+                        .ok_or(Error::other(concat!(
+                            "Test validation failed: ",
+                            "Function call returned Err while it should return Ok"
+                        )))
+                }
+                // check if changes are as expected
+                .and_then(|_| {
+                    // check if # increased when Link, and decreased when Unlink
+                    match testkind {
+                        TestKind::Link => dirent_before.len() < dirent_after.len(),
+                        TestKind::Unlink => dirent_before.len() > dirent_after.len(),
+                    }
+                    // check if different item is what the call expected
+                    .bitand({
+                        let diff_expected = [
+                            wslcmd_list
+                                .binpath
+                                .with_file_name(wslcmd_with_ext!(cmdname)),
+                            wslcmd_list
+                                .binpath
+                                .with_file_name(wslcmd_detached_bin!(wslcmd_with_ext!(cmdname))),
+                        ];
+
+                        HashSet::<_>::from_iter(&diff_expected)
+                            .symmetric_difference(&HashSet::from_iter(&mut dirent_diff))
+                            .count()
+                            == 0
+                    })
+                    .then_some(())
+                    .ok_or(Error::other(concat!(
+                        "Test validation failed: ",
+                        "Changed directory entries are not matched with expected changes"
+                    )))
+                })
+            }
+        }
+    }
+
+    fn unit_test_list<T: WCStr>(
+        wslcmd_list: &mut WslCmdList,
+        expected_result: &[T],
+    ) -> io::Result<()> {
+        // convert cmdlist in wslcmd_list to basenamed result
+        let cmdlist_basename: &HashSet<String> = wslcmd_list.get_cmdlist();
+        dbg!(&cmdlist_basename);
+
+        // convert expected list to basenamed result
+        let expected_list_basename: HashSet<_> = HashSet::from_iter(
+            expected_result
+                .iter()
+                .filter_map(|s| s.wcpath_basename().wcstr_to_string()),
+        );
+        dbg!(&expected_list_basename);
+
+        // get diff of expected and real result
+        let list_diff = cmdlist_basename.symmetric_difference(&expected_list_basename);
+        //dbg!(&list_diff);
+
+        // validate result if worked as expected
+        { list_diff.count() == 0 }
+            .then_some(())
+            .ok_or(Error::other("Test validation failed"))
+    }
+
+    fn unit_test_meta(
+        wslcmd_list: &mut WslCmdList,
+        cmdname: &str,
+        command: &str,
+        description: Option<&str>,
+    ) {
+        let meta = wslcmd_list.get_link_meta(cmdname).expect("Get link meta");
+        dbg!(&meta);
+
+        assert_eq!(meta.get_command(), command);
+        assert_eq!(meta.get_description().as_deref(), description);
+    }
+
+    // check if given path is manifest (or lock, index) file, which is not a link
+    fn is_manifest_file(pb: &Path) -> bool {
+        pb.extension().is_some_and(|ext| {
+            ["toml", "tmp", "lock", "index", "journal"]
+                .iter()
+                .any(|s| ext == *s)
+        })
+    }
+
+    fn new_dummy_file<T: WCPath>(fpath: &T) {
+        use std::fs::File;
+        use std::io::prelude::*;
+
+        fpath
+            .wcpath_as_ref()
+            .ok_or(Error::new(ErrorKind::InvalidInput, "Arguments not valid"))
+            .and_then(File::create)
+            .and_then(|mut f| f.write_all(b"wslcmd dummy"))
+            .expect("Dummy file creation");
+    }
+}
@@ -0,0 +1,270 @@
+use std::ffi::OsStr;
+use std::io;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use super::super::{DetachedMode, LinkKind, LinkMeta, WCPath, WslCmdManifest};
+use super::{link_file_paths, LinkResults, WslCmdList, BINARY_EXTENSION, DETACHED_PROC_PREFIX};
+
+/// Extension appended to link files of another binary, while retargeting them
+const RETARGET_BACKUP_EXTENSION: &str = "retarget-old";
+
+impl WslCmdList {
+    ///
+    /// Retarget links of another WslCmd binary in the link dir to current binary
+    ///
+    /// This is used after the binary is renamed or moved (Ex: 'wslcmd.exe' -> 'wslcmd-0.9.3.exe'),
+    /// as links point to the filename of the binary.
+    /// Both symlinks of each chain are re-created, and metadata of the links are moved
+    /// from the manifest of the old binary. Shim scripts, hardlinks and copies recorded
+    /// in the old manifest are re-created as well.
+    ///
+    /// # Arguments
+    ///
+    /// * `binname_old` - Filename of the old binary (Extension can be omitted)
+    ///
+    /// # Return
+    ///
+    /// [`Ok`]\([`Vec`]\) of (link name, result) of each found link, sorted by link name.
+    /// If failed to retarget a link, the link is left as it was.
+    ///
+    /// [`Err`]\([`Error`]\) if the old binary name is invalid, or failed to load its manifest
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// for (linkname, result) in wslcmd_list.retarget_from("wslcmd.exe")? {
+    ///     println!("{}: {:?}", linkname, result);
+    /// }
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn retarget_from<T: WCPath>(&mut self, binname_old: &T) -> io::Result<LinkResults> {
+        // filename of the old binary, with the binary extension
+        let binname_old = {
+            binname_old
+                .wcpath_filename()
+                .filter(|s| !s.starts_with(DETACHED_PROC_PREFIX))
+                .map(|s| match Path::new(s).extension() {
+                    Some(_) => s.to_string(),
+                    None => wslcmd_with_ext!(s),
+                })
+                .ok_or(Error::new(ErrorKind::InvalidInput, "Invalid binary name"))?
+        };
+        self.binpath
+            .wcpath_filename()
+            .is_none_or(|s| !s.eq_ignore_ascii_case(&binname_old))
+            .then_some(())
+            .ok_or(Error::new(
+                ErrorKind::InvalidInput,
+                "Given binary is the current binary",
+            ))?;
+
+        self.with_journal("retarget", |this| {
+            let mut manifest_old =
+                WslCmdManifest::load_with(&this.linkdir.join(&binname_old), this.fs.clone())?;
+            let links = this.find_links_of(&binname_old, &manifest_old);
+
+            // retarget each link, moving its metadata to current manifest
+            let results: LinkResults = links
+                .into_iter()
+                .map(|(linkname, kind, detached)| {
+                    let meta = {
+                        manifest_old
+                            .get(&linkname)
+                            .cloned()
+                            .unwrap_or_else(|| LinkMeta::new(&linkname, None))
+                    };
+                    let result = this.retarget_link(&linkname, meta.kind(kind).detached(detached));
+                    (linkname, result)
+                })
+                .collect();
+
+            // drop moved links from the old manifest, if it exists
+            if this.fs.exists(manifest_old.get_path()) {
+                results
+                    .iter()
+                    .filter(|(_, result)| result.is_ok())
+                    .for_each(|(linkname, _)| {
+                        manifest_old.remove(linkname);
+                    });
+                manifest_old.save()?;
+            }
+
+            this.refresh_wslcmd_list(true);
+            Ok(results)
+        })
+    }
+
+    // find links of the old binary, and their kinds and detached modes
+    //   - symlink chains of which detached link points to the old binary's filename
+    //   - shim scripts, hardlinks, copies and single links recorded in the old manifest
+    fn find_links_of(
+        &self,
+        binname_old: &str,
+        manifest_old: &WslCmdManifest,
+    ) -> Vec<(String, LinkKind, DetachedMode)> {
+        let mut links: Vec<(String, LinkKind, DetachedMode)> = self
+            .fs
+            .read_dir(&self.linkdir)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(pb, _)| pb)
+            .filter(|pb| pb.extension().and_then(OsStr::to_str) == Some(BINARY_EXTENSION))
+            .filter_map(|pb| {
+                let linkname = pb.wcpath_basename()?.to_string();
+                let pb_detached =
+                    link_file_paths(&pb, LinkKind::Symlink, DetachedMode::Both).pop()?;
+
+                // chain: cmd.exe -> .cmd.exe -> (old binary)
+                (!linkname.starts_with(DETACHED_PROC_PREFIX)
+                    && self.fs.read_link(&pb).ok()?.file_name() == pb_detached.file_name()
+                    && self
+                        .fs
+                        .read_link(&pb_detached)
+                        .ok()?
+                        .file_name()
+                        .and_then(OsStr::to_str)
+                        .is_some_and(|s| s.eq_ignore_ascii_case(binname_old)))
+                .then_some((linkname, LinkKind::Symlink, DetachedMode::Both))
+            })
+            .chain(
+                manifest_old
+                    .get_links()
+                    .iter()
+                    .filter(|(_, meta)| {
+                        !meta.get_kind().is_symlink() || !meta.get_detached().is_both()
+                    })
+                    .filter(|(linkname, meta)| {
+                        self.cmd_pathbuf(linkname).is_ok_and(|pb_cmd| {
+                            link_file_paths(&pb_cmd, *meta.get_kind(), *meta.get_detached())
+                                .iter()
+                                .any(|pb| self.fs.entry_exists(pb))
+                        })
+                    })
+                    .map(|(linkname, meta)| {
+                        (linkname.clone(), *meta.get_kind(), *meta.get_detached())
+                    }),
+            )
+            .collect();
+        links.sort_by(|a, b| a.0.cmp(&b.0));
+        links.dedup_by(|a, b| a.0 == b.0);
+
+        links
+    }
+
+    // re-create link chain to current binary, then record its metadata
+    // existing files are moved aside first, and moved back if failed
+    fn retarget_link(&mut self, linkname: &str, meta: LinkMeta) -> io::Result<()> {
+        let (kind, detached) = (*meta.get_kind(), *meta.get_detached());
+        let pb_cmd = self.cmd_pathbuf(&linkname)?;
+
+        // move existing link files aside: [(file, backup)]
+        let backups: Vec<(PathBuf, PathBuf)> = link_file_paths(&pb_cmd, kind, detached)
+            .into_iter()
+            .filter(|pb| self.fs.entry_exists(pb))
+            .map(|pb| {
+                let mut s_backup = pb.clone().into_os_string();
+                s_backup.push(format!(".{}", RETARGET_BACKUP_EXTENSION));
+                (pb, PathBuf::from(s_backup))
+            })
+            .collect();
+        let fs = self.fs.clone();
+        let restore = |moved: &[(PathBuf, PathBuf)]| {
+            moved.iter().for_each(|(pb, pb_backup)| {
+                fs.rename(pb_backup, pb).ok();
+            })
+        };
+        backups
+            .iter()
+            .enumerate()
+            .try_for_each(|(i, (pb, pb_backup))| {
+                fs.rename(pb, pb_backup).inspect_err(|_| {
+                    restore(&backups[..i]);
+                })
+            })?;
+
+        // create new link chain, and record metadata
+        self.create_link_chain(&pb_cmd, kind, detached)
+            .and_then(|_| {
+                let meta_prev = self.manifest.insert(linkname, meta);
+                self.manifest.save().inspect_err(|_| {
+                    // if failed, restore manifest and clean progress (remove created links)
+                    match meta_prev {
+                        Some(meta_prev) => self.manifest.insert(linkname, meta_prev),
+                        None => self.manifest.remove(linkname),
+                    };
+                    self.remove_link_chain(&pb_cmd, kind, detached).ok();
+                })
+            })
+            // remove moved files if succeeded, or move them back if failed
+            .map(|_| {
+                backups.iter().for_each(|(_, pb_backup)| {
+                    fs.remove_file(pb_backup).ok();
+                })
+            })
+            .inspect_err(|_| {
+                restore(&backups);
+            })
+    }
+}
+
+#[cfg(test)]
+/// For module test
+mod test {
+    use super::super::super::LinkKind;
+    use super::super::test::{clean_tmpdir, copy_tmpbin, init_tmpdir};
+    use super::super::{LinkOptions, WslCmdList};
+    use std::fs;
+
+    #[test]
+    fn test_retarget() {
+        const TMPDIR_POSTFIX: &str = "wslcmd-retarget";
+
+        // init tmpdir, and link commands to the old binary
+        let tmpdir = init_tmpdir(TMPDIR_POSTFIX).expect("Tmp dir initialize");
+        let (bin_old, binname_old) = copy_tmpbin(&tmpdir, None).expect("Bin initialize");
+        let mut wslcmd_list = WslCmdList::new(&bin_old).expect("New WslCmdList");
+        wslcmd_list
+            .link_wslcmd_with(&"git", &LinkOptions::new().description("Git"))
+            .expect("Link");
+        wslcmd_list
+            .link_wslcmd_with(&"node", &LinkOptions::new().kind(LinkKind::ShimCmd))
+            .expect("Link");
+        wslcmd_list.link_wslcmd(&"vim").expect("Link");
+
+        // rename the binary: all links are orphaned
+        let bin_new = tmpdir.join("wslcmd-new.exe");
+        fs::rename(&bin_old, &bin_new).expect("Rename bin");
+        let mut wslcmd_list = WslCmdList::new(&bin_new).expect("New WslCmdList");
+        assert!(wslcmd_list.get_cmdlist().is_empty());
+
+        // retarget from the old binary
+        let results = wslcmd_list.retarget_from(&binname_old).expect("Retarget");
+        dbg!(&results);
+        assert_eq!(
+            results.iter().map(|(s, _)| s.as_str()).collect::<Vec<_>>(),
+            vec!["git", "node", "vim"]
+        );
+        assert!(results.iter().all(|(_, result)| result.is_ok()));
+
+        // links and metadata are moved to the new binary
+        let cmdlist = wslcmd_list.get_cmdlist().clone();
+        assert!(["git", "node", "vim"].iter().all(|s| cmdlist.contains(*s)));
+        let meta = wslcmd_list.get_link_meta("git").expect("Meta");
+        assert_eq!(meta.get_description().as_deref(), Some("Git"));
+        assert!(!meta.get_reconciled());
+        assert!(fs::read_to_string(tmpdir.join("node.cmd"))
+            .expect("Shim")
+            .contains("wslcmd-new.exe"));
+        assert!(wslcmd_list.diagnose().is_empty());
+
+        // nothing left to retarget, and current binary cannot be retargeted
+        let results = wslcmd_list.retarget_from(&binname_old).expect("Retarget");
+        assert!(results.is_empty());
+        assert!(wslcmd_list.retarget_from(&"wslcmd-new").is_err());
+
+        // clean tmpdir
+        clean_tmpdir(TMPDIR_POSTFIX);
+    }
+}
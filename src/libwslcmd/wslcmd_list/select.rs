@@ -0,0 +1,181 @@
+use std::collections::HashSet;
+use std::io;
+use std::io::{Error, ErrorKind};
+
+use super::super::WCStr;
+use super::WslCmdList;
+
+/// Characters which make a command name a glob pattern
+const GLOB_CHARS: &[char] = &['*', '?', '['];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Selector of linked commands, by exact name or by pattern
+pub enum CmdSelector {
+    /// Exact link name
+    Name(String),
+
+    /// Glob pattern, matching whole link names (`*`, `?`, `[...]`)
+    Glob(String),
+
+    /// Regex pattern, matching any part of link names (Ex: `^git`)
+    Regex(String),
+
+    /// Tag of links, recorded in the manifest (Ex: `build`)
+    Tag(String),
+}
+
+impl CmdSelector {
+    ///
+    /// Parse a selector: glob pattern if it has glob characters, or exact name if not
+    ///
+    /// # Arguments
+    ///
+    /// * `s`        - A link name or pattern
+    /// * `is_regex` - Parse as a regex pattern
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// assert_eq!(CmdSelector::parse("git*", false), CmdSelector::Glob("git*".into()));
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn parse(s: &str, is_regex: bool) -> Self {
+        match (is_regex, s.contains(GLOB_CHARS)) {
+            (true, _) => Self::Regex(s.to_string()),
+            (false, true) => Self::Glob(s.to_string()),
+            (false, false) => Self::Name(s.to_string()),
+        }
+    }
+
+    ///
+    /// Check if the selector is a pattern, which may select multiple links
+    ///
+    #[allow(dead_code)]
+    pub fn is_pattern(&self) -> bool {
+        !matches!(self, Self::Name(_))
+    }
+}
+
+impl WslCmdList {
+    ///
+    /// Select linked commands with the selector
+    ///
+    /// # Arguments
+    ///
+    /// * `selector` - A link name or pattern
+    ///
+    /// # Return
+    ///
+    /// [`Ok`]\([`Vec`]\) of sorted link names (Empty if no link is selected),
+    /// [`Err`]\([`Error`]\) if the regex pattern is invalid
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let linknames = wslcmd_list.select_wslcmds(&CmdSelector::parse("*!debian", false))?;
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn select_wslcmds(&mut self, selector: &CmdSelector) -> io::Result<Vec<String>> {
+        let is_selected: Box<dyn Fn(&String) -> bool> = match selector {
+            CmdSelector::Name(name) => Box::new(move |s| s == name),
+            // exact name is also selected, as '[' is valid in filenames
+            CmdSelector::Glob(pattern) => {
+                Box::new(move |s| s == pattern || s.wcstr_matches_glob(pattern))
+            }
+            CmdSelector::Regex(pattern) => {
+                let re = regex::Regex::new(pattern).map_err(|e| {
+                    Error::new(ErrorKind::InvalidInput, format!("Invalid regex: {}", e))
+                })?;
+                Box::new(move |s| re.is_match(s))
+            }
+            CmdSelector::Tag(tag) => {
+                let tagged: HashSet<String> = {
+                    self.manifest
+                        .get_links()
+                        .iter()
+                        .filter(|(_, meta)| meta.has_tag(tag))
+                        .map(|(linkname, _)| linkname.clone())
+                        .collect()
+                };
+                Box::new(move |s| tagged.contains(s))
+            }
+        };
+
+        let mut linknames: Vec<String> = {
+            self.get_cmdlist()
+                .iter()
+                .filter(|s| is_selected(s))
+                .cloned()
+                .collect()
+        };
+        linknames.sort();
+
+        Ok(linknames)
+    }
+}
+
+#[cfg(test)]
+/// For module test
+mod test {
+    use super::super::super::link_fs::{mem_root, LinkFs, MemFs};
+    use super::super::{LinkOptions, WslCmdList};
+    use super::CmdSelector;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_select() {
+        // link commands on in-memory fs
+        let fs = Rc::new(MemFs::new());
+        let dir = mem_root().join("bin");
+        fs.create_dir_all(&dir).expect("Dir initialize");
+        fs.write_new(&dir.join("wslcmd.exe"), b"wslcmd")
+            .expect("Bin initialize");
+        let mut wslcmd_list =
+            WslCmdList::with_fs(&dir.join("wslcmd.exe"), &dir, fs).expect("New WslCmdList");
+        wslcmd_list
+            .link_wslcmd_batch(
+                &["git", "git-lfs", "gitk!!debian", "vim!!debian", "[x]"],
+                &LinkOptions::new(),
+            )
+            .expect("Link");
+
+        // parse
+        assert_eq!(
+            CmdSelector::parse("git", false),
+            CmdSelector::Name("git".into())
+        );
+        assert!(CmdSelector::parse("git?", false).is_pattern());
+        assert!(CmdSelector::parse("git", true).is_pattern());
+
+        // select by name, glob and regex
+        let select = |wslcmd_list: &mut WslCmdList, s: &str, is_regex: bool| {
+            wslcmd_list
+                .select_wslcmds(&CmdSelector::parse(s, is_regex))
+                .expect("Select")
+        };
+        assert_eq!(select(&mut wslcmd_list, "git", false), vec!["git"]);
+        assert!(select(&mut wslcmd_list, "emacs", false).is_empty());
+        assert_eq!(
+            select(&mut wslcmd_list, "git*", false),
+            vec!["git", "git-lfs", "gitk!!debian"]
+        );
+        assert_eq!(
+            select(&mut wslcmd_list, "*!debian", false),
+            vec!["gitk!!debian", "vim!!debian"]
+        );
+        assert_eq!(select(&mut wslcmd_list, "[x]", false), vec!["[x]"]);
+        assert_eq!(
+            select(&mut wslcmd_list, "Git*", false),
+            vec!["git", "git-lfs", "gitk!!debian"]
+        );
+        assert_eq!(
+            select(&mut wslcmd_list, "^git(-|$)", true),
+            vec!["git", "git-lfs"]
+        );
+        assert!(wslcmd_list
+            .select_wslcmds(&CmdSelector::parse("(", true))
+            .is_err());
+    }
+}
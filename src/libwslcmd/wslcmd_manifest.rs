@@ -0,0 +1,824 @@
+use derive_getters::Getters;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::io;
+use std::io::{Error, ErrorKind};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::link_fs::LinkFsRef;
+use super::{WCPath, CMDNAME_DELIM, DETACHED_PROC_PREFIX};
+
+/// Extension of manifest file
+const MANIFEST_EXTENSION: &str = "toml";
+
+/// Current schema version of manifest file
+const MANIFEST_SCHEMA_VERSION: u32 = 1;
+
+/// Delimiter of tags in a list (Ex: 'build,ci')
+const TAG_DELIM: char = ',';
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+/// Kind of link files of a linked command
+pub enum LinkKind {
+    /// Symlink chain to the binary (`cmd.exe` -> `.cmd.exe` -> binary).
+    /// Needs Developer Mode or admin privileges on creation.
+    #[default]
+    Symlink,
+
+    /// Hardlinks of the binary (`cmd.exe`, `.cmd.exe`), on the same volume
+    Hardlink,
+
+    /// Copies of the binary (`cmd.exe`, `.cmd.exe`), used as launchers
+    Copy,
+
+    /// Batch shim script (`cmd.cmd`), which runs the binary with the command name
+    ShimCmd,
+
+    /// PowerShell shim script (`cmd.ps1`), which runs the binary with the command name
+    ShimPs1,
+
+    /// Both of batch and PowerShell shim scripts (`cmd.cmd`, `cmd.ps1`)
+    ShimBoth,
+}
+
+impl LinkKind {
+    ///
+    /// Parse [`LinkKind`] from its name
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the kind (`symlink`, `hardlink`, `copy`)
+    ///
+    /// # Return
+    ///
+    /// [`Some`]\([`LinkKind`]\) if valid name, [`None`] if not
+    ///
+    #[allow(dead_code)]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "symlink" => Some(Self::Symlink),
+            "hardlink" => Some(Self::Hardlink),
+            "copy" => Some(Self::Copy),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Parse shim [`LinkKind`] from name of shim type
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of shim type (`cmd`, `ps1`, `both`)
+    ///
+    /// # Return
+    ///
+    /// [`Some`]\([`LinkKind`]\) if valid name, [`None`] if not
+    ///
+    #[allow(dead_code)]
+    pub fn from_shim_name(name: &str) -> Option<Self> {
+        match name {
+            "cmd" => Some(Self::ShimCmd),
+            "ps1" => Some(Self::ShimPs1),
+            "both" => Some(Self::ShimBoth),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Get name of [`LinkKind`], which is same as the serialized one
+    ///
+    #[allow(dead_code)]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Symlink => "symlink",
+            Self::Hardlink => "hardlink",
+            Self::Copy => "copy",
+            Self::ShimCmd => "shim-cmd",
+            Self::ShimPs1 => "shim-ps1",
+            Self::ShimBoth => "shim-both",
+        }
+    }
+
+    ///
+    /// Check if links of this kind are symlinks
+    ///
+    #[allow(dead_code)]
+    pub fn is_symlink(&self) -> bool {
+        matches!(self, Self::Symlink)
+    }
+
+    ///
+    /// Check if links of this kind are hardlinks or copies of the binary
+    ///
+    #[allow(dead_code)]
+    pub fn is_binary_copy(&self) -> bool {
+        matches!(self, Self::Hardlink | Self::Copy)
+    }
+
+    ///
+    /// Get extensions of shim scripts of this kind (Empty if not a shim)
+    ///
+    #[allow(dead_code)]
+    pub fn shim_extensions(&self) -> &'static [&'static str] {
+        match self {
+            Self::ShimCmd => &["cmd"],
+            Self::ShimPs1 => &["ps1"],
+            Self::ShimBoth => &["cmd", "ps1"],
+            _ => &[],
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+/// Which of the link (`cmd.exe`) and its detached companion (`.cmd.exe`) are created
+pub enum DetachedMode {
+    /// Both of the link and the detached link (`cmd.exe` -> `.cmd.exe` -> binary)
+    #[default]
+    Both,
+
+    /// Only the link, without the detached one (`cmd.exe` -> binary)
+    NoDetached,
+
+    /// Only the detached link, which runs the command as a detached process (`.cmd.exe` -> binary)
+    DetachedOnly,
+}
+
+impl DetachedMode {
+    ///
+    /// Get name of [`DetachedMode`], which is same as the serialized one
+    ///
+    #[allow(dead_code)]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Both => "both",
+            Self::NoDetached => "no-detached",
+            Self::DetachedOnly => "detached-only",
+        }
+    }
+
+    ///
+    /// Check if both of the link and the detached link are created
+    ///
+    #[allow(dead_code)]
+    pub fn is_both(&self) -> bool {
+        matches!(self, Self::Both)
+    }
+
+    ///
+    /// Check if the link (non-detached one) is created
+    ///
+    #[allow(dead_code)]
+    pub fn has_link(&self) -> bool {
+        !matches!(self, Self::DetachedOnly)
+    }
+
+    ///
+    /// Check if the detached link is created
+    ///
+    #[allow(dead_code)]
+    pub fn has_detached(&self) -> bool {
+        !matches!(self, Self::NoDetached)
+    }
+}
+
+#[derive(Getters, Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+/// Metadata of a linked command, stored in the manifest
+pub struct LinkMeta {
+    /// Created time of the link (seconds since unix epoch)
+    #[getter(rename = "get_created")]
+    #[serde(default)]
+    created: u64,
+
+    /// Windows user who added the link
+    #[getter(rename = "get_added_by")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    added_by: Option<String>,
+
+    /// Description of the link
+    #[getter(rename = "get_description")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+
+    /// WSL command name, after stripping namespace prefix
+    #[getter(rename = "get_command")]
+    #[serde(default)]
+    command: String,
+
+    /// WSL user of the link
+    #[getter(rename = "get_user")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    user: Option<String>,
+
+    /// WSL distribution of the link
+    #[getter(rename = "get_dist")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dist: Option<String>,
+
+    /// Namespace prefix of the link
+    #[getter(rename = "get_prefix")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    prefix: Option<String>,
+
+    /// Kind of link files
+    #[getter(rename = "get_kind")]
+    #[serde(default, skip_serializing_if = "LinkKind::is_symlink")]
+    kind: LinkKind,
+
+    /// Which of the link and the detached link are created
+    #[getter(rename = "get_detached")]
+    #[serde(default, skip_serializing_if = "DetachedMode::is_both")]
+    detached: DetachedMode,
+
+    /// Filenames of existing files which were moved to backups on linking,
+    /// to be restored when the link is removed
+    #[getter(rename = "get_backups")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    backups: Vec<String>,
+
+    /// Tags to group the link (Ex: 'build')
+    #[getter(rename = "get_tags")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+
+    /// Set if the link was not created with manifest (by older versions),
+    /// and added to manifest later
+    #[getter(rename = "get_reconciled")]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    reconciled: bool,
+
+    /// Set if link files are missing on the directory (Ex: removed outside of WslCmd).
+    /// The entry is kept with its backups, until the link is re-created or unlinked.
+    #[getter(rename = "get_missing")]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    missing: bool,
+}
+
+impl LinkMeta {
+    ///
+    /// Create new [`LinkMeta`] from link name, for a link created now
+    ///
+    /// # Arguments
+    ///
+    /// * `linkname` - Link name (basename of the link file)
+    /// * `prefix`   - Namespace prefix of the link
+    ///
+    /// # Return
+    ///
+    /// A newly created [`LinkMeta`]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let meta = LinkMeta::new("w-git!john!debian", Some("w-"));
+    /// assert_eq!(meta.get_command(), "git");
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn new(linkname: &str, prefix: Option<&str>) -> Self {
+        let mut it = linkname.split(CMDNAME_DELIM);
+        let field = |s: Option<&str>| s.filter(|s| !s.is_empty()).map(String::from);
+
+        Self {
+            created: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            added_by: std::env::var("USERNAME").ok(),
+            description: None,
+            command: {
+                let cmd = it.next().unwrap_or_default();
+                prefix
+                    .and_then(|prefix| cmd.strip_prefix(prefix))
+                    .unwrap_or(cmd)
+                    .to_string()
+            },
+            user: field(it.next()),
+            dist: field(it.next()),
+            prefix: prefix.map(String::from),
+            kind: LinkKind::Symlink,
+            detached: DetachedMode::Both,
+            backups: vec![],
+            tags: vec![],
+            reconciled: false,
+            missing: false,
+        }
+    }
+
+    ///
+    /// Set description of [`LinkMeta`]
+    ///
+    /// # Arguments
+    ///
+    /// * `description` - Description of the link
+    ///
+    /// # Return
+    ///
+    /// Self [`LinkMeta`] after setting description
+    ///
+    #[allow(dead_code)]
+    pub fn description(mut self, description: Option<&str>) -> Self {
+        self.description = description.map(String::from);
+
+        self
+    }
+
+    ///
+    /// Set kind of link files of [`LinkMeta`]
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - Kind of link files
+    ///
+    /// # Return
+    ///
+    /// Self [`LinkMeta`] after setting kind
+    ///
+    #[allow(dead_code)]
+    pub fn kind(mut self, kind: LinkKind) -> Self {
+        self.kind = kind;
+
+        self
+    }
+
+    ///
+    /// Set which of the link and the detached link are created, of [`LinkMeta`]
+    ///
+    /// # Arguments
+    ///
+    /// * `detached` - Detached mode of the link
+    ///
+    /// # Return
+    ///
+    /// Self [`LinkMeta`] after setting detached mode
+    ///
+    #[allow(dead_code)]
+    pub fn detached(mut self, detached: DetachedMode) -> Self {
+        self.detached = detached;
+
+        self
+    }
+
+    ///
+    /// Set filenames of files moved to backups on linking, of [`LinkMeta`]
+    ///
+    /// # Arguments
+    ///
+    /// * `backups` - Original filenames of backed up files (Ex: `["make.exe"]`)
+    ///
+    /// # Return
+    ///
+    /// Self [`LinkMeta`] after setting backups
+    ///
+    #[allow(dead_code)]
+    pub fn backups(mut self, backups: Vec<String>) -> Self {
+        self.backups = backups;
+
+        self
+    }
+
+    ///
+    /// Set tags of [`LinkMeta`], sorted without duplication
+    ///
+    /// # Arguments
+    ///
+    /// * `tags` - Tags of the link
+    ///
+    /// # Return
+    ///
+    /// Self [`LinkMeta`] after setting tags
+    ///
+    #[allow(dead_code)]
+    pub fn tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self.tags.sort();
+        self.tags.dedup();
+
+        self
+    }
+
+    ///
+    /// Check if the link has the tag
+    ///
+    #[allow(dead_code)]
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|s| s == tag)
+    }
+
+    ///
+    /// Check if a tag is valid: non-empty, without whitespaces and tag delimiter (`,`)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// assert!(LinkMeta::is_valid_tag("build"));
+    /// assert!(!LinkMeta::is_valid_tag("build,ci"));
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn is_valid_tag(tag: &str) -> bool {
+        !tag.is_empty() && !tag.contains(|c: char| c == TAG_DELIM || c.is_whitespace())
+    }
+
+    ///
+    /// Split a list of tags delimited by `,` (Ex: 'build,ci' -> `["build", "ci"]`)
+    ///
+    #[allow(dead_code)]
+    pub fn split_tags(s: &str) -> Vec<String> {
+        s.split(TAG_DELIM)
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    ///
+    /// Create new [`LinkMeta`] for another link name, keeping other fields of self
+    ///
+    /// # Arguments
+    ///
+    /// * `linkname` - New link name
+    /// * `prefix`   - Namespace prefix of the new link
+    ///
+    /// # Return
+    ///
+    /// A newly created [`LinkMeta`], with fields parsed from the new link name
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let meta_renamed = meta.renamed("python!!debian", None);
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn renamed(&self, linkname: &str, prefix: Option<&str>) -> Self {
+        let meta_new = Self::new(linkname, prefix);
+
+        Self {
+            command: meta_new.command,
+            user: meta_new.user,
+            dist: meta_new.dist,
+            prefix: meta_new.prefix,
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+/// Serialized form of manifest file
+struct ManifestData {
+    /// Schema version of the manifest
+    #[serde(default)]
+    schema_version: u32,
+
+    /// Metadata of each link, by link name
+    #[serde(default)]
+    links: BTreeMap<String, LinkMeta>,
+}
+
+#[derive(Debug)]
+/// Manifest of links, stored next to the binary (`<binary-basename>.toml`)
+pub struct WslCmdManifest {
+    /// Path of manifest file
+    path: PathBuf,
+
+    /// Loaded manifest data
+    data: ManifestData,
+
+    /// Filesystem where the manifest file is stored
+    fs: LinkFsRef,
+}
+
+impl WslCmdManifest {
+    /// Extension of manifest file
+    pub const EXTENSION: &'static str = MANIFEST_EXTENSION;
+
+    ///
+    /// Load [`WslCmdManifest`] of the given binary
+    ///
+    /// # Arguments
+    ///
+    /// * `binpath` - A wslcmd bin path, of which the manifest is placed next to
+    ///
+    /// # Return
+    ///
+    /// [`Ok`]\([`WslCmdManifest`]\) if succeeded (Empty manifest if not exists),
+    /// [`Err`]\([`Error`]\) if failed to read or parse
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let manifest = WslCmdManifest::load(&"/path/to/wslcmd.exe");
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn load<T: WCPath>(binpath: &T) -> io::Result<Self> {
+        Self::load_with(binpath, LinkFsRef::default())
+    }
+
+    ///
+    /// Load [`WslCmdManifest`] of the given binary, on the given filesystem
+    ///
+    /// # Arguments
+    ///
+    /// * `binpath` - A wslcmd bin path, of which the manifest is placed next to
+    /// * `fs`      - A filesystem to read and write the manifest file
+    ///
+    /// # Return
+    ///
+    /// [`Ok`]\([`WslCmdManifest`]\) if succeeded (Empty manifest if not exists),
+    /// [`Err`]\([`Error`]\) if failed to read or parse
+    ///
+    #[allow(dead_code)]
+    pub fn load_with<T: WCPath>(binpath: &T, fs: LinkFsRef) -> io::Result<Self> {
+        let path = {
+            binpath
+                .wcpath_as_path()
+                .map(|p| p.with_extension(MANIFEST_EXTENSION))
+                .ok_or(Error::new(ErrorKind::InvalidInput, "Invalid exe path"))?
+        };
+
+        // read and parse manifest
+        let data = match fs.read_to_string(&path) {
+            Ok(s) => toml::from_str::<ManifestData>(&s)
+                .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))?,
+            Err(e) if e.kind() == ErrorKind::NotFound => ManifestData::default(),
+            Err(e) => return Err(e),
+        };
+
+        // check schema version
+        match data.schema_version {
+            v if v > MANIFEST_SCHEMA_VERSION => Err(Error::new(
+                ErrorKind::InvalidData,
+                "Manifest was written by a newer version of WslCmd",
+            )),
+            _ => Ok(Self { path, data, fs }),
+        }
+    }
+
+    ///
+    /// Find metadata of a link, in manifests inside the link directory
+    ///
+    /// Only the manifest of the binary is read if `binname` is given.
+    /// If not (Ex: hardlinks and copies, which cannot be followed to the original binary),
+    /// all manifests inside the directory are searched.
+    ///
+    /// # Arguments
+    ///
+    /// * `linkdir`  - A directory of the link, where manifests are placed
+    /// * `linkname` - A link name (Leading [`DETACHED_PROC_PREFIX`] is ignored)
+    /// * `binname`  - Filename of WslCmd binary which the link points to, if known
+    ///
+    /// # Return
+    ///
+    /// [`Some`]\([`LinkMeta`]\) if found, [`None`] if not
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let meta = WslCmdManifest::find_link_meta(&"/path/to/links", "w-git", Some("wslcmd.exe"));
+    /// ```
+    ///
+    #[allow(dead_code)]
+    pub fn find_link_meta<T: WCPath>(
+        linkdir: &T,
+        linkname: &str,
+        binname: Option<&str>,
+    ) -> Option<LinkMeta> {
+        let linkname = linkname
+            .strip_prefix(DETACHED_PROC_PREFIX)
+            .unwrap_or(linkname);
+        let manifest_paths: Vec<PathBuf> = match binname {
+            // manifest of the binary only
+            Some(binname) => vec![linkdir
+                .wcpath_as_path()?
+                .join(binname)
+                .with_extension(MANIFEST_EXTENSION)],
+
+            // all manifests inside the dir
+            None => linkdir
+                .wcpath_read_dir()?
+                .into_iter()
+                .filter(|pb| pb.extension().is_some_and(|ext| ext == MANIFEST_EXTENSION))
+                .collect(),
+        };
+
+        manifest_paths
+            .into_iter()
+            .filter_map(|pb| std::fs::read_to_string(pb).ok())
+            .filter_map(|s| toml::from_str::<ManifestData>(&s).ok())
+            .find_map(|mut data| data.links.remove(linkname))
+    }
+
+    ///
+    /// Get path of the manifest file
+    ///
+    #[allow(dead_code)]
+    pub fn get_path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    ///
+    /// Get metadata of all links
+    ///
+    #[allow(dead_code)]
+    pub fn get_links(&self) -> &BTreeMap<String, LinkMeta> {
+        &self.data.links
+    }
+
+    ///
+    /// Get metadata of a link
+    ///
+    /// # Arguments
+    ///
+    /// * `linkname` - Link name to get
+    ///
+    #[allow(dead_code)]
+    pub fn get(&self, linkname: &str) -> Option<&LinkMeta> {
+        self.data.links.get(linkname)
+    }
+
+    ///
+    /// Insert metadata of a link (not saved until [`save`](Self::save) is called)
+    ///
+    /// # Return
+    ///
+    /// Previous metadata of the link if exists
+    ///
+    #[allow(dead_code)]
+    pub fn insert(&mut self, linkname: &str, meta: LinkMeta) -> Option<LinkMeta> {
+        self.data.links.insert(linkname.to_string(), meta)
+    }
+
+    ///
+    /// Remove metadata of a link (not saved until [`save`](Self::save) is called)
+    ///
+    /// # Return
+    ///
+    /// Removed metadata of the link if exists
+    ///
+    #[allow(dead_code)]
+    pub fn remove(&mut self, linkname: &str) -> Option<LinkMeta> {
+        self.data.links.remove(linkname)
+    }
+
+    ///
+    /// Save manifest to the file, replacing it atomically
+    ///
+    /// # Return
+    ///
+    /// [`Ok`]\([`()`](unit)\) if succeeded, [`Err`]\([`Error`]\) if failed
+    ///
+    #[allow(dead_code)]
+    pub fn save(&mut self) -> io::Result<()> {
+        self.data.schema_version = MANIFEST_SCHEMA_VERSION;
+
+        let tmppath = self
+            .path
+            .with_extension(format!("{}.tmp", MANIFEST_EXTENSION));
+
+        toml::to_string_pretty(&self.data)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))
+            // write to tmp file first, then replace manifest with it
+            .and_then(|s| self.fs.write(&tmppath, s.as_bytes()))
+            .and_then(|_| {
+                self.fs.rename(&tmppath, &self.path).inspect_err(|_| {
+                    self.fs.remove_file(&tmppath).ok();
+                })
+            })
+    }
+
+    ///
+    /// Reconcile manifest with links existing on the directory:
+    /// add links not in manifest (created by older versions),
+    /// and flag entries without link as missing (kept with their backups, tags and description)
+    ///
+    /// Manifest is not saved here, but on the next change of links.
+    ///
+    /// # Arguments
+    ///
+    /// * `cmdlist` - Link names existing on the directory
+    ///
+    /// # Return
+    ///
+    /// `true` if manifest is changed
+    ///
+    #[allow(dead_code)]
+    pub fn reconcile(&mut self, cmdlist: &HashSet<String>) -> bool {
+        let mut changed = false;
+
+        // flag entries without link, and unflag entries of which link exists again
+        self.data.links.iter_mut().for_each(|(name, meta)| {
+            let missing = !cmdlist.contains(name);
+            if meta.missing != missing {
+                meta.missing = missing;
+                changed = true;
+            }
+        });
+
+        // add links without entry
+        cmdlist
+            .iter()
+            .filter(|name| !self.data.links.contains_key(*name))
+            .cloned()
+            .collect::<Vec<String>>()
+            .into_iter()
+            .for_each(|name| {
+                let meta = LinkMeta {
+                    created: 0, // unknown
+                    added_by: None,
+                    reconciled: true,
+                    // namespace prefix is unknown, as other links may start with the same letters
+                    ..LinkMeta::new(&name, None)
+                };
+                self.data.links.insert(name, meta);
+                changed = true;
+            });
+
+        // schema of older versions should be migrated
+        changed || self.data.schema_version < MANIFEST_SCHEMA_VERSION
+    }
+}
+
+#[cfg(test)]
+/// For module test
+mod test {
+    use super::{LinkMeta, ManifestData, WslCmdManifest, MANIFEST_SCHEMA_VERSION};
+
+    #[test]
+    fn test_linkmeta_new() {
+        let meta = LinkMeta::new("w-git!john!debian", Some("w-"));
+        assert_eq!(meta.get_command(), "git");
+        assert_eq!(meta.get_user().as_deref(), Some("john"));
+        assert_eq!(meta.get_dist().as_deref(), Some("debian"));
+
+        let meta = LinkMeta::new("emacs!!ubuntu", None);
+        assert_eq!(meta.get_command(), "emacs");
+        assert_eq!(meta.get_user(), &None);
+        assert_eq!(meta.get_dist().as_deref(), Some("ubuntu"));
+    }
+
+    #[test]
+    fn test_manifest_serde() {
+        let mut data = ManifestData {
+            schema_version: MANIFEST_SCHEMA_VERSION,
+            ..Default::default()
+        };
+        data.links.insert(
+            "git!!debian".to_string(),
+            LinkMeta::new("git!!debian", None).description(Some("Git of debian")),
+        );
+
+        let s = toml::to_string_pretty(&data).expect("Serialize manifest");
+        let data_parsed: ManifestData = toml::from_str(&s).expect("Deserialize manifest");
+        assert_eq!(data.links, data_parsed.links);
+        assert_eq!(data_parsed.schema_version, MANIFEST_SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_find_link_meta() {
+        let dir = std::env::temp_dir().join(format!(
+            "wslcmd_tmpdir_test-manifest-find_{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).expect("Tmp dir initialize");
+        let write_manifest = |binname: &str, links: &[(&str, Option<&str>)]| {
+            let mut data = ManifestData::default();
+            links.iter().for_each(|(name, prefix)| {
+                data.links
+                    .insert(name.to_string(), LinkMeta::new(name, *prefix));
+            });
+            std::fs::write(
+                dir.join(binname).with_extension("toml"),
+                toml::to_string_pretty(&data).expect("Serialize manifest"),
+            )
+            .expect("Write manifest");
+        };
+        write_manifest("wslcmd.exe", &[("wfind", Some("w")), ("which", None)]);
+        write_manifest("wslcmd-old.exe", &[("which", Some("w"))]);
+
+        // found with its own prefix, ignoring detached process prefix
+        let prefix_of = |linkname: &str, binname: Option<&str>| {
+            WslCmdManifest::find_link_meta(&dir, linkname, binname)
+                .map(|meta| meta.get_prefix().clone())
+        };
+        assert_eq!(
+            prefix_of(".wfind", Some("wslcmd.exe")),
+            Some(Some("w".to_string()))
+        );
+        assert_eq!(prefix_of("which", Some("wslcmd.exe")), Some(None)); // manifest of the binary only
+        assert_eq!(
+            prefix_of("which", Some("wslcmd-old.exe")),
+            Some(Some("w".to_string()))
+        );
+        assert_eq!(prefix_of("wfind", Some("wslcmd-old.exe")), None);
+        assert_eq!(prefix_of("wget", Some("wslcmd.exe")), None);
+
+        // all manifests are searched if the binary is unknown
+        assert_eq!(prefix_of("wfind", None), Some(Some("w".to_string())));
+        assert_eq!(prefix_of("wget", None), None);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -17,25 +17,27 @@ mod management_mode;
 
 /// Core routines of WslCmd
 mod libwslcmd;
+use libwslcmd::LinkMeta;
 use libwslcmd::WCPath;
 use libwslcmd::WCStr;
 
 /// Branch routine (Management/Execution mode),
-/// by checking if the binary is executed directly or through symlink
+/// by checking if the binary is executed directly or through link
 fn main() {
     // debug msg
     __wslcmd_dbg!("* Executed in debug mode! Debug msgs will be printed. *");
 
-    // init
+    // init: metadata of the link is found once, for both of mode check and execution
     let args: Vec<String> = std::env::args().collect();
+    let meta = execution_mode::find_link_meta(&args, None);
 
     // call either mode, then get exitcode
-    let exit_code = match is_exemode(&args) {
+    let exit_code = match is_exemode(&args, meta.as_ref()) {
         // comparison succeeded
         Some(ret) => {
             match ret {
-                // if executed through command symlink
-                true => execution_mode::execution_mode(&args),
+                // if executed through command link (symlink, hardlink, copy)
+                true => execution_mode::execution_mode(&args, meta.as_ref()),
 
                 // if executed directly
                 // (including symlinks of which basename == 'orig bin basename')
@@ -56,21 +58,25 @@ fn main() {
 }
 
 /// Check if execution mode,
-/// by (orig_binname != cmdline_binname)
-fn is_exemode<T: WCStr>(cmd_args: &[T]) -> Option<bool> {
+/// by (orig_binname != cmdline_binname),
+/// or by checking if the binary is a hardlink/copy recorded in the manifest
+fn is_exemode<T: WCStr>(cmd_args: &[T], meta: Option<&LinkMeta>) -> Option<bool> {
+    // current exe fullpath (link or bin)
+    let exepath = std::env::current_exe().ok()?; // return None if failed
+
     Some(
         // comparison
         {
             // current exe basename
-            std::env::current_exe() // Result<PathBuf> link_or_bin_fullpath
-                .ok()? // return None if failed
+            exepath
                 .wcpath_canonicalize()? // resolve all links, return None if failed
                 .wcpath_basename() // slice basename
         } != {
             // command-line basename
-            cmd_args
-                .get(0)? // fullpath, return None if failed
+            cmd_args.first()? // fullpath, return None if failed
                 .wcpath_basename() // slice basename
-        },
+        }
+        // hardlinks and copies cannot be followed to the orig bin
+        || meta.is_some_and(|meta| meta.get_kind().is_binary_copy()),
     )
 }
@@ -1,219 +1,1642 @@
-use super::libwslcmd::WCPath;
-use super::libwslcmd::WslCmdList;
+use super::libwslcmd::{strip_prefix, WCPath, WCStr, CMDNAME_DELIM};
+use super::libwslcmd::{wsl_path_executables, wsl_whatis, CmdShadows, WinCmdPath};
+use super::libwslcmd::{CmdSelector, DetachedMode, JournalEntry, LinkIssue, LinkKind};
+use super::libwslcmd::{LinkHealth, LinkInfo, LinkMeta, LinkOptions, LinkSet, WslCmdList};
+use std::collections::HashMap;
+use std::path::Path;
 
 /// Manage (add/del/list) linked WSL commands
 pub fn management_mode(args: &[String]) -> Result<(), i32> {
     crate::__wslcmd_dbg!("Management mode - cmdline args", &args); // debug
 
     let binname = args
-        .get(0)
+        .first()
         .and_then(WCPath::wcpath_basename)
         .unwrap_or_default();
 
-    let mut wslcmd_list =
-        std::env::current_exe().map_or_else(|_| Err(1), |pb| WslCmdList::new(&pb).ok_or(1))?;
+    // run a command through the binary (used by shim scripts),
+    // before loading the list to keep it fast
+    if let (Some("run"), Some(run_args)) = (
+        args.get(1).map(String::as_str),
+        args.get(2..).filter(|a| !a.is_empty()),
+    ) {
+        // link dir to find namespace prefixes, if given before command name
+        let (run_args, linkdir) = match run_args {
+            [opt, dir, run_args @ ..] if opt == "--dir" => (run_args, Some(Path::new(dir))),
+            _ => (run_args, None),
+        };
+        let meta = super::execution_mode::find_link_meta(run_args, linkdir);
+        return super::execution_mode::execution_mode(run_args, meta.as_ref());
+    }
+
+    // split remaining args into options and operands
+    let (opts, operands) = match split_opts(args.get(2..).unwrap_or_default()) {
+        Some(t) => t,
+        None => {
+            print_help(binname);
+            return Err(-1); // return err
+        }
+    };
+
+    // load list of the link dir (Default: current exe dir)
+    let mut wslcmd_list = std::env::current_exe()
+        .ok()
+        .and_then(|pb| match opts.get("--dir") {
+            Some(dir) => WslCmdList::with_dir(&pb, dir),
+            None => WslCmdList::new(&pb),
+        })
+        .ok_or_else(|| {
+            cprintln!(
+                Color::Red,
+                " * Failed to load WslCmd list (Check if the link dir exists, and the manifest file is valid)"
+            );
+            1 // return err
+        })?;
     crate::__wslcmd_dbg!("Management mode - WslCmdList", &wslcmd_list); // debug
 
     // branch based on first arg
     match (
         args.get(1).filter(|s| !s.is_empty()).map(String::as_str),
-        args.get(2).filter(|s| !s.is_empty()),
+        operands.first().is_some_and(|s| !s.is_empty()),
     ) {
-        // ops with more than 2 args
-        (Some(op), Some(_)) => {
-            // link
-            if ["add", "new"].iter().any(|s| s.starts_with(op)) {
-                match args[2..]
-                    // do all jobs for each arg
-                    .iter()
-                    .map(|s_cmd| {
-                        wslcmd_list
-                            .link_wslcmd(s_cmd)
-                            // if s_cmd is error
-                            .map_err(|e| {
-                                cprintln!(
-                                    Color::Red,
-                                    " * Failed to link command '{}': {}",
-                                    s_cmd,
-                                    e
-                                );
-
-                                e // bypass Err
-                            })
-                            .is_ok()
-                    })
-                    // check if there is failed job
-                    // * calling 'all' directly without collecting
-                    //   leads to immediate stop right after fail
-                    .collect::<Vec<bool>>()
-                    .iter()
-                    .all(|is_ok| *is_ok)
-                {
-                    true => {
-                        cprintln!(Color::Green, " - Linked command(s) successfully");
+        // link
+        (Some(op), true) if ["add", "new"].iter().any(|s| s.starts_with(op)) => {
+            link_cmds(&mut wslcmd_list, &opts, &operands)
+        }
+        // unlink (names, patterns or tags)
+        (Some(op), has_operands)
+            if (has_operands || opts.contains_key("--tag"))
+                && ["del", "rm"].iter().any(|s| s.starts_with(op)) =>
+        {
+            select_cmds(&mut wslcmd_list, &opts, &operands)
+                .and_then(|linknames| unlink_cmds(&mut wslcmd_list, &opts, &linknames))
+        }
+        // list (all, or only selected with names, patterns or tags)
+        (Some(op), _) if ["list", "ls"].iter().any(|s| s.starts_with(op)) => {
+            let is_all = operands.is_empty() && !opts.contains_key("--tag");
+            select_cmds(&mut wslcmd_list, &opts, &operands).and_then(|linknames| {
+                match (
+                    opts.contains_key("--json"),
+                    opts.contains_key("--long"),
+                    opts.contains_key("--one-per-line"),
+                ) {
+                    (true, _, _) => list_cmds_json(&mut wslcmd_list, &linknames, is_all),
+                    (_, true, _) => list_cmds_long(&mut wslcmd_list, &linknames, is_all),
+                    (_, _, true) => list_cmds_plain(&mut wslcmd_list, &linknames),
+                    _ => list_cmds(&mut wslcmd_list, &linknames),
+                }
+                .and_then(|_| match opts.contains_key("--check") {
+                    true => check_shadows(&mut wslcmd_list, &linknames),
+                    false => Ok(()),
+                })
+            })
+        }
+        // convert links to another detached mode
+        (Some("convert"), has_operands) if has_operands || opts.contains_key("--tag") => {
+            select_cmds(&mut wslcmd_list, &opts, &operands)
+                .and_then(|linknames| convert_cmds(&mut wslcmd_list, &opts, &linknames))
+        }
+        // add or remove a tag of links
+        (Some(op), true)
+            if ["tag", "untag"].contains(&op)
+                && (operands.len() >= 2 || opts.contains_key("--tag")) =>
+        {
+            select_cmds(&mut wslcmd_list, &opts, &operands[1..]).and_then(|linknames| {
+                tag_cmds(&mut wslcmd_list, operands[0], &linknames, op == "tag")
+            })
+        }
+        // rename or copy a link
+        (Some("rename"), true) | (Some("mv"), true) if operands.len() == 2 => {
+            duplicate_cmd(&mut wslcmd_list, operands[0], operands[1], true)
+        }
+        (Some("copy"), true) | (Some("cp"), true) if operands.len() == 2 => {
+            duplicate_cmd(&mut wslcmd_list, operands[0], operands[1], false)
+        }
+        // search commands on WSL PATH, with descriptions
+        (Some("search"), true) if operands.len() == 1 => {
+            search_cmds(&mut wslcmd_list, &opts, operands[0])
+        }
+        // discover and link commands on WSL PATH
+        (Some("discover"), _) => discover_cmds(&mut wslcmd_list, &opts, &operands),
+        // export and import links
+        (Some("export"), false) => {
+            println!("{}", wslcmd_list.export_link_set().to_json());
+            Ok(())
+        }
+        (Some("import"), true) if operands.len() == 1 => {
+            import_cmds(&mut wslcmd_list, &opts, operands[0])
+        }
+        // retarget links of another binary (renamed or moved one)
+        (Some("retarget"), false) if opts.contains_key("--from") => {
+            retarget_cmds(&mut wslcmd_list, opts["--from"])
+        }
+        // find and fix problematic links
+        (Some("doctor"), false) => doctor_cmds(&mut wslcmd_list, &opts),
+        // install the binary and links to another dir, or uninstall them
+        (Some("install"), true) if operands.len() == 1 => {
+            install_cmds(&mut wslcmd_list, operands[0])
+        }
+        (Some("uninstall"), false) => uninstall_cmds(&mut wslcmd_list, &opts),
+        // list or undo operations recorded in the journal
+        (Some("history"), false) => history_cmds(&mut wslcmd_list),
+        (Some("undo"), _) if operands.len() <= 1 => {
+            match operands
+                .first()
+                .map_or(Some(1), |s| s.parse::<usize>().ok())
+            {
+                Some(count) if count > 0 => undo_cmds(&mut wslcmd_list, &opts, count),
+                _ => {
+                    print_help(binname);
+                    Err(-1) // return err
+                }
+            }
+        }
 
-                        Ok(()) // return ok
-                    }
-                    false => {
-                        cprintln!(Color::Red, " * Failed to link some commands while working!");
+        // default
+        _ => {
+            print_help(binname);
+            Err(-1) // return err
+        }
+    }
+}
 
-                        Err(-1) // return err
-                    }
+// link all given commands
+fn link_cmds(
+    wslcmd_list: &mut WslCmdList,
+    opts: &HashMap<&str, &str>,
+    cmdnames: &[&String],
+) -> Result<(), i32> {
+    // kind of link files (default: symlink)
+    let kind = match (opts.get("--mode"), opts.get("--shim")) {
+        (Some(s), None) => {
+            LinkKind::from_name(s).ok_or("Invalid link mode (Available: symlink, hardlink, copy)")
+        }
+        (None, Some(s)) => {
+            LinkKind::from_shim_name(s).ok_or("Invalid shim type (Available: cmd, ps1, both)")
+        }
+        (None, None) => Ok(LinkKind::default()),
+        (Some(_), Some(_)) => Err("'--mode' and '--shim' cannot be used together"),
+    }
+    .map_err(|msg| {
+        cprintln!(Color::Red, " * {}", msg);
+        -1 // return err
+    })?;
+
+    // which of the link and the detached link are created (default: both)
+    let detached = detached_mode(opts)
+        .map(Option::unwrap_or_default)
+        .and_then(|detached| match (detached, kind.shim_extensions()) {
+            (DetachedMode::Both, _) | (_, []) => Ok(detached),
+            (_, _) => Err("Shim scripts have no detached link"),
+        })
+        .map_err(|msg| {
+            cprintln!(Color::Red, " * {}", msg);
+            -1 // return err
+        })?;
+
+    // set options: namespace prefix, description, link kind, detached mode,
+    // check that WSL commands exist unless '--no-verify' is given,
+    // and back up existing files with the same name if '--force' is given
+    let link_opts = LinkOptions::new()
+        .prefix(opts.get("--prefix").copied())
+        .description(opts.get("--desc").copied())
+        .kind(kind)
+        .detached(detached)
+        .verify(!opts.contains_key("--no-verify"))
+        .force(opts.contains_key("--force"))
+        .tags(
+            &opts
+                .get("--tag")
+                .map_or(vec![], |s| LinkMeta::split_tags(s)),
+        );
+
+    // all-or-nothing, unless '--keep-going' is given
+    if !opts.contains_key("--keep-going") {
+        return wslcmd_list
+            .link_wslcmd_batch(cmdnames, &link_opts)
+            .map(|_| {
+                cprintln!(Color::Green, " - Linked command(s) successfully");
+                print_backups(wslcmd_list, cmdnames, opts.get("--prefix").copied());
+                warn_shadows(wslcmd_list, cmdnames, opts.get("--prefix").copied());
+            })
+            .map_err(|e| {
+                cprintln!(Color::Red, " * Failed to link command {}", e);
+                cprintln!(Color::Red, " * No command was linked");
+                -1 // return err
+            });
+    }
+
+    let results = cmdnames
+        // do all jobs for each arg
+        .iter()
+        .map(|s_cmd| {
+            wslcmd_list
+                .link_wslcmd_with(s_cmd, &link_opts)
+                // if s_cmd is error
+                .map_err(|e| {
+                    cprintln!(Color::Red, " * Failed to link command '{}': {}", s_cmd, e);
+
+                    e // bypass Err
+                })
+                .is_ok()
+        })
+        // * calling 'all' directly without collecting
+        //   leads to immediate stop right after fail
+        .collect::<Vec<bool>>();
+
+    // print backups and warn about shadowing of linked ones
+    let cmdnames_linked = cmdnames
+        .iter()
+        .zip(&results)
+        .filter(|(_, is_ok)| **is_ok)
+        .map(|(s_cmd, _)| *s_cmd)
+        .collect::<Vec<&String>>();
+    print_backups(wslcmd_list, &cmdnames_linked, opts.get("--prefix").copied());
+    warn_shadows(wslcmd_list, &cmdnames_linked, opts.get("--prefix").copied());
+
+    // check if there is failed job
+    match results.iter().all(|is_ok| *is_ok) {
+        true => {
+            cprintln!(Color::Green, " - Linked command(s) successfully");
+
+            Ok(()) // return ok
+        }
+        false => {
+            cprintln!(Color::Red, " * Failed to link some commands while working!");
+
+            Err(-1) // return err
+        }
+    }
+}
+
+// select link names with names or patterns (all links if none given)
+//   - names (with namespace prefix if given) are selected as they are, even if not linked
+//   - glob patterns (or regex patterns with '--regex') are matched against linked commands
+fn select_cmds(
+    wslcmd_list: &mut WslCmdList,
+    opts: &HashMap<&str, &str>,
+    operands: &[&String],
+) -> Result<Vec<String>, i32> {
+    // only links with any of the tags, if given
+    let linknames = select_cmds_by_selectors(wslcmd_list, opts, operands)?;
+    let tags = match opts.get("--tag") {
+        Some(s) => LinkMeta::split_tags(s),
+        None => return Ok(linknames),
+    };
+    let tagged = tags
+        .iter()
+        .map(|tag| wslcmd_list.select_wslcmds(&CmdSelector::Tag(tag.clone())))
+        .collect::<std::io::Result<Vec<Vec<String>>>>()
+        .map(|v| v.concat())
+        .map_err(|e| {
+            cprintln!(Color::Red, " * {}", e);
+            -1 // return err
+        })?;
+    let linknames: Vec<String> = linknames
+        .into_iter()
+        .filter(|s| tagged.contains(s))
+        .collect();
+    if linknames.is_empty() {
+        cprintln!(
+            Color::Yellow,
+            " - No linked command has tag '{}'",
+            tags.join(",")
+        );
+    }
+
+    Ok(linknames)
+}
+
+// select link names with names or patterns (all links if none given)
+fn select_cmds_by_selectors(
+    wslcmd_list: &mut WslCmdList,
+    opts: &HashMap<&str, &str>,
+    operands: &[&String],
+) -> Result<Vec<String>, i32> {
+    let prefix = opts.get("--prefix").copied().unwrap_or_default();
+    let is_regex = opts.contains_key("--regex");
+
+    // no selector: all links
+    if operands.is_empty() {
+        let mut linknames: Vec<String> = wslcmd_list.get_cmdlist().iter().cloned().collect();
+        linknames.sort();
+        return Ok(linknames);
+    }
+
+    operands
+        .iter()
+        .map(|s| {
+            // namespace prefix is prepended to names and globs, and regex must match after it
+            let selector = match CmdSelector::parse(s, is_regex) {
+                CmdSelector::Regex(pattern) => CmdSelector::Regex(pattern),
+                _ => CmdSelector::parse(&format!("{}{}", prefix, s), false),
+            };
+            match &selector {
+                CmdSelector::Name(name) => Ok(vec![name.clone()]),
+                _ => wslcmd_list.select_wslcmds(&selector),
+            }
+            .map(|linknames| {
+                linknames
+                    .into_iter()
+                    .filter(|linkname| linkname.starts_with(prefix))
+                    .collect::<Vec<String>>()
+            })
+            .inspect(|linknames| {
+                if linknames.is_empty() {
+                    cprintln!(Color::Yellow, " - No linked command matches '{}'", s);
                 }
+            })
+            .map_err(|e| {
+                cprintln!(Color::Red, " * {}", e);
+                -1 // return err
+            })
+        })
+        .collect::<Result<Vec<Vec<String>>, i32>>()
+        // flatten, removing duplicates
+        .map(|v| {
+            v.into_iter().flatten().fold(vec![], |mut linknames, s| {
+                if !linknames.contains(&s) {
+                    linknames.push(s);
+                }
+                linknames
+            })
+        })
+}
+
+// unlink all given links, after confirmation if more than one
+fn unlink_cmds(
+    wslcmd_list: &mut WslCmdList,
+    opts: &HashMap<&str, &str>,
+    linknames: &[String],
+) -> Result<(), i32> {
+    // preview and confirm, unless '--yes' is given
+    match linknames.len() {
+        0 => {
+            cprintln!(Color::Yellow, " - No command to unlink");
+            return Ok(());
+        }
+        1 => (),
+        n if !opts.contains_key("--yes") => {
+            cprintln!(Color::Yellow, " - {} command(s) to unlink:", n);
+            println!("{}", linknames.join("\t"));
+            if !confirm("Unlink these commands?") {
+                cprintln!(Color::Yellow, " - Canceled");
+                return Err(-1); // return err
             }
-            // unlink
-            else if ["del", "rm"].iter().any(|s| s.starts_with(op)) {
-                match args[2..]
-                    // do all jobs for each arg
-                    .iter()
-                    .map(|s_cmd| {
-                        wslcmd_list
-                            .unlink_wslcmd(s_cmd)
-                            // if s_cmd is error
-                            .map_err(|e| {
-                                cprintln!(
-                                    Color::Red,
-                                    " * Failed to unlink command '{}': {}",
-                                    s_cmd,
-                                    e
-                                );
-
-                                e // bypass Err
-                            })
-                            .is_ok()
-                    })
-                    // check if there is failed job
-                    // * calling 'all' directly without collecting
-                    //   leads to immediate stop right after fail
-                    .collect::<Vec<bool>>()
-                    .iter()
-                    .all(|is_ok| *is_ok)
-                {
-                    true => {
-                        cprintln!(Color::Green, " - Unlinked command(s) successfully");
+        }
+        _ => (),
+    }
 
-                        Ok(()) // return ok
-                    }
-                    false => {
-                        cprintln!(
-                            Color::Red,
-                            " * Failed to unlink some commands while working!"
-                        );
+    // all-or-nothing, unless '--keep-going' is given
+    if !opts.contains_key("--keep-going") {
+        return wslcmd_list
+            .unlink_wslcmd_batch(linknames)
+            .map(|_| cprintln!(Color::Green, " - Unlinked command(s) successfully"))
+            .map_err(|e| {
+                cprintln!(Color::Red, " * Failed to unlink command {}", e);
+                cprintln!(Color::Red, " * No command was unlinked");
+                -1 // return err
+            });
+    }
+
+    match linknames
+        // do all jobs for each arg
+        .iter()
+        .map(|s_cmd| {
+            wslcmd_list
+                .unlink_wslcmd(s_cmd)
+                // if s_cmd is error
+                .map_err(|e| {
+                    cprintln!(Color::Red, " * Failed to unlink command '{}': {}", s_cmd, e);
+
+                    e // bypass Err
+                })
+                .is_ok()
+        })
+        // check if there is failed job
+        // * calling 'all' directly without collecting
+        //   leads to immediate stop right after fail
+        .collect::<Vec<bool>>()
+        .iter()
+        .all(|is_ok| *is_ok)
+    {
+        true => {
+            cprintln!(Color::Green, " - Unlinked command(s) successfully");
+
+            Ok(()) // return ok
+        }
+        false => {
+            cprintln!(
+                Color::Red,
+                " * Failed to unlink some commands while working!"
+            );
+
+            Err(-1) // return err
+        }
+    }
+}
+
+// convert linked commands to the detached mode given by options
+fn convert_cmds(
+    wslcmd_list: &mut WslCmdList,
+    opts: &HashMap<&str, &str>,
+    linknames: &[String],
+) -> Result<(), i32> {
+    let detached = match detached_mode(opts) {
+        Ok(Some(detached)) => detached,
+        Ok(None) => {
+            cprintln!(
+                Color::Red,
+                " * Detached mode is not given (Available: --with-detached, --no-detached, --detached-only)"
+            );
+            return Err(-1); // return err
+        }
+        Err(msg) => {
+            cprintln!(Color::Red, " * {}", msg);
+            return Err(-1); // return err
+        }
+    };
 
-                        Err(-1) // return err
+    // convert each link, skipping failed ones
+    let results = linknames
+        .iter()
+        .map(|linkname| {
+            wslcmd_list
+                .set_detached_mode(linkname, detached)
+                .map_err(|e| {
+                    cprintln!(
+                        Color::Red,
+                        " * Failed to convert command '{}': {}",
+                        linkname,
+                        e
+                    );
+                })
+                .is_ok()
+        })
+        .collect::<Vec<bool>>();
+
+    match results.iter().all(|is_ok| *is_ok) {
+        true => {
+            cprintln!(
+                Color::Green,
+                " - Converted command(s) to '{}' successfully",
+                detached.name()
+            );
+
+            Ok(()) // return ok
+        }
+        false => Err(-1), // return err
+    }
+}
+
+// get detached mode from options: '--with-detached', '--no-detached' or '--detached-only'
+// returns Ok(None) if not given, Err if more than one is given
+fn detached_mode(opts: &HashMap<&str, &str>) -> Result<Option<DetachedMode>, &'static str> {
+    let modes: Vec<DetachedMode> = [
+        ("--with-detached", DetachedMode::Both),
+        ("--no-detached", DetachedMode::NoDetached),
+        ("--detached-only", DetachedMode::DetachedOnly),
+    ]
+    .iter()
+    .filter(|(opt, _)| opts.contains_key(opt))
+    .map(|(_, detached)| *detached)
+    .collect();
+
+    match modes.as_slice() {
+        [] => Ok(None),
+        [detached] => Ok(Some(*detached)),
+        _ => {
+            Err("Only one of '--with-detached', '--no-detached' and '--detached-only' can be used")
+        }
+    }
+}
+
+// rename or copy a linked command
+fn duplicate_cmd(
+    wslcmd_list: &mut WslCmdList,
+    cmdname: &String,
+    cmdname_new: &String,
+    is_rename: bool,
+) -> Result<(), i32> {
+    let (op_name, op_done, result) = match is_rename {
+        true => (
+            "rename",
+            "Renamed",
+            wslcmd_list.rename_wslcmd(cmdname, cmdname_new),
+        ),
+        false => (
+            "copy",
+            "Copied",
+            wslcmd_list.copy_wslcmd(cmdname, cmdname_new),
+        ),
+    };
+
+    match result {
+        Ok(_) => {
+            cprintln!(
+                Color::Green,
+                " - {} command '{}' to '{}' successfully",
+                op_done,
+                cmdname,
+                cmdname_new
+            );
+
+            Ok(()) // return ok
+        }
+        Err(e) => {
+            cprintln!(
+                Color::Red,
+                " * Failed to {} command '{}' to '{}': {}",
+                op_name,
+                cmdname,
+                cmdname_new,
+                e
+            );
+
+            Err(-1) // return err
+        }
+    }
+}
+
+// print all linked commands
+fn list_cmds(wslcmd_list: &mut WslCmdList, linknames: &[String]) -> Result<(), i32> {
+    use std::io::Write;
+    use termcolor::{BufferWriter, Color, ColorChoice, ColorSpec, WriteColor};
+
+    // colored output writer
+    let buf_writer = BufferWriter::stdout(ColorChoice::Auto);
+    let mut buf = buf_writer.buffer();
+
+    // get all sorted cmdlist, grouped by namespace prefix
+    let cmdlist_groups = {
+        let mut cmdlist_sorted: Vec<String> = wslcmd_list
+            .get_cmdlist()
+            .iter()
+            // only selected ones
+            .filter(|s| linknames.contains(s))
+            .cloned()
+            .collect();
+        cmdlist_sorted.sort();
+        cmdlist_sorted.into_iter().fold(
+            std::collections::BTreeMap::<Option<String>, Vec<String>>::new(),
+            |mut groups, s| {
+                groups
+                    .entry(prefix_of(wslcmd_list, &s))
+                    .or_default()
+                    .push(s);
+                groups
+            },
+        )
+    };
+
+    // build and print wslcmd list string
+    match cmdlist_groups.is_empty() {
+        // if no entry
+        true => {
+            cprint!(Color::Yellow, "(No linked WSL command)");
+            Ok(())
+        }
+        // if WSL commands exist - commands without prefix first
+        false => cmdlist_groups
+            .iter()
+            .enumerate()
+            .try_for_each(|(i, (prefix, v))| {
+                // print group header for namespaced commands
+                match prefix {
+                    Some(prefix) => match i {
+                        0 => Ok(()),             // first line
+                        _ => writeln!(&mut buf), // newline between groups
                     }
+                    .and_then(|_| buf.set_color(ColorSpec::new().set_fg(Some(Color::Yellow))))
+                    .and_then(|_| write!(&mut buf, "[{}*]", prefix))
+                    .and_then(|_| buf.set_color(ColorSpec::new().set_reset(true)))
+                    .and_then(|_| write!(&mut buf, "\t")),
+                    None => Ok(()),
                 }
+                .and_then(|_| write_cmdnames(&mut buf, &v.iter().collect::<Vec<&String>>()))
+            }),
+    }
+    // end with newline
+    .and_then(|_| writeln!(&mut buf))
+    // reset color at the end
+    .and_then(|_| buf.set_color(ColorSpec::new().set_reset(true)))
+    // print built buf to terminal
+    .and_then(|_| buf_writer.print(&buf))
+    .map(|_| {
+        buf.clear();
+    })
+    .map_err(|_| -1)
+}
+
+// print selected linked commands in a table, one per line: decoded fields, health and metadata
+//   partial or broken links are also printed, if all links are selected
+fn list_cmds_long(
+    wslcmd_list: &mut WslCmdList,
+    linknames: &[String],
+    is_all: bool,
+) -> Result<(), i32> {
+    let infos = select_link_infos(wslcmd_list, linknames, is_all);
+    if infos.is_empty() {
+        cprintln!(Color::Yellow, "(No linked WSL command)");
+        return Ok(());
+    }
+
+    // build rows with header, then align columns
+    let field = |s: &Option<String>| s.clone().unwrap_or_else(|| "-".to_string());
+    let rows: Vec<Vec<String>> = std::iter::once(
+        [
+            "NAME",
+            "COMMAND",
+            "USER",
+            "DIST",
+            "KIND",
+            "DETACHED",
+            "HEALTH",
+            "TAGS",
+            "DESCRIPTION",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect(),
+    )
+    .chain(infos.iter().map(|info| {
+        vec![
+            info.get_name().clone(),
+            info.get_command().clone(),
+            field(info.get_user()),
+            field(info.get_dist()),
+            match info.get_detached().is_both() {
+                true => info.get_kind().name().to_string(),
+                false => format!("{},{}", info.get_kind().name(), info.get_detached().name()),
+            },
+            match info.get_has_detached() {
+                true => "yes".to_string(),
+                false => "no".to_string(),
+            },
+            info.get_health().name().to_string(),
+            match info.get_tags().is_empty() {
+                true => "-".to_string(),
+                false => info.get_tags().join(","),
+            },
+            info.get_description().clone().unwrap_or_default(),
+        ]
+    }))
+    .collect();
+    let widths: Vec<usize> = (0..rows[0].len())
+        .map(|i| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    rows.iter().enumerate().for_each(|(i, row)| {
+        let line = row
+            .iter()
+            .zip(widths.iter())
+            .map(|(s, width)| format!("{:<1$}", s, width))
+            .collect::<Vec<String>>()
+            .join("  ");
+        match i {
+            0 => cprintln!(Color::Yellow, "{}", line.trim_end()),
+            _ if *infos[i - 1].get_health() != LinkHealth::Ok => {
+                cprintln!(Color::Red, "{}", line.trim_end())
             }
-            // default
-            else {
-                print_help(binname);
-                Err(-1) // return err
+            _ => println!("{}", line.trim_end()),
+        }
+    });
+
+    Ok(())
+}
+
+// print selected linked commands as JSON: {"links": [{"name", "command", "user", ...}, ...]}
+fn list_cmds_json(
+    wslcmd_list: &mut WslCmdList,
+    linknames: &[String],
+    is_all: bool,
+) -> Result<(), i32> {
+    let infos = select_link_infos(wslcmd_list, linknames, is_all);
+
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&serde_json::json!({ "links": infos })).unwrap_or_default()
+    );
+
+    Ok(())
+}
+
+// print selected linked commands one per line, without color
+fn list_cmds_plain(wslcmd_list: &mut WslCmdList, linknames: &[String]) -> Result<(), i32> {
+    let mut linknames: Vec<&String> = {
+        let cmdlist = wslcmd_list.get_cmdlist();
+        linknames.iter().filter(|s| cmdlist.contains(*s)).collect()
+    };
+    linknames.sort();
+    linknames.iter().for_each(|s| println!("{}", s));
+
+    Ok(())
+}
+
+// get details of selected links (and partial or broken links, if all links are selected)
+fn select_link_infos(
+    wslcmd_list: &mut WslCmdList,
+    linknames: &[String],
+    is_all: bool,
+) -> Vec<LinkInfo> {
+    wslcmd_list
+        .list_links()
+        .into_iter()
+        .filter(|info| is_all || linknames.contains(info.get_name()))
+        .collect()
+}
+
+// add or remove tags (delimited by ',') of all given links
+fn tag_cmds(
+    wslcmd_list: &mut WslCmdList,
+    s_tags: &str,
+    linknames: &[String],
+    is_add: bool,
+) -> Result<(), i32> {
+    let tags = LinkMeta::split_tags(s_tags);
+    let (op_name, op_done) = match is_add {
+        true => ("tag", "Tagged"),
+        false => ("untag", "Untagged"),
+    };
+    let no_tags: &[&str] = &[];
+
+    let results = linknames
+        .iter()
+        .map(|linkname| {
+            match is_add {
+                true => wslcmd_list.update_link_tags(linkname, &tags, no_tags),
+                false => wslcmd_list.update_link_tags(linkname, no_tags, &tags),
             }
+            .map_err(|e| {
+                cprintln!(
+                    Color::Red,
+                    " * Failed to {} command '{}': {}",
+                    op_name,
+                    linkname,
+                    e
+                );
+            })
+            .is_ok()
+        })
+        .collect::<Vec<bool>>();
+
+    match !linknames.is_empty() && results.iter().all(|is_ok| *is_ok) {
+        true => {
+            cprintln!(
+                Color::Green,
+                " - {} command(s) with '{}' successfully",
+                op_done,
+                tags.join(",")
+            );
+
+            Ok(()) // return ok
         }
+        false => Err(-1), // return err
+    }
+}
 
-        // ops with 1 arg
-        (Some(op), None) => {
-            // link
-            if ["list", "ls"].iter().any(|s| s.starts_with(op)) {
-                use std::io::Write;
-                use termcolor::{BufferWriter, Color, ColorChoice, ColorSpec, WriteColor};
-
-                // colored output writer
-                let buf_writer = BufferWriter::stdout(ColorChoice::Auto);
-                let mut buf = buf_writer.buffer();
-
-                // build and print wslcmd list string
-                {
-                    // get iter of all sorted cmdlist
-                    Some(
-                        wslcmd_list
-                            .get_cmdlist()
-                            .iter()
-                            .collect::<std::collections::BinaryHeap<_>>()
-                            .into_sorted_vec(),
-                    )
-                }
-                .filter(|v| v.len() > 0)
-                .map_or_else(
-                    // if no entry
-                    || {
-                        cprint!(Color::Yellow, "(No linked WSL command)");
-                        true
-                    },
-                    // if WSL commands exist
-                    |v| {
-                        // do for all list
-                        v.into_iter().all(|pb| {
-                            {
-                                // get basename of current cmdlist
-                                pb.wcpath_basename().ok_or(())
-                            }
-                            .and_then(|s| {
-                                // if current string contains ws, wrap with '
-                                {
-                                    s.contains(char::is_whitespace)
-                                        .then(|| ("'", "'")) // wrapper front/back
-                                        .or_else(|| Some(("", ""))) // no wrapper
-                                }
-                                // print using 's' and 'wrap_*' data
-                                .map_or(
-                                    Ok(()),
-                                    |(wrap_front, wrap_back)| {
-                                        // write list to buf
-                                        write!(&mut buf, "{}", wrap_front)
-                                            .and_then(|_| {
-                                                buf.set_color(
-                                                    ColorSpec::new().set_fg(Some(Color::Green)),
-                                                )
-                                            })
-                                            .and_then(|_| write!(&mut buf, "{}", s))
-                                            .and_then(|_| {
-                                                buf.set_color(ColorSpec::new().set_reset(true))
-                                            })
-                                            .and_then(|_| write!(&mut buf, "{}", wrap_back))
-                                            .and_then(|_| write!(&mut buf, "\t"))
-                                            .map_err(|_| ())
-                                    },
-                                )
-                            })
-                            .is_ok()
-                        })
-                    },
-                )
-                .then(|| ())
-                .ok_or(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    "Color print error",
-                ))
-                // end with newline
-                .and_then(|_| writeln!(&mut buf))
-                // reset color at the end
-                .and_then(|_| buf.set_color(ColorSpec::new().set_reset(true)))
-                // print built buf to terminal
-                .and_then(|_| buf_writer.print(&buf))
-                .and_then(|_| Ok(buf.clear()))
-                .map_err(|_| -1)
+// check links for other Windows commands with the same name on PATH
+// returns Err if any link is shadowed by others
+fn check_shadows(wslcmd_list: &mut WslCmdList, linknames: &[String]) -> Result<(), i32> {
+    let linked = wslcmd_list.get_cmdlist().clone();
+
+    println!();
+    match warn_shadows(
+        wslcmd_list,
+        &linknames
+            .iter()
+            .filter(|s| linked.contains(*s))
+            .collect::<Vec<&String>>(),
+        None,
+    ) {
+        (0, 0) => {
+            cprintln!(
+                Color::Green,
+                " - No link is shadowed by, or shadowing, other commands on PATH"
+            );
+            Ok(())
+        }
+        (0, _) => Ok(()),
+        (n, _) => {
+            cprintln!(
+                Color::Yellow,
+                " * {} link(s) are not run by their names, as other commands come earlier",
+                n
+            );
+            Err(-1) // return err
+        }
+    }
+}
+
+// print existing files which were moved to backups on linking
+fn print_backups(wslcmd_list: &mut WslCmdList, cmdnames: &[&String], prefix: Option<&str>) {
+    cmdnames
+        .iter()
+        .filter_map(|s_cmd| s_cmd.wcpath_filename())
+        .map(|s_cmd| format!("{}{}", prefix.unwrap_or_default(), s_cmd))
+        .for_each(|linkname| {
+            wslcmd_list
+                .get_link_meta(&linkname)
+                .map(|meta| meta.get_backups().clone())
+                .unwrap_or_default()
+                .iter()
+                .for_each(|s_file| {
+                    cprintln!(
+                        Color::Yellow,
+                        " - Backed up existing file '{}' for '{}' (restored when unlinked)",
+                        s_file,
+                        linkname
+                    );
+                })
+        });
+}
+
+// print warnings about other Windows commands with the same name as links, on PATH and PATHEXT
+// returns count of (shadowed links, shadowing links)
+fn warn_shadows(
+    wslcmd_list: &WslCmdList,
+    cmdnames: &[&String],
+    prefix: Option<&str>,
+) -> (usize, usize) {
+    let win_cmd_path = WinCmdPath::from_env();
+    let linkdir = wslcmd_list.get_linkdir();
+    let shadows: Vec<(String, CmdShadows)> = cmdnames
+        .iter()
+        .filter_map(|s_cmd| s_cmd.wcpath_filename())
+        .map(|s_cmd| format!("{}{}", prefix.unwrap_or_default(), s_cmd))
+        .map(|linkname| {
+            let shadows = win_cmd_path.find_shadows(&linkname, linkdir);
+            (linkname, shadows)
+        })
+        .filter(|(_, shadows)| !shadows.is_empty())
+        .collect();
+
+    // link dir itself is not on PATH
+    if shadows
+        .iter()
+        .any(|(_, shadows)| !shadows.get_linkdir_on_path())
+    {
+        cprintln!(
+            Color::Yellow,
+            " * Link dir '{}' is not on PATH",
+            linkdir.display()
+        );
+    }
+
+    shadows.iter().for_each(|(linkname, shadows)| {
+        if *shadows.get_builtin() {
+            cprintln!(
+                Color::Yellow,
+                " * '{}' is shadowed by the built-in command of cmd.exe",
+                linkname
+            );
+        }
+        shadows.get_shadowed_by().iter().for_each(|pb| {
+            cprintln!(
+                Color::Yellow,
+                " * '{}' is shadowed by '{}', which comes earlier on PATH",
+                linkname,
+                pb.display()
+            );
+        });
+        shadows.get_shadowing().iter().for_each(|pb| {
+            cprintln!(
+                Color::Yellow,
+                " - '{}' shadows '{}', which comes later on PATH",
+                linkname,
+                pb.display()
+            );
+        });
+    });
+
+    (
+        shadows.iter().filter(|(_, s)| s.is_shadowed()).count(),
+        shadows
+            .iter()
+            .filter(|(_, s)| !s.get_shadowing().is_empty())
+            .count(),
+    )
+}
+
+// find executables on WSL PATH (or on given WSL dirs), then link them after confirmation
+fn discover_cmds(
+    wslcmd_list: &mut WslCmdList,
+    opts: &HashMap<&str, &str>,
+    dirs: &[&String],
+) -> Result<(), i32> {
+    let user = opts.get("--user").copied().filter(|s| !s.is_empty());
+    let dist = opts.get("--dist").copied().filter(|s| !s.is_empty());
+    let prefix = opts.get("--prefix").copied().unwrap_or_default();
+    let patterns = |opt: &str| -> Vec<&str> {
+        opts.get(opt)
+            .map_or(vec![], |s| s.split(',').filter(|s| !s.is_empty()).collect())
+    };
+    let (includes, excludes) = (patterns("--include"), patterns("--exclude"));
+
+    // query executables from WSL
+    let wsl_cmds = wsl_path_executables(user, dist, dirs).ok_or_else(|| {
+        cprintln!(Color::Red, " * Failed to get executables from WSL");
+        -1 // return err
+    })?;
+
+    // classify commands into (to be linked, shadowing windows commands)
+    let win_cmd_path = WinCmdPath::from_env();
+    let linkdir = wslcmd_list.get_linkdir().clone();
+    let linked = wslcmd_list.get_cmdlist().clone();
+    let (cmds_to_link, cmds_shadowing): (Vec<&String>, Vec<&String>) = wsl_cmds
+        .iter()
+        // filter with include/exclude patterns
+        .filter(|s| includes.is_empty() || includes.iter().any(|p| s.wcstr_matches_glob(p)))
+        .filter(|s| !excludes.iter().any(|p| s.wcstr_matches_glob(p)))
+        // skip names which cannot be a cmdname
+        .filter(|s| !s.contains(CMDNAME_DELIM) && !s.starts_with('.'))
+        // skip already linked commands
+        .filter(|s| !linked.contains(&format!("{}{}", prefix, cmdname_for(s, user, dist))))
+        // check shadowing with the name typed on Windows, without user/dist suffix
+        .partition(|s| {
+            let name_bare = format!("{}{}", prefix, s);
+            !WinCmdPath::is_builtin(&name_bare)
+                && win_cmd_path.find(&name_bare, Some(&linkdir)).is_empty()
+        });
+    let (cmds_to_link, cmds_shadowing): (Vec<String>, Vec<String>) = (
+        cmds_to_link
+            .iter()
+            .map(|s| cmdname_for(s, user, dist))
+            .collect(),
+        cmds_shadowing
+            .iter()
+            .map(|s| cmdname_for(s, user, dist))
+            .collect(),
+    );
+
+    // preview
+    if !cmds_shadowing.is_empty() {
+        cprintln!(
+            Color::Yellow,
+            " - Skipping {} command(s) which would shadow Windows commands:",
+            cmds_shadowing.len()
+        );
+        println!("{}", cmds_shadowing.join("\t"));
+    }
+    if cmds_to_link.is_empty() {
+        cprintln!(Color::Yellow, " - No new command to link");
+        return Ok(());
+    }
+    cprintln!(
+        Color::Green,
+        " - {} command(s) to link:",
+        cmds_to_link.len()
+    );
+    println!(
+        "{}",
+        cmds_to_link
+            .iter()
+            .map(|s| format!("{}{}", prefix, s))
+            .collect::<Vec<String>>()
+            .join("\t")
+    );
+
+    // confirm, then link
+    match opts.contains_key("--yes") || confirm("Link these commands?") {
+        true => link_cmds(
+            wslcmd_list,
+            opts,
+            &cmds_to_link.iter().collect::<Vec<&String>>(),
+        ),
+        false => {
+            cprintln!(Color::Yellow, " - Canceled");
+            Err(-1) // return err
+        }
+    }
+}
+
+// search executables on WSL PATH matching the pattern, with descriptions from whatis
+//   pattern: substring of names (case-insensitive), glob if it has glob chars, or regex with '--regex'
+fn search_cmds(
+    wslcmd_list: &mut WslCmdList,
+    opts: &HashMap<&str, &str>,
+    pattern: &str,
+) -> Result<(), i32> {
+    let user = opts.get("--user").copied().filter(|s| !s.is_empty());
+    let dist = opts.get("--dist").copied().filter(|s| !s.is_empty());
+    let is_matched: Box<dyn Fn(&String) -> bool> = match CmdSelector::parse(pattern, false) {
+        _ if opts.contains_key("--regex") => {
+            let re = regex::Regex::new(pattern).map_err(|e| {
+                cprintln!(Color::Red, " * Invalid regex: {}", e);
+                -1 // return err
+            })?;
+            Box::new(move |s| re.is_match(s))
+        }
+        CmdSelector::Glob(_) => Box::new(move |s| s.wcstr_matches_glob(pattern)),
+        _ => {
+            let pattern = pattern.to_lowercase();
+            Box::new(move |s| s.to_lowercase().contains(&pattern))
+        }
+    };
+
+    // query executables and their descriptions from WSL
+    let wsl_cmds: Vec<String> = wsl_path_executables(user, dist, &[] as &[&str])
+        .ok_or_else(|| {
+            cprintln!(Color::Red, " * Failed to get executables from WSL");
+            -1 // return err
+        })?
+        .into_iter()
+        .filter(|s| is_matched(s))
+        .collect();
+    if wsl_cmds.is_empty() {
+        cprintln!(
+            Color::Yellow,
+            " - No command matching '{}' found in WSL",
+            pattern
+        );
+        return Ok(());
+    }
+    let descs = wsl_whatis(user, dist, &wsl_cmds).unwrap_or_default();
+
+    // linked commands, without namespace prefixes (Ex: 'w-jq!!debian' -> 'jq!!debian')
+    let linked: std::collections::HashSet<String> = {
+        let linknames: Vec<String> = wslcmd_list.get_cmdlist().iter().cloned().collect();
+        linknames
+            .iter()
+            .map(|s| strip_prefix(s, prefix_of(wslcmd_list, s).as_deref()))
+            .collect()
+    };
+
+    let mut wsl_cmds = wsl_cmds;
+    wsl_cmds.sort();
+    let width = wsl_cmds
+        .iter()
+        .map(|s| s.chars().count())
+        .max()
+        .unwrap_or(0);
+    wsl_cmds.iter().for_each(|s| {
+        let is_linked = linked.contains(&cmdname_for(s, user, dist));
+        let line = format!(
+            "{:<w$}  {:<8}  {}",
+            s,
+            match is_linked {
+                true => "[linked]",
+                false => "",
+            },
+            descs.get(s).map_or("", String::as_str),
+            w = width
+        );
+        match is_linked {
+            true => cprintln!(Color::Green, "{}", line.trim_end()),
+            false => println!("{}", line.trim_end()),
+        }
+    });
+
+    Ok(())
+}
+
+// import links from a link set file ('-' for stdin)
+fn import_cmds(
+    wslcmd_list: &mut WslCmdList,
+    opts: &HashMap<&str, &str>,
+    path: &str,
+) -> Result<(), i32> {
+    // read and parse link set
+    let link_set = match path {
+        "-" => {
+            let mut s = String::new();
+            std::io::Read::read_to_string(&mut std::io::stdin(), &mut s).map(|_| s)
+        }
+        _ => std::fs::read_to_string(path),
+    }
+    .and_then(|s| LinkSet::from_json(&s))
+    .map_err(|e| {
+        cprintln!(Color::Red, " * Failed to read link set '{}': {}", path, e);
+        -1 // return err
+    })?;
+
+    // plan and preview
+    let plan = wslcmd_list.plan_import(&link_set, opts.contains_key("--sync"));
+    plan.get_conflicts().iter().for_each(|(entry, reason)| {
+        cprintln!(Color::Red, " * Conflict '{}': {}", entry.get_name(), reason);
+    });
+    if !plan.get_unchanged().is_empty() {
+        cprintln!(
+            Color::Green,
+            " - {} command(s) already linked",
+            plan.get_unchanged().len()
+        );
+    }
+    if !plan.get_to_link().is_empty() {
+        cprintln!(
+            Color::Green,
+            " - {} command(s) to link:",
+            plan.get_to_link().len()
+        );
+        println!(
+            "{}",
+            plan.get_to_link()
+                .iter()
+                .map(|entry| entry.get_name().as_str())
+                .collect::<Vec<&str>>()
+                .join("\t")
+        );
+    }
+    if !plan.get_to_unlink().is_empty() {
+        cprintln!(
+            Color::Yellow,
+            " - {} command(s) to unlink (not in the link set):",
+            plan.get_to_unlink().len()
+        );
+        println!("{}", plan.get_to_unlink().join("\t"));
+    }
+
+    // stop here if dry run, or nothing to do
+    match (
+        opts.contains_key("--dry-run"),
+        plan.get_to_link().is_empty() && plan.get_to_unlink().is_empty(),
+    ) {
+        (true, _) => {
+            cprintln!(Color::Yellow, " - Dry run: nothing changed");
+            return plan.get_conflicts().is_empty().then_some(()).ok_or(-1);
+        }
+        (false, true) => {
+            cprintln!(Color::Green, " - Nothing to change");
+            return plan.get_conflicts().is_empty().then_some(()).ok_or(-1);
+        }
+        (false, false) => (),
+    }
+
+    // confirm removal, then apply
+    if !plan.get_to_unlink().is_empty()
+        && !opts.contains_key("--yes")
+        && !confirm("Unlink commands not in the link set?")
+    {
+        cprintln!(Color::Yellow, " - Canceled");
+        return Err(-1); // return err
+    }
+    // all-or-nothing, as one operation to undo
+    wslcmd_list
+        .apply_import(&plan)
+        .map_err(|e| {
+            cprintln!(Color::Red, " * Failed to import command {}", e);
+            cprintln!(Color::Red, " * Nothing was changed");
+            -1 // return err
+        })
+        .and_then(|_| match plan.get_conflicts().is_empty() {
+            true => {
+                cprintln!(Color::Green, " - Imported link set successfully");
+                Ok(()) // return ok
             }
-            // default
-            else {
-                print_help(binname);
+            false => {
+                cprintln!(
+                    Color::Yellow,
+                    " * Imported link set, except conflicting commands"
+                );
                 Err(-1) // return err
             }
+        })
+}
+
+// retarget links of another binary to current binary
+fn retarget_cmds(wslcmd_list: &mut WslCmdList, binname_old: &str) -> Result<(), i32> {
+    let results = wslcmd_list.retarget_from(&binname_old).map_err(|e| {
+        cprintln!(
+            Color::Red,
+            " * Failed to retarget links of '{}': {}",
+            binname_old,
+            e
+        );
+        -1 // return err
+    })?;
+
+    // print result of each link
+    results.iter().for_each(|(linkname, result)| match result {
+        Ok(_) => cprintln!(Color::Green, " - Retargeted command '{}'", linkname),
+        Err(e) => cprintln!(
+            Color::Red,
+            " * Failed to retarget command '{}': {}",
+            linkname,
+            e
+        ),
+    });
+
+    match results.iter().filter(|(_, result)| result.is_err()).count() {
+        _ if results.is_empty() => {
+            cprintln!(Color::Yellow, " - No link of '{}' found", binname_old);
+            Ok(())
+        }
+        0 => Ok(()),
+        n => {
+            cprintln!(Color::Red, " * {} command(s) could not be retargeted", n);
+            Err(-1) // return err
         }
+    }
+}
 
-        // default
-        _ => {
-            print_help(binname);
+// install the binary to the dir, and re-create all links there
+fn install_cmds(wslcmd_list: &mut WslCmdList, dir: &str) -> Result<(), i32> {
+    let (wslcmd_list_new, results) = wslcmd_list.install_to(&dir).map_err(|e| {
+        cprintln!(Color::Red, " * Failed to install to '{}': {}", dir, e);
+        -1 // return err
+    })?;
+    let binpath_new = wslcmd_list_new.get_binpath();
+
+    // print result of each link
+    results.iter().for_each(|(linkname, result)| match result {
+        Ok(_) => cprintln!(Color::Green, " - Linked command '{}'", linkname),
+        Err(e) => cprintln!(
+            Color::Red,
+            " * Failed to link command '{}': {}",
+            linkname,
+            e
+        ),
+    });
+    cprintln!(
+        Color::Green,
+        " - Installed to '{}' successfully",
+        binpath_new.display()
+    );
+
+    // guide to switch PATH, and remove the old installation
+    print_path_guide(wslcmd_list_new.get_linkdir(), true);
+    cprintln!(
+        Color::Yellow,
+        " - Old installation can be removed with '{} uninstall --purge'",
+        wslcmd_list.get_binpath().display()
+    );
+
+    match results.iter().filter(|(_, result)| result.is_err()).count() {
+        0 => Ok(()),
+        n => {
+            cprintln!(Color::Red, " * {} command(s) could not be linked", n);
             Err(-1) // return err
         }
     }
 }
 
+// remove all links, then config files and the binary if '--purge' is given
+fn uninstall_cmds(wslcmd_list: &mut WslCmdList, opts: &HashMap<&str, &str>) -> Result<(), i32> {
+    let purge = opts.contains_key("--purge");
+    let n_links = wslcmd_list.get_cmdlist().len();
+
+    // confirm, unless '--yes' is given
+    cprintln!(
+        Color::Yellow,
+        " - {} command(s) will be unlinked{}",
+        n_links,
+        match purge {
+            true => ", and config files and the binary will be removed",
+            false => "",
+        }
+    );
+    if !opts.contains_key("--yes") && !confirm("Uninstall WslCmd?") {
+        cprintln!(Color::Yellow, " - Canceled");
+        return Err(-1); // return err
+    }
+
+    let removed = wslcmd_list.uninstall(purge).map_err(|e| {
+        cprintln!(Color::Red, " * Failed to uninstall: {}", e);
+        -1 // return err
+    })?;
+    cprintln!(Color::Green, " - Unlinked {} command(s)", n_links);
+    removed
+        .iter()
+        .for_each(|pb| cprintln!(Color::Green, " - Removed '{}'", pb.display()));
+
+    // running binary cannot be removed on Windows
+    if purge {
+        let binpath = wslcmd_list.get_binpath();
+        match std::fs::remove_file(binpath) {
+            Ok(_) => cprintln!(Color::Green, " - Removed '{}'", binpath.display()),
+            Err(_) => cprintln!(
+                Color::Yellow,
+                " - Remove the binary after this exits: del \"{}\"",
+                binpath.display()
+            ),
+        }
+    }
+    print_path_guide(wslcmd_list.get_linkdir(), false);
+
+    Ok(())
+}
+
+// print how to add the dir to (or remove it from) user PATH, if needed
+fn print_path_guide(dir: &Path, is_add: bool) {
+    if WinCmdPath::from_env().contains_dir(dir) == is_add {
+        return;
+    }
+
+    let dir = dir.display().to_string().replace('\'', "''");
+    let (msg, new_path) = match is_add {
+        true => ("Add the link dir to PATH", format!("$p + ';{}'", dir)),
+        false => (
+            "Remove the link dir from PATH",
+            format!(
+                "($p -split ';' | Where-Object {{ $_ -and $_ -ne '{}' }}) -join ';'",
+                dir
+            ),
+        ),
+    };
+    cprintln!(Color::Yellow, " - {} (PowerShell):", msg);
+    println!(
+        "   $p = [Environment]::GetEnvironmentVariable('Path', 'User'); [Environment]::SetEnvironmentVariable('Path', {}, 'User')",
+        new_path
+    );
+}
+
+// print operations recorded in the journal, from the latest
+fn history_cmds(wslcmd_list: &mut WslCmdList) -> Result<(), i32> {
+    let entries = wslcmd_list.get_journal().map_err(|e| {
+        cprintln!(Color::Red, " * Failed to read the journal: {}", e);
+        -1 // return err
+    })?;
+
+    if entries.is_empty() {
+        cprintln!(Color::Yellow, "(No operation recorded)");
+    }
+    entries
+        .iter()
+        .rev()
+        .enumerate()
+        .for_each(|(i, entry)| print_journal_entry(i + 1, entry));
+
+    Ok(())
+}
+
+// undo the latest operations, after preview and confirmation
+fn undo_cmds(
+    wslcmd_list: &mut WslCmdList,
+    opts: &HashMap<&str, &str>,
+    count: usize,
+) -> Result<(), i32> {
+    let entries = wslcmd_list.get_journal().map_err(|e| {
+        cprintln!(Color::Red, " * Failed to read the journal: {}", e);
+        -1 // return err
+    })?;
+    if entries.is_empty() {
+        cprintln!(Color::Yellow, " - No operation to undo");
+        return Ok(());
+    }
+
+    // preview and confirm, unless '--yes' is given
+    cprintln!(Color::Yellow, " - Operation(s) to undo:");
+    entries
+        .iter()
+        .rev()
+        .take(count)
+        .enumerate()
+        .for_each(|(i, entry)| print_journal_entry(i + 1, entry));
+    if !opts.contains_key("--yes") && !confirm("Undo these operations?") {
+        cprintln!(Color::Yellow, " - Canceled");
+        return Err(-1); // return err
+    }
+
+    wslcmd_list
+        .undo_operations(count)
+        .map(|undone| {
+            undone.iter().for_each(|entry| {
+                cprintln!(
+                    Color::Green,
+                    " - Undone '{}' at {}",
+                    entry.get_operation(),
+                    entry.format_time()
+                );
+            })
+        })
+        .map_err(|e| {
+            cprintln!(Color::Red, " * {}", e);
+            -1 // return err
+        })
+}
+
+// print an operation in the journal, with changed links
+fn print_journal_entry(index: usize, entry: &JournalEntry) {
+    cprint!(Color::Green, "{:>3}  ", index);
+    println!(
+        "{}  {:<8}  {}",
+        entry.format_time(),
+        entry.get_operation(),
+        entry
+            .get_changes()
+            .iter()
+            .map(|change| format!("{} ({})", change.get_name(), change.action()))
+            .collect::<Vec<String>>()
+            .join(", ")
+    );
+}
+
+// find problematic links, then fix them if '--fix' is given
+fn doctor_cmds(wslcmd_list: &mut WslCmdList, opts: &HashMap<&str, &str>) -> Result<(), i32> {
+    let do_fix = opts.contains_key("--fix");
+
+    // diagnose, and fix each issue if needed
+    let results: Vec<(LinkIssue, Option<Result<(), String>>)> = wslcmd_list
+        .diagnose()
+        .into_iter()
+        .map(|issue| {
+            let fix_result =
+                do_fix.then(|| wslcmd_list.fix_issue(&issue).map_err(|e| e.to_string()));
+            (issue, fix_result)
+        })
+        .collect();
+
+    // print report
+    match opts.contains_key("--json") {
+        // json report
+        true => println!(
+            "{}",
+            serde_json::json!({
+                "issues": results
+                    .iter()
+                    .map(|(issue, fix_result)| serde_json::json!({
+                        "kind": issue.get_kind(),
+                        "description": issue.get_kind().description(),
+                        "path": issue.get_path(),
+                        "target": issue.get_target(),
+                        "fixable": issue.get_kind().is_fixable(),
+                        "fixed": fix_result.as_ref().map(Result::is_ok),
+                        "error": fix_result.as_ref().and_then(|r| r.as_ref().err()),
+                    }))
+                    .collect::<Vec<_>>()
+            })
+        ),
+
+        // human-readable report
+        false => {
+            results.iter().for_each(|(issue, fix_result)| {
+                cprintln!(
+                    Color::Red,
+                    " * [{}] {}: {}",
+                    issue.get_kind().name(),
+                    issue.get_path().wcpath_filename().unwrap_or_default(),
+                    issue.get_kind().description()
+                );
+                match fix_result {
+                    Some(Ok(())) => cprintln!(Color::Green, "   - Fixed"),
+                    Some(Err(e)) => cprintln!(Color::Red, "   - Failed to fix: {}", e),
+                    None => (),
+                }
+            });
+
+            match results.iter().filter(|(_, r)| r.is_none()).count() {
+                _ if results.is_empty() => cprintln!(Color::Green, " - No problem found"),
+                0 => (),
+                n => cprintln!(Color::Yellow, " - Run with '--fix' to fix {} problem(s)", n),
+            }
+        }
+    }
+
+    // Ok only if no remaining issue
+    results
+        .iter()
+        .all(|(_, fix_result)| matches!(fix_result, Some(Ok(()))))
+        .then_some(())
+        .ok_or(-1)
+}
+
+// build cmdname from command, user, and distribution
+//   Ex) ("git", None, Some("debian")) -> "git!!debian"
+fn cmdname_for(cmd: &str, user: Option<&str>, dist: Option<&str>) -> String {
+    match (user, dist) {
+        (_, Some(dist)) => format!(
+            "{1}{0}{2}{0}{3}",
+            CMDNAME_DELIM,
+            cmd,
+            user.unwrap_or_default(),
+            dist
+        ),
+        (Some(user), None) => format!("{1}{0}{2}", CMDNAME_DELIM, cmd, user),
+        (None, None) => cmd.to_string(),
+    }
+}
+
+// get namespace prefix of the link, recorded in the manifest
+fn prefix_of(wslcmd_list: &mut WslCmdList, linkname: &str) -> Option<String> {
+    wslcmd_list
+        .get_link_meta(linkname)
+        .and_then(|meta| meta.get_prefix().clone())
+}
+
+// ask user to confirm on terminal, and return true if answered yes
+fn confirm(msg: &str) -> bool {
+    cprint!(Color::Yellow, "{} [y/N] ", msg);
+
+    let mut answer = String::new();
+    std::io::stdin()
+        .read_line(&mut answer)
+        .is_ok_and(|_| ["y", "yes"].contains(&answer.trim().to_lowercase().as_str()))
+}
+
+/// Options which take a value (Ex: '--prefix w-')
+const OPTS_WITH_VALUE: &[&str] = &[
+    "--prefix",
+    "--desc",
+    "--user",
+    "--dist",
+    "--include",
+    "--exclude",
+    "--mode",
+    "--shim",
+    "--dir",
+    "--from",
+    "--tag",
+];
+
+/// Options which take no value
+const OPTS_FLAG: &[&str] = &[
+    "--yes",
+    "--force",
+    "--no-verify",
+    "--fix",
+    "--check",
+    "--json",
+    "--dry-run",
+    "--sync",
+    "--keep-going",
+    "--regex",
+    "--long",
+    "--one-per-line",
+    "--purge",
+    "--with-detached",
+    "--no-detached",
+    "--detached-only",
+];
+
+/// Short options, as aliases of options which take no value
+const OPTS_SHORT: &[(&str, &str)] = &[("-1", "--one-per-line"), ("-l", "--long")];
+
+// split args into (options, operands)
+//   - options: '--opt' in OPTS_FLAG, or '--opt <value>' in OPTS_WITH_VALUE
+//   - short options: '-x' in OPTS_SHORT, stored as its long option
+//   - operands: other args, and all args after '--'
+// returns None if error (unknown option, option without value)
+fn split_opts(args: &[String]) -> Option<(HashMap<&str, &str>, Vec<&String>)> {
+    let mut opts = HashMap::new();
+    let mut operands = Vec::new();
+
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        let opt = OPTS_SHORT
+            .iter()
+            .find(|(short, _)| short == arg)
+            .map_or(arg.as_str(), |(_, long)| long);
+
+        match opt {
+            // all remaining args are operands
+            "--" => {
+                operands.extend(it.by_ref());
+            }
+            // option with value
+            s if OPTS_WITH_VALUE.contains(&s) => {
+                opts.insert(s, it.next()?.as_str());
+            }
+            // option without value
+            s if OPTS_FLAG.contains(&s) => {
+                opts.insert(s, "");
+            }
+            // unknown option
+            s if s.starts_with("--") => return None,
+            // operand
+            _ => operands.push(arg),
+        }
+    }
+
+    Some((opts, operands))
+}
+
+// write list of cmdnames to buf, wrapping with ' if contains whitespace
+fn write_cmdnames(buf: &mut termcolor::Buffer, cmdnames: &[&String]) -> std::io::Result<()> {
+    use std::io::Write;
+    use termcolor::{Color, ColorSpec, WriteColor};
+
+    cmdnames.iter().try_for_each(|s| {
+        // if current string contains ws, wrap with '
+        let (wrap_front, wrap_back) = match s.contains(char::is_whitespace) {
+            true => ("'", "'"), // wrapper front/back
+            false => ("", ""),  // no wrapper
+        };
+
+        // write list to buf
+        write!(buf, "{}", wrap_front)
+            .and_then(|_| buf.set_color(ColorSpec::new().set_fg(Some(Color::Green))))
+            .and_then(|_| write!(buf, "{}", s))
+            .and_then(|_| buf.set_color(ColorSpec::new().set_reset(true)))
+            .and_then(|_| write!(buf, "{}", wrap_back))
+            .and_then(|_| write!(buf, "\t"))
+    })
+}
+
 fn print_help(bin_name: &str) {
     let bin_name_blank = format!("{: ^1$}", " ", bin_name.len());
     print!(
         concat!(
-            "usage: {0} <operation> [<arg1> <arg2> ...]\n",
+            "usage: {0} <operation> [--dir <link-dir>] [<arg1> <arg2> ...]\n",
+            "\n",
+            "  --dir <link-dir>\n",
+            "\n",
+            "    Manage links in the given existing dir, instead of the dir of the binary\n",
+            "    (The manifest with namespace prefixes is also stored in the dir)\n",
             "\n",
             "  <operation>\n",
             "\n",
@@ -224,6 +1647,30 @@ fn print_help(bin_name: &str) {
             "        {1} new        \"                 \"          ...\n",
             "        {1} n          \"                 \"          ...\n",
             "\n",
+            "        {0} add --tag <tag1,tag2> <command-name-1> ...\n",
+            "          (Tag the links to group them, Ex: 'build')\n",
+            "\n",
+            "        {0} add --no-verify <command-name-1> ...\n",
+            "          (Link even if the command is not found in WSL)\n",
+            "\n",
+            "        {0} add --force <command-name-1> ...\n",
+            "          (Back up existing files with the same name, which are restored when unlinked)\n",
+            "\n",
+            "        {0} add --prefix <prefix> <command-name-1> ...\n",
+            "          (Link as '<prefix><command-name>', to avoid name conflicts)\n",
+            "\n",
+            "        {0} add --desc <description> <command-name-1> ...\n",
+            "          (Record description of the links to the manifest)\n",
+            "\n",
+            "        {0} add --mode <symlink|hardlink|copy> <command-name-1> ...\n",
+            "          (Use hardlinks or copies of the binary, if symlinks are not allowed)\n",
+            "\n",
+            "        {0} add --shim <cmd|ps1|both> <command-name-1> ...\n",
+            "          (Create '.cmd'/'.ps1' shim scripts instead of links)\n",
+            "\n",
+            "        {0} add [--no-detached|--detached-only] <command-name-1> ...\n",
+            "          (Create only '<command-name>.exe', or only detached '.<command-name>.exe')\n",
+            "\n",
             "    - Unlink existing commands\n",
             "\n",
             "        {0} del <command-name-1> (<command-name-2>) ...\n",
@@ -231,11 +1678,105 @@ fn print_help(bin_name: &str) {
             "        {1} rm         \"                 \"          ...\n",
             "        {1} r          \"                 \"          ...\n",
             "\n",
+            "        {0} del --prefix <prefix> <command-name-1> ...\n",
+            "\n",
+            "        {0} del [--regex] [--yes] <pattern-1> (<pattern-2>) ...\n",
+            "          (Unlink commands matching case-insensitive glob patterns (Ex: 'git*'), or regex with '--regex'.\n",
+            "           Confirm before unlinking more than one command, unless '--yes' is given)\n",
+            "\n",
+            "      * Commands are added/deleted all-or-nothing: if any of them fails,\n",
+            "        all changes are rolled back. Use '--keep-going' to skip failed ones.\n",
+            "\n",
             "    - List linked commands\n",
             "\n",
             "        {0} list\n",
             "        {1} ls\n",
             "        {1} l\n",
+            "\n",
+            "        {0} list [--regex] <pattern-1> (<pattern-2>) ...\n",
+            "          (List only commands matching glob patterns (Ex: '*!debian'), or regex)\n",
+            "\n",
+            "        {0} list -l | --long\n",
+            "          (List in a table: decoded command, user, dist, kind, detached link, health, tags and description)\n",
+            "\n",
+            "        {0} list -1 | --one-per-line\n",
+            "        {0} list --json\n",
+            "          (List for scripts: names one per line without color, or details as JSON)\n",
+            "\n",
+            "        {0} list --check\n",
+            "          (Also check if other commands on PATH have the same name as links)\n",
+            "\n",
+            "    - Select linked commands by tags, for del, list and convert\n",
+            "\n",
+            "        {0} <operation> --tag <tag1,tag2> (<command-name-or-pattern-1> ...)\n",
+            "          (Ex: '{0} del --tag experiments', '{0} ls --tag build')\n",
+            "\n",
+            "    - Add or remove tags of linked commands\n",
+            "\n",
+            "        {0} tag <tag1,tag2> <command-name-or-pattern-1> ...\n",
+            "        {0} untag <tag1,tag2> <command-name-or-pattern-1> ...\n",
+            "\n",
+            "    - Convert linked commands to another detached mode (names or patterns)\n",
+            "\n",
+            "        {0} convert <--with-detached|--no-detached|--detached-only> <command-name-1> ...\n",
+            "\n",
+            "    - Rename or copy a linked command (metadata is kept)\n",
+            "\n",
+            "        {0} rename <command-name> <new-command-name>\n",
+            "        {1} mv         \"               \"\n",
+            "        {0} copy <command-name> <new-command-name>\n",
+            "        {1} cp         \"               \"\n",
+            "\n",
+            "    - Run a WSL command through the binary (Used by shim scripts)\n",
+            "\n",
+            "        {0} run [--dir <link-dir>] <command-name> (<command-arg-1> ...)\n",
+            "\n",
+            "    - Discover and link executables on WSL PATH (or on given WSL dirs)\n",
+            "\n",
+            "        {0} discover [<options>] (<wsl-dir-1> <wsl-dir-2> ...)\n",
+            "\n",
+            "          --user <user>             WSL user to find commands and link\n",
+            "          --dist <dist>             WSL distribution to find commands and link\n",
+            "          --include <glob1,glob2>   Link only commands matching patterns\n",
+            "          --exclude <glob1,glob2>   Do not link commands matching patterns\n",
+            "          --prefix <prefix>         Link with namespace prefix\n",
+            "          --mode <mode>             Link with hardlinks or copies (Default: symlink)\n",
+            "          --yes                     Link without confirmation\n",
+            "\n",
+            "    - Search commands on WSL PATH, with descriptions (substring, glob or regex)\n",
+            "\n",
+            "        {0} search [--user <user>] [--dist <dist>] [--regex] <pattern>\n",
+            "          (Ex: '{0} search json', '{0} search \"py*\"')\n",
+            "\n",
+            "    - Export links to a link set file (JSON), or import links from it\n",
+            "\n",
+            "        {0} export > <file>\n",
+            "        {0} import [--dry-run] [--sync] [--yes] <file>\n",
+            "\n",
+            "          --dry-run                 Only show changes, without applying\n",
+            "          --sync                    Unlink commands not in the file\n",
+            "          --yes                     Unlink without confirmation\n",
+            "\n",
+            "    - Retarget links of another binary, after renaming or moving the binary\n",
+            "\n",
+            "        {0} retarget --from <old-binary-name>\n",
+            "          (Ex: '{0} retarget --from wslcmd.exe', after renaming it to '{0}')\n",
+            "\n",
+            "    - Find (and fix) broken, orphaned and foreign links\n",
+            "\n",
+            "        {0} doctor [--fix] [--json]\n",
+            "\n",
+            "    - Install the binary and all links to another dir, or uninstall them\n",
+            "\n",
+            "        {0} install <dir>\n",
+            "        {0} uninstall [--purge] [--yes]\n",
+            "\n",
+            "          --purge                   Also remove config files and the binary\n",
+            "\n",
+            "    - Show history of operations, or undo the latest N operations (Default: 1)\n",
+            "\n",
+            "        {0} history\n",
+            "        {0} undo [--yes] [N]\n",
             "\n"
         ),
         bin_name, bin_name_blank